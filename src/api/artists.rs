@@ -0,0 +1,104 @@
+use reqwest::Method;
+use rspotify_model::{CursorBasedPage, CursorPageFullArtists, FullArtist, FullArtists, FullTrack, FullTracks, Page, SimplifiedAlbum};
+
+use super::{error::SpotifyAPIError, utils, SpotifyAPI};
+
+// Wrappers for the /artists endpoints, and following them from /me/following.
+impl SpotifyAPI {
+    pub async fn get_artist(&self, artist_id: String) -> Result<FullArtist, SpotifyAPIError> {
+        self.request::<FullArtist>(Method::GET, format!("/artists/{}", artist_id), None).await
+    }
+
+    /// `market` is an ISO 3166-1 alpha-2 country code, Spotify
+    /// picks one from the account when it's left out.
+    pub async fn get_artist_top_tracks(&self, artist_id: String, market: Option<String>) -> Result<Vec<FullTrack>, SpotifyAPIError> {
+        let market = market.unwrap_or("from_token".to_string());
+
+        let query = utils::create_hashmap(&[
+            ("market", Some(&market))
+        ]);
+
+        self.request::<FullTracks>(Method::GET, format!("/artists/{}/top-tracks", artist_id), Some(query))
+            .await
+            .map(|r| r.tracks)
+    }
+
+    /// `include_groups` is a comma separated list of album, single, compilation and appears_on.
+    pub async fn get_artist_albums(&self, artist_id: String, include_groups: String, limit: Option<u32>) -> Result<Page<SimplifiedAlbum>, SpotifyAPIError> {
+        let limit = limit.map(|x| x.to_string());
+
+        let query = utils::create_hashmap(&[
+            ("include_groups", Some(&include_groups)),
+            ("limit", limit.as_ref())
+        ]);
+
+        self.request::<Page<SimplifiedAlbum>>(Method::GET, format!("/artists/{}/albums", artist_id), Some(query)).await
+    }
+
+    pub async fn get_related_artists(&self, artist_id: String) -> Result<Vec<FullArtist>, SpotifyAPIError> {
+        self.request::<FullArtists>(Method::GET, format!("/artists/{}/related-artists", artist_id), None)
+            .await
+            .map(|r| r.artists)
+    }
+
+    pub async fn is_following_artist(&self, artist_id: String) -> Result<bool, SpotifyAPIError> {
+        let kind = "artist".to_string();
+
+        let query = utils::create_hashmap(&[
+            ("type", Some(&kind)),
+            ("ids", Some(&artist_id))
+        ]);
+
+        self.request::<Vec<bool>>(Method::GET, "/me/following/contains".to_string(), Some(query))
+            .await
+            .map(|r| r.first().copied().unwrap_or(false))
+    }
+
+    /// Artists the user follows, paged by the ID of the last artist
+    /// on the previous page rather than an offset.
+    pub async fn get_followed_artists(&self, limit: Option<u32>, after: Option<String>) -> Result<CursorBasedPage<FullArtist>, SpotifyAPIError> {
+        let kind = "artist".to_string();
+        let limit = limit.map(|x| x.to_string());
+
+        let query = utils::create_hashmap(&[
+            ("type", Some(&kind)),
+            ("limit", limit.as_ref()),
+            ("after", after.as_ref())
+        ]);
+
+        self.request::<CursorPageFullArtists>(Method::GET, "/me/following".to_string(), Some(query))
+            .await
+            .map(|r| r.artists)
+    }
+
+    pub async fn get_all_followed_artists(&self) -> Result<Vec<FullArtist>, SpotifyAPIError> {
+        let mut artists = Vec::new();
+        let mut after = None;
+
+        loop {
+            let page = self.get_followed_artists(Some(50), after).await?;
+
+            after = page.cursors.and_then(|c| c.after);
+            artists.extend(page.items);
+
+            if page.next.is_none() || after.is_none() {
+                break;
+            }
+        }
+
+        Ok(artists)
+    }
+
+    pub async fn set_following_artist(&self, artist_id: String, follow: bool) -> Result<(), SpotifyAPIError> {
+        let kind = "artist".to_string();
+
+        let query = utils::create_hashmap(&[
+            ("type", Some(&kind)),
+            ("ids", Some(&artist_id))
+        ]);
+
+        let method = if follow { Method::PUT } else { Method::DELETE };
+
+        self.request::<()>(method, "/me/following".to_string(), Some(query)).await
+    }
+}
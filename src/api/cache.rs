@@ -0,0 +1,383 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use directories::ProjectDirs;
+use reqwest::header::{HeaderMap, CACHE_CONTROL, ETAG};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::constants::UI_APP_NAME;
+
+use super::constants::{SPOTIFY_API_CACHE_MAX_BYTES, SPOTIFY_API_UNCACHED_ROUTE_PREFIXES, SPOTIFY_API_UNCACHED_ROUTE_SUFFIXES};
+
+/// Whether GET responses from this route are worth keeping at all.
+pub fn is_cacheable(route: &str) -> bool {
+    !SPOTIFY_API_UNCACHED_ROUTE_PREFIXES.iter().any(|prefix| route.starts_with(prefix)) &&
+    !SPOTIFY_API_UNCACHED_ROUTE_SUFFIXES.iter().any(|suffix| route.ends_with(suffix))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpotifyAPICacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+
+    // Unix time after which we have to check back with Spotify first.
+    pub expires_at: u64,
+
+    pub body: String,
+}
+
+impl SpotifyAPICacheEntry {
+    pub fn is_fresh(&self) -> bool {
+        now_secs() < self.expires_at
+    }
+}
+
+/// What the response's Cache-Control lets us do with it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SpotifyAPICachePolicy {
+    pub no_store: bool,
+    pub max_age: u64,
+}
+
+impl SpotifyAPICachePolicy {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut policy = SpotifyAPICachePolicy::default();
+
+        let directives = headers
+            .get_all(CACHE_CONTROL)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|d| d.trim().to_lowercase());
+
+        for directive in directives {
+            match directive.split_once('=') {
+                Some(("max-age", age)) => policy.max_age = age.trim_matches('"').parse().unwrap_or(0),
+                None if directive == "no-store" => policy.no_store = true,
+                // We still keep no-cache responses, they just always get revalidated.
+                None if directive == "no-cache" => policy.max_age = 0,
+                _ => {}
+            }
+        }
+
+        policy
+    }
+
+    pub fn expires_at(&self) -> u64 {
+        now_secs() + self.max_age
+    }
+}
+
+pub fn parse_etag(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+}
+
+/// Keeps GET responses on disk, so we can revalidate them with Spotify
+/// instead of downloading them again, and still show something offline.
+/// Least recently used entries are evicted past the size limit.
+#[derive(Clone)]
+pub struct SpotifyAPICache {
+    dir: Option<PathBuf>,
+
+    max_bytes: Arc<AtomicU64>,
+    size_bytes: Arc<AtomicU64>,
+
+    // Responses like /me differ per account, keep each account's apart.
+    namespace: Arc<Mutex<Option<String>>>,
+
+    lock: Arc<Mutex<()>>,
+}
+
+impl Default for SpotifyAPICache {
+    fn default() -> Self {
+        // Linux: ~/.cache/spottyfi/api
+        let dir = ProjectDirs::from("com", UI_APP_NAME, UI_APP_NAME)
+            .map(|d| d.cache_dir().join("api"));
+
+        if dir.is_none() {
+            warn!("No suitable directory found to cache API responses.");
+        }
+
+        Self::new(dir, SPOTIFY_API_CACHE_MAX_BYTES)
+    }
+}
+
+impl SpotifyAPICache {
+    pub fn new(dir: Option<PathBuf>, max_bytes: u64) -> Self {
+        let dir = dir.filter(|dir| match fs::create_dir_all(dir) {
+            Ok(_) => true,
+            Err(err) => {
+                error!("Failed to create API cache dir {}: {:#?}", dir.display(), err);
+
+                false
+            }
+        });
+
+        let cache = SpotifyAPICache {
+            dir,
+
+            max_bytes: Arc::new(AtomicU64::new(max_bytes)),
+            size_bytes: Arc::new(AtomicU64::new(0)),
+
+            namespace: Arc::new(Mutex::new(None)),
+
+            lock: Arc::new(Mutex::new(())),
+        };
+
+        cache.size_bytes.store(
+            cache.entries().iter().map(|(_, size, _)| size).sum(),
+            Ordering::Relaxed
+        );
+
+        cache
+    }
+
+    pub fn set_namespace(&self, namespace: Option<String>) {
+        *self.namespace.lock().unwrap() = namespace;
+    }
+
+    pub fn set_max_bytes(&self, max_bytes: u64) {
+        self.max_bytes.store(max_bytes, Ordering::Relaxed);
+
+        let cache = self.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let _lock = cache.lock.lock().unwrap();
+            cache.evict();
+        });
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn size_bytes(&self) -> u64 {
+        self.size_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Key for a request, the query is sorted so the same request always maps to the same entry.
+    pub fn key(&self, url: &Url, query: &HashMap<&str, Option<&String>>) -> String {
+        let mut pairs = query
+            .iter()
+            .filter_map(|(k, v)| v.map(|v| (*k, v.as_str())))
+            .collect::<Vec<(&str, &str)>>();
+
+        pairs.sort();
+
+        let mut url = url.clone();
+        url.query_pairs_mut().extend_pairs(pairs);
+
+        let namespace = self.namespace.lock().unwrap().clone().unwrap_or_default();
+
+        hex::encode(Sha1::digest(format!("{} {}", namespace, url).as_bytes()))
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.json", key)))
+    }
+
+    pub async fn get(&self, key: &str) -> Option<SpotifyAPICacheEntry> {
+        let path = self.path(key)?;
+        let cache = self.clone();
+
+        tokio::task::spawn_blocking(move || cache.read(&path))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub async fn put(&self, key: &str, entry: &SpotifyAPICacheEntry) {
+        let Some(path) = self.path(key) else {
+            return;
+        };
+
+        let Ok(contents) = serde_json::to_string(entry) else {
+            return;
+        };
+
+        let cache = self.clone();
+
+        tokio::task::spawn_blocking(move || cache.write(&path, &contents))
+            .await
+            .ok();
+    }
+
+    /// Marks an entry as fresh again after Spotify told us it hasn't changed.
+    pub async fn revalidate(&self, key: &str, mut entry: SpotifyAPICacheEntry, expires_at: u64) -> SpotifyAPICacheEntry {
+        entry.expires_at = expires_at;
+
+        self.put(key, &entry).await;
+
+        entry
+    }
+
+    // Blocking, run it off the async threads.
+    fn read(&self, path: &Path) -> Option<SpotifyAPICacheEntry> {
+        let _lock = self.lock.lock().unwrap();
+
+        let entry = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<SpotifyAPICacheEntry>(&contents).ok())?;
+
+        // Bump the modified time, it's what eviction goes by.
+        File::options()
+            .append(true)
+            .open(path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+            .ok();
+
+        Some(entry)
+    }
+
+    // Blocking, run it off the async threads.
+    fn write(&self, path: &Path, contents: &str) {
+        let _lock = self.lock.lock().unwrap();
+
+        let previous_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+        match fs::write(path, contents) {
+            Ok(_) => {
+                self.size_bytes.fetch_sub(previous_size.min(self.size_bytes()), Ordering::Relaxed);
+                self.size_bytes.fetch_add(contents.len() as u64, Ordering::Relaxed);
+
+                self.evict();
+            },
+            Err(err) => warn!("Failed to write API cache entry {}: {:#?}", path.display(), err),
+        }
+    }
+
+    // Every entry with its size and when it was last used.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Some(dir) = &self.dir else {
+            return vec![];
+        };
+
+        fs::read_dir(dir)
+            .map(|entries| entries
+                .flatten()
+                .filter_map(|entry| {
+                    let metadata = entry.metadata().ok()?;
+
+                    Some((entry.path(), metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)))
+                })
+                .collect())
+            .unwrap_or_default()
+    }
+
+    // Expects the caller to hold the lock.
+    fn evict(&self) {
+        let max_bytes = self.max_bytes();
+
+        if self.size_bytes() <= max_bytes {
+            return;
+        }
+
+        let mut entries = self.entries();
+        entries.sort_by_key(|(_, _, used)| *used);
+
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        let mut evicted = 0;
+
+        for (path, entry_size, _) in entries {
+            if size <= max_bytes {
+                break;
+            }
+
+            if fs::remove_file(&path).is_ok() {
+                size -= entry_size;
+                evicted += 1;
+            }
+        }
+
+        self.size_bytes.store(size, Ordering::Relaxed);
+
+        info!("Evicted {} entries from the API cache.", evicted);
+    }
+
+    pub fn clear(&self) {
+        let cache = self.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let _lock = cache.lock.lock().unwrap();
+
+            for (path, _, _) in cache.entries() {
+                fs::remove_file(path).ok();
+            }
+
+            cache.size_bytes.store(0, Ordering::Relaxed);
+
+            info!("Cleared the API cache.");
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_volatile_routes() {
+        assert!(!is_cacheable("/me/player"));
+        assert!(!is_cacheable("/me/player/devices"));
+        assert!(!is_cacheable("/me/player/recently-played"));
+        assert!(!is_cacheable("/me/tracks/contains"));
+        assert!(!is_cacheable("/me/following/contains"));
+
+        assert!(is_cacheable("/me"));
+        assert!(is_cacheable("/me/tracks"));
+        assert!(is_cacheable("/me/playlists"));
+        assert!(is_cacheable("/albums/4aawyAB9vmqN3uQ7FjRGTy"));
+    }
+
+    fn entry(body: &str) -> SpotifyAPICacheEntry {
+        SpotifyAPICacheEntry {
+            url: "https://api.spotify.com/v1/me".to_string(),
+            etag: None,
+            expires_at: now_secs() + 60,
+            body: body.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SpotifyAPICache::new(Some(dir.path().to_path_buf()), 1000);
+
+        let body = "x".repeat(400);
+
+        cache.put("a", &entry(&body)).await;
+        cache.put("b", &entry(&body)).await;
+
+        // Reading "a" makes "b" the least recently used.
+        assert_eq!(cache.get("a").await.unwrap().body, body);
+
+        cache.put("c", &entry(&body)).await;
+
+        assert!(cache.get("a").await.is_some());
+        assert!(cache.get("b").await.is_none());
+        assert!(cache.get("c").await.is_some());
+        assert!(cache.size_bytes() <= 1000);
+    }
+}
@@ -1,5 +1,5 @@
 // Credit to https://github.com/jpochyla/psst/blob/a492c33e5d314b40ef7c6ed6634e4fbe4857a8ad/psst-core/src/session/access_token.rs#L10
-pub const SPOTIFY_SCOPES: &str = "streaming,user-read-email,user-read-private,playlist-read-private,playlist-read-collaborative,playlist-modify-public,playlist-modify-private,user-follow-modify,user-follow-read,user-library-read,user-library-modify,user-top-read,user-read-recently-played";
+pub const SPOTIFY_SCOPES: &str = "streaming,user-read-email,user-read-private,playlist-read-private,playlist-read-collaborative,playlist-modify-public,playlist-modify-private,user-follow-modify,user-follow-read,user-library-read,user-library-modify,user-top-read,user-read-recently-played,user-read-playback-state,user-modify-playback-state";
 pub const SPOTIFY_CLIENT_ID: &str = "65b708073fc0480ea92a077233ca87bd";
 pub const SPOTIFY_DEVICE_NAME: &str = "Spottyfi";
 
@@ -8,6 +8,37 @@ pub const SPOTIFY_API_URL: &str = "https://api.spotify.com/v1/";
 pub const SPOTIFY_OAUTH_AUTHORISE_URL: &str = "https://accounts.spotify.com/authorize";
 pub const SPOTIFY_OAUTH_TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
 
+// Point the app at another Web API, e.g. the mock server or a proxy.
+pub const SPOTIFY_API_URL_ENV: &str = "SPOTTYFI_API_URL";
+pub const SPOTIFY_OAUTH_AUTHORISE_URL_ENV: &str = "SPOTTYFI_OAUTH_AUTHORISE_URL";
+pub const SPOTIFY_OAUTH_TOKEN_URL_ENV: &str = "SPOTTYFI_OAUTH_TOKEN_URL";
+
+pub const SPOTIFY_API_MAX_CONCURRENT_REQUESTS: usize = 4;
+pub const SPOTIFY_API_MAX_RETRIES: u32 = 4;
+pub const SPOTIFY_API_BACKOFF_BASE_MS: u64 = 500;
+pub const SPOTIFY_API_BACKOFF_MAX_MS: u64 = 30_000;
+
+// Refresh access tokens this long before Spotify says they expire.
+pub const SPOTIFY_TOKEN_REFRESH_MARGIN_SECS: u64 = 60;
+
+// Give up waiting for the browser to redirect back to us after this long.
+pub const SPOTIFY_OAUTH_CALLBACK_TIMEOUT_SECS: u64 = 300;
+// Drop callback connections that haven't sent their request by then.
+pub const SPOTIFY_OAUTH_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+// Where Spotify sends the browser back to after logging in, the ports
+// after the default one are tried in turn when it's already taken.
+pub const SPOTIFY_OAUTH_REDIRECT_HOST: &str = "127.0.0.1";
+pub const SPOTIFY_OAUTH_REDIRECT_PORT: u16 = 8888;
+pub const SPOTIFY_OAUTH_REDIRECT_PORT_FALLBACKS: u16 = 10;
+
+pub const SPOTIFY_API_CACHE_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+// What's playing and what's saved change behind our back, a cached
+// answer to these would only ever be stale.
+pub const SPOTIFY_API_UNCACHED_ROUTE_PREFIXES: [&str; 1] = ["/me/player"];
+pub const SPOTIFY_API_UNCACHED_ROUTE_SUFFIXES: [&str; 1] = ["/contains"];
+
 pub const SPOTIFY_ACCOUNTS_URL: &str = "https://www.spotify.com/account/overview/";
 
 pub const SPOTIFY_CATEGORY_ID_MADE_FOR_YOU: &str = "0JQ5DAt0tbjZptfcdMSKl3";
@@ -11,5 +11,9 @@ pub enum SpotifyAPIDataError {
 
 #[derive(Debug, Clone, Default)]
 pub struct SpotifyAPIData {
-    pub profile: Option<PrivateUser>
+    pub profile: Option<PrivateUser>,
+
+    // Everything in the user's library, in the order Spotify has them.
+    // The Web API doesn't expose playlist folders, so this is flat.
+    pub playlists: Option<Vec<SimplifiedPlaylist>>
 }
\ No newline at end of file
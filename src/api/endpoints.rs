@@ -0,0 +1,81 @@
+use std::env;
+
+use tracing::{info, warn};
+use url::Url;
+
+use super::constants::{
+    SPOTIFY_API_URL, SPOTIFY_API_URL_ENV, SPOTIFY_OAUTH_AUTHORISE_URL, SPOTIFY_OAUTH_AUTHORISE_URL_ENV,
+    SPOTIFY_OAUTH_TOKEN_URL, SPOTIFY_OAUTH_TOKEN_URL_ENV,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotifyAPIEndpoints {
+    // Routes are joined onto this, so it needs a trailing slash.
+    pub api_url: Url,
+
+    pub oauth_authorise_url: Url,
+    pub oauth_token_url: Url,
+}
+
+impl Default for SpotifyAPIEndpoints {
+    fn default() -> Self {
+        SpotifyAPIEndpoints {
+            api_url: Url::parse(SPOTIFY_API_URL).unwrap(),
+
+            oauth_authorise_url: Url::parse(SPOTIFY_OAUTH_AUTHORISE_URL).unwrap(),
+            oauth_token_url: Url::parse(SPOTIFY_OAUTH_TOKEN_URL).unwrap(),
+        }
+    }
+}
+
+fn url_from_env(key: &str, default: Url) -> Url {
+    match env::var(key) {
+        Ok(value) if !value.trim().is_empty() => match Url::parse(value.trim()) {
+            Ok(url) => {
+                info!("Using {} from {}.", url, key);
+
+                url
+            },
+            Err(err) => {
+                warn!("Ignoring invalid URL in {}: {}", key, err);
+
+                default
+            }
+        },
+        _ => default,
+    }
+}
+
+impl SpotifyAPIEndpoints {
+    /// The real Spotify endpoints, with any overrides from the environment.
+    pub fn from_env() -> Self {
+        let default = SpotifyAPIEndpoints::default();
+
+        SpotifyAPIEndpoints {
+            api_url: with_trailing_slash(url_from_env(SPOTIFY_API_URL_ENV, default.api_url)),
+
+            oauth_authorise_url: url_from_env(SPOTIFY_OAUTH_AUTHORISE_URL_ENV, default.oauth_authorise_url),
+            oauth_token_url: url_from_env(SPOTIFY_OAUTH_TOKEN_URL_ENV, default.oauth_token_url),
+        }
+    }
+
+    /// Endpoints for a server that serves both the Web API and accounts
+    /// routes from one origin, laid out like Spotify's own.
+    pub fn from_base_url(base_url: &Url) -> Self {
+        SpotifyAPIEndpoints {
+            api_url: base_url.join("v1/").unwrap(),
+
+            oauth_authorise_url: base_url.join("authorize").unwrap(),
+            oauth_token_url: base_url.join("api/token").unwrap(),
+        }
+    }
+}
+
+fn with_trailing_slash(mut url: Url) -> Url {
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+
+    url
+}
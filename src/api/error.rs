@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use super::{data::SpotifyAPIDataError, providers::oauth2::SpotifyAPIOAuthError};
 
@@ -12,4 +12,10 @@ pub enum SpotifyAPIError {
     DataError(SpotifyAPIDataError),
     RequestError(Arc<reqwest::Error>),
     SerdeError(Arc<serde_json::Error>),
+
+    // Spotify kept rate limiting us, even after waiting as long as it asked.
+    RateLimited(Duration),
+
+    // Asked for a cached response only, and there wasn't one.
+    NotCached,
 }
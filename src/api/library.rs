@@ -0,0 +1,87 @@
+use reqwest::Method;
+use rspotify_model::{Page, SavedAlbum, SavedTrack};
+
+use super::{error::SpotifyAPIError, utils, SpotifyAPI};
+
+// Wrappers for the user's saved tracks under /me/tracks, aka Liked Songs,
+// and their saved albums under /me/albums.
+impl SpotifyAPI {
+    pub async fn get_saved_tracks(&self, limit: Option<u32>, offset: Option<u32>) -> Result<Page<SavedTrack>, SpotifyAPIError> {
+        let limit = limit.map(|x| x.to_string());
+        let offset = offset.map(|x| x.to_string());
+
+        let query = utils::create_hashmap(&[
+            ("limit", limit.as_ref()),
+            ("offset", offset.as_ref())
+        ]);
+
+        self.request::<Page<SavedTrack>>(Method::GET, "/me/tracks".to_string(), Some(query)).await
+    }
+
+    /// Saves or removes `track_ids` from Liked Songs depending on `saved`.
+    pub async fn set_saved_tracks(&self, track_ids: Vec<String>, saved: bool) -> Result<(), SpotifyAPIError> {
+        let method = if saved { Method::PUT } else { Method::DELETE };
+
+        // Spotify only takes 50 IDs at a time.
+        for chunk in track_ids.chunks(50) {
+            let ids = chunk.join(",");
+
+            let query = utils::create_hashmap(&[
+                ("ids", Some(&ids))
+            ]);
+
+            self.request::<()>(method.clone(), "/me/tracks".to_string(), Some(query)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether each of `track_ids` is in Liked Songs, in the same order.
+    pub async fn contains_saved_tracks(&self, track_ids: Vec<String>) -> Result<Vec<bool>, SpotifyAPIError> {
+        let mut saved = Vec::with_capacity(track_ids.len());
+
+        for chunk in track_ids.chunks(50) {
+            let ids = chunk.join(",");
+
+            let query = utils::create_hashmap(&[
+                ("ids", Some(&ids))
+            ]);
+
+            saved.extend(
+                self.request::<Vec<bool>>(Method::GET, "/me/tracks/contains".to_string(), Some(query)).await?
+            );
+        }
+
+        Ok(saved)
+    }
+
+    pub async fn get_saved_albums(&self, limit: Option<u32>, offset: Option<u32>) -> Result<Page<SavedAlbum>, SpotifyAPIError> {
+        let limit = limit.map(|x| x.to_string());
+        let offset = offset.map(|x| x.to_string());
+
+        let query = utils::create_hashmap(&[
+            ("limit", limit.as_ref()),
+            ("offset", offset.as_ref())
+        ]);
+
+        self.request::<Page<SavedAlbum>>(Method::GET, "/me/albums".to_string(), Some(query)).await
+    }
+
+    /// Saves or removes `album_ids` from the library depending on `saved`.
+    pub async fn set_saved_albums(&self, album_ids: Vec<String>, saved: bool) -> Result<(), SpotifyAPIError> {
+        let method = if saved { Method::PUT } else { Method::DELETE };
+
+        // Albums only go 20 at a time.
+        for chunk in album_ids.chunks(20) {
+            let ids = chunk.join(",");
+
+            let query = utils::create_hashmap(&[
+                ("ids", Some(&ids))
+            ]);
+
+            self.request::<()>(method.clone(), "/me/albums".to_string(), Some(query)).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,870 @@
+use std::{collections::HashMap, io, net::SocketAddr, sync::{Arc, Mutex}};
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tracing::{info, warn};
+use url::Url;
+
+use super::endpoints::SpotifyAPIEndpoints;
+
+pub const MOCK_ACCESS_TOKEN: &str = "mock-access-token";
+pub const MOCK_REFRESH_TOKEN: &str = "mock-refresh-token";
+pub const MOCK_AUTHORIZATION_CODE: &str = "mock-authorization-code";
+pub const MOCK_USER_ID: &str = "mockuser";
+
+// Enough of each to need more than one page.
+pub const MOCK_USER_PLAYLISTS: usize = 5;
+pub const MOCK_SEARCH_TRACKS: usize = 30;
+pub const MOCK_ALBUM_TRACKS: usize = 25;
+
+// Set this in builds with the mock-api feature to run the app against the mock server.
+pub const MOCK_API_ENV: &str = "SPOTTYFI_MOCK_API";
+
+/// A tiny stand-in for the Spotify Web API and accounts service,
+/// serving canned responses on a random local port so the API client
+/// can be exercised without touching the network. Only built for tests,
+/// and with the mock-api feature.
+pub struct SpotifyAPIMockServer {
+    pub addr: SocketAddr,
+
+    // Everything that's been asked of us, so tests can check what was sent.
+    received: Arc<Mutex<Vec<MockRequest>>>,
+
+    // Errors to answer the next matching requests with, see `fail_next`.
+    failures: Arc<Mutex<Vec<MockFailure>>>,
+
+    task: JoinHandle<()>,
+}
+
+impl SpotifyAPIMockServer {
+    /// Must be called from within a tokio runtime.
+    pub fn start() -> io::Result<Self> {
+        let std_listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        std_listener.set_nonblocking(true)?;
+
+        let addr = std_listener.local_addr()?;
+        let listener = TcpListener::from_std(std_listener)?;
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_task = Arc::clone(&received);
+
+        let failures = Arc::new(Mutex::new(Vec::new()));
+        let failures_task = Arc::clone(&failures);
+
+        let task = tokio::task::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let received = Arc::clone(&received_task);
+                let failures = Arc::clone(&failures_task);
+
+                tokio::task::spawn(async move {
+                    if let Err(err) = handle_connection(stream, received, failures).await {
+                        warn!("Mock API connection failed: {}", err);
+                    }
+                });
+            }
+        });
+
+        info!("Mock Spotify API listening on http://{}/", addr);
+
+        Ok(SpotifyAPIMockServer { addr, received, failures, task })
+    }
+
+    pub fn base_url(&self) -> Url {
+        Url::parse(&format!("http://{}/", self.addr)).unwrap()
+    }
+
+    pub fn endpoints(&self) -> SpotifyAPIEndpoints {
+        SpotifyAPIEndpoints::from_base_url(&self.base_url())
+    }
+
+    /// Answers the next `method` request to `path` with `status` instead,
+    /// telling the client to wait `retry_after` seconds if given.
+    pub fn fail_next(&self, method: &str, path: &str, status: &'static str, retry_after: Option<u64>) {
+        self.failures.lock().unwrap().push(MockFailure {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            retry_after,
+        });
+    }
+
+    /// Requests received for `method` and `path`, oldest first.
+    pub fn received(&self, method: &str, path: &str) -> Vec<MockRequest> {
+        self.received
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == method && r.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for SpotifyAPIMockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+struct MockFailure {
+    method: String,
+    path: String,
+    status: &'static str,
+    retry_after: Option<u64>,
+}
+
+struct MockResponse {
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Option<Value>,
+}
+
+impl MockResponse {
+    fn json(status: &'static str, body: Value) -> Self {
+        MockResponse {
+            status,
+            headers: vec![],
+            body: Some(body),
+        }
+    }
+
+    fn no_content() -> Self {
+        MockResponse {
+            status: "204 No Content",
+            headers: vec![],
+            body: None,
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        let code = status.split(' ').next().and_then(|c| c.parse::<u16>().ok()).unwrap_or(500);
+
+        MockResponse::json(status, json!({
+            "error": {
+                "status": code,
+                "message": message
+            }
+        }))
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    received: Arc<Mutex<Vec<MockRequest>>>,
+    failures: Arc<Mutex<Vec<MockFailure>>>
+) -> io::Result<()> {
+    let mut reader = BufReader::new(&mut stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    let mut headers = HashMap::new();
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET").to_string();
+    let target = parts.next().unwrap_or("/");
+
+    let url = Url::parse(&format!("http://localhost{}", target))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let request = MockRequest {
+        method,
+        path: url.path().to_string(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    };
+
+    let failure = {
+        let mut failures = failures.lock().unwrap();
+
+        failures
+            .iter()
+            .position(|f| f.method == request.method && f.path == request.path)
+            .map(|index| failures.remove(index))
+    };
+
+    let response = match failure {
+        Some(failure) => {
+            let mut response = MockResponse::error(failure.status, "Mock failure");
+
+            if let Some(retry_after) = failure.retry_after {
+                response.headers.push(("Retry-After", retry_after.to_string()));
+            }
+
+            response
+        },
+        None => route(&request),
+    };
+
+    received.lock().unwrap().push(request);
+
+    let body = response.body
+        .map(|b| b.to_string())
+        .unwrap_or_default();
+
+    let mut message = format!("HTTP/1.1 {}\r\n", response.status);
+
+    for (key, value) in response.headers {
+        message.push_str(&format!("{}: {}\r\n", key, value));
+    }
+
+    message.push_str("Content-Type: application/json\r\n");
+    message.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    message.push_str("Connection: close\r\n\r\n");
+    message.push_str(&body);
+
+    stream.write_all(message.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn route(request: &MockRequest) -> MockResponse {
+    let segments = request.path
+        .trim_matches('/')
+        .split('/')
+        .collect::<Vec<&str>>();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["authorize"]) => authorize(request),
+        ("POST", ["api", "token"]) => token(request),
+
+        (_, ["v1", ..]) if !is_authorized(request) => {
+            MockResponse::error("401 Unauthorized", "Invalid access token")
+        },
+
+        ("GET", ["v1", "me"]) => MockResponse::json("200 OK", user()),
+        // Nothing is ever playing, but commands are taken as if they worked.
+        ("GET", ["v1", "me", "player"]) => MockResponse::no_content(),
+        ("GET", ["v1", "me", "player", "devices"]) => MockResponse::json("200 OK", json!({ "devices": [device()] })),
+        ("GET", ["v1", "me", "player", "recently-played"]) => MockResponse::json("200 OK", recently_played(request)),
+        ("PUT", ["v1", "me", "player", ..]) | ("POST", ["v1", "me", "player", ..]) => MockResponse::no_content(),
+
+        ("GET", ["v1", "me", "playlists"]) => MockResponse::json("200 OK", paged(request, "me/playlists", user_playlists())),
+        ("GET", ["v1", "search"]) => MockResponse::json("200 OK", search(request)),
+        ("GET", ["v1", "browse", "categories"]) => MockResponse::json("200 OK", json!({
+            "categories": page("browse/categories", vec![category("toplists", "Top Lists"), category("mood", "Mood")])
+        })),
+        ("GET", ["v1", "browse", "categories", id]) => MockResponse::json("200 OK", category(id, "Mock Category")),
+        ("GET", ["v1", "browse", "categories", id, "playlists"]) => MockResponse::json("200 OK", json!({
+            "message": "Mock category playlists",
+            "playlists": page(&format!("browse/categories/{}/playlists", id), vec![playlist("37i9dQZF1DXcBWIGoYBM5M", "Today's Top Hits")])
+        })),
+        ("GET", ["v1", "browse", "featured-playlists"]) => MockResponse::json("200 OK", json!({
+            "message": "Mock featured playlists",
+            "playlists": page("browse/featured-playlists", vec![playlist("37i9dQZF1DX0XUsuxWHRQd", "RapCaviar")])
+        })),
+        ("GET", ["v1", "albums", id]) => MockResponse::json("200 OK", full_album(request, id)),
+        ("GET", ["v1", "albums", id, "tracks"]) => MockResponse::json("200 OK", paged(request, &format!("albums/{}/tracks", id), album_tracks())),
+        ("GET", ["v1", "tracks"]) => MockResponse::json("200 OK", several_tracks(request)),
+        ("GET", ["v1", "tracks", id]) => MockResponse::json("200 OK", track(id, "Mock Track")),
+
+        _ => MockResponse::error("404 Not Found", "Service not found"),
+    }
+}
+
+fn is_authorized(request: &MockRequest) -> bool {
+    let expected = format!("Bearer {}", MOCK_ACCESS_TOKEN);
+
+    request.headers.get("authorization") == Some(&expected)
+}
+
+// Skips the consent screen and sends the browser straight back to the app.
+fn authorize(request: &MockRequest) -> MockResponse {
+    let Some(mut redirect_uri) = request.query.get("redirect_uri").and_then(|u| Url::parse(u).ok()) else {
+        return MockResponse::error("400 Bad Request", "Missing redirect_uri");
+    };
+
+    redirect_uri
+        .query_pairs_mut()
+        .append_pair("code", MOCK_AUTHORIZATION_CODE)
+        .append_pair("state", request.query.get("state").map(|s| s.as_str()).unwrap_or(""));
+
+    MockResponse {
+        status: "302 Found",
+        headers: vec![("Location", redirect_uri.to_string())],
+        body: None,
+    }
+}
+
+fn token(request: &MockRequest) -> MockResponse {
+    let form = url::form_urlencoded::parse(request.body.as_bytes())
+        .into_owned()
+        .collect::<HashMap<String, String>>();
+
+    let valid = match form.get("grant_type").map(|g| g.as_str()) {
+        Some("authorization_code") => form.get("code").map(|c| c.as_str()) == Some(MOCK_AUTHORIZATION_CODE),
+        Some("refresh_token") => form.get("refresh_token").map(|t| t.as_str()) == Some(MOCK_REFRESH_TOKEN),
+        _ => false,
+    };
+
+    if !valid {
+        return MockResponse::json("400 Bad Request", json!({
+            "error": "invalid_grant",
+            "error_description": "Invalid mock credentials"
+        }));
+    }
+
+    MockResponse::json("200 OK", json!({
+        "access_token": MOCK_ACCESS_TOKEN,
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "refresh_token": MOCK_REFRESH_TOKEN,
+        "scope": "streaming user-read-email user-read-private"
+    }))
+}
+
+fn search(request: &MockRequest) -> Value {
+    let query = request.query.get("query").cloned().unwrap_or_default();
+
+    let tracks = (1..=MOCK_SEARCH_TRACKS)
+        .map(|i| track(&format!("mocktrack{:0>13}", i), &format!("{} (Track {})", query, i)))
+        .collect();
+
+    json!({
+        "albums": page("search", vec![album("4aawyAB9vmqN3uQ7FjRGTy", &format!("{} (Album)", query))]),
+        "artists": page("search", vec![artist("0TnOYISbd1XYRBk9myaseg", &format!("{} (Artist)", query))]),
+        "playlists": page("search", vec![playlist("37i9dQZF1DXcBWIGoYBM5M", &format!("{} (Playlist)", query))]),
+        "tracks": paged(request, "search", tracks)
+    })
+}
+
+fn user_playlists() -> Vec<Value> {
+    (1..=MOCK_USER_PLAYLISTS)
+        .map(|i| playlist(&format!("mockplaylist{:0>10}", i), &format!("Mock Playlist {}", i)))
+        .collect()
+}
+
+fn page(route: &str, items: Vec<Value>) -> Value {
+    json!({
+        "href": format!("https://api.spotify.com/v1/{}", route),
+        "items": items,
+        "limit": 20,
+        "next": null,
+        "offset": 0,
+        "previous": null,
+        "total": items.len()
+    })
+}
+
+// One page of `items` by the request's offset and limit, linking to the next
+// the way Spotify does, with the rest of the query carried over.
+fn paged(request: &MockRequest, route: &str, items: Vec<Value>) -> Value {
+    let param = |name: &str, default: usize| request.query
+        .get(name)
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(default);
+
+    let offset = param("offset", 0);
+    let limit = param("limit", 20);
+    let total = items.len();
+
+    let link = |offset: usize| {
+        let mut url = Url::parse(&format!("https://api.spotify.com/v1/{}", route)).unwrap();
+
+        url.query_pairs_mut()
+            .extend_pairs(request.query.iter().filter(|(key, _)| *key != "offset" && *key != "limit"))
+            .append_pair("offset", &offset.to_string())
+            .append_pair("limit", &limit.to_string());
+
+        url.to_string()
+    };
+
+    json!({
+        "href": link(offset),
+        "items": items.into_iter().skip(offset).take(limit).collect::<Vec<Value>>(),
+        "limit": limit,
+        "next": (offset + limit < total).then(|| link(offset + limit)),
+        "offset": offset,
+        "previous": (offset > 0).then(|| link(offset.saturating_sub(limit))),
+        "total": total
+    })
+}
+
+fn image() -> Value {
+    json!([{
+        "url": "https://i.scdn.co/image/ab67616d0000b273mock",
+        "height": 640,
+        "width": 640
+    }])
+}
+
+fn external_urls(kind: &str, id: &str) -> Value {
+    json!({ "spotify": format!("https://open.spotify.com/{}/{}", kind, id) })
+}
+
+fn user() -> Value {
+    json!({
+        "country": "GB",
+        "display_name": "Mock User",
+        "email": "mock@example.com",
+        "explicit_content": { "filter_enabled": false, "filter_locked": false },
+        "external_urls": external_urls("user", MOCK_USER_ID),
+        "followers": { "href": null, "total": 0 },
+        "href": format!("https://api.spotify.com/v1/users/{}", MOCK_USER_ID),
+        "id": MOCK_USER_ID,
+        "images": [],
+        "product": "premium",
+        "type": "user",
+        "uri": format!("spotify:user:{}", MOCK_USER_ID)
+    })
+}
+
+fn device() -> Value {
+    json!({
+        "id": "mockdevice",
+        "is_active": true,
+        "is_private_session": false,
+        "is_restricted": false,
+        "name": "Mock Speaker",
+        "type": "Speaker",
+        "volume_percent": 50
+    })
+}
+
+// Two plays, newest first, on a single page.
+fn recently_played(request: &MockRequest) -> Value {
+    let plays = [
+        ("2024-01-01T12:05:00.000Z", "11dFghVXANMlKmJXsNCbNl"),
+        ("2024-01-01T12:00:00.000Z", "4iV5W9uYEdYUVa79Axb7Rh"),
+    ];
+
+    let items = plays
+        .iter()
+        .map(|(played_at, id)| json!({
+            "track": track(id, "Mock Track"),
+            "played_at": played_at,
+            "context": null
+        }))
+        .collect::<Vec<Value>>();
+
+    json!({
+        "href": "https://api.spotify.com/v1/me/player/recently-played",
+        "items": items,
+        "limit": request.query.get("limit").and_then(|l| l.parse::<u32>().ok()).unwrap_or(20),
+        "next": null,
+        "cursors": { "after": "1704110700000", "before": "1704110400000" },
+        "total": null
+    })
+}
+
+fn category(id: &str, name: &str) -> Value {
+    json!({
+        "href": format!("https://api.spotify.com/v1/browse/categories/{}", id),
+        "icons": image(),
+        "id": id,
+        "name": name
+    })
+}
+
+fn playlist(id: &str, name: &str) -> Value {
+    json!({
+        "collaborative": false,
+        "description": "A mock playlist.",
+        "external_urls": external_urls("playlist", id),
+        "href": format!("https://api.spotify.com/v1/playlists/{}", id),
+        "id": id,
+        "images": image(),
+        "name": name,
+        "owner": {
+            "display_name": "Spotify",
+            "external_urls": external_urls("user", "spotify"),
+            "href": "https://api.spotify.com/v1/users/spotify",
+            "id": "spotify",
+            "type": "user",
+            "uri": "spotify:user:spotify"
+        },
+        "public": true,
+        "snapshot_id": "mock",
+        "tracks": {
+            "href": format!("https://api.spotify.com/v1/playlists/{}/tracks", id),
+            "total": 1
+        },
+        "type": "playlist",
+        "uri": format!("spotify:playlist:{}", id)
+    })
+}
+
+fn simplified_artist(id: &str, name: &str) -> Value {
+    json!({
+        "external_urls": external_urls("artist", id),
+        "href": format!("https://api.spotify.com/v1/artists/{}", id),
+        "id": id,
+        "name": name,
+        "type": "artist",
+        "uri": format!("spotify:artist:{}", id)
+    })
+}
+
+fn artist(id: &str, name: &str) -> Value {
+    let mut artist = simplified_artist(id, name);
+
+    artist["followers"] = json!({ "href": null, "total": 1000 });
+    artist["genres"] = json!(["mock"]);
+    artist["images"] = image();
+    artist["popularity"] = json!(50);
+
+    artist
+}
+
+fn album(id: &str, name: &str) -> Value {
+    json!({
+        "album_type": "album",
+        "artists": [simplified_artist("0TnOYISbd1XYRBk9myaseg", "Mock Artist")],
+        "available_markets": ["GB"],
+        "external_urls": external_urls("album", id),
+        "href": format!("https://api.spotify.com/v1/albums/{}", id),
+        "id": id,
+        "images": image(),
+        "name": name,
+        "release_date": "2024-01-01",
+        "release_date_precision": "day",
+        "total_tracks": 1,
+        "type": "album",
+        "uri": format!("spotify:album:{}", id)
+    })
+}
+
+// An album with its first page of tracks, the way Spotify sends it.
+fn full_album(request: &MockRequest, id: &str) -> Value {
+    let mut album = album(id, "Mock Album");
+
+    album["copyrights"] = json!([]);
+    album["external_ids"] = json!({ "upc": "000000000001" });
+    album["genres"] = json!([]);
+    album["label"] = json!("Mock Label");
+    album["popularity"] = json!(50);
+    album["total_tracks"] = json!(MOCK_ALBUM_TRACKS);
+    album["tracks"] = paged(request, &format!("albums/{}/tracks", id), album_tracks());
+
+    album
+}
+
+fn album_tracks() -> Vec<Value> {
+    (1..=MOCK_ALBUM_TRACKS)
+        .map(|i| track(&format!("mockalbumtrack{:0>8}", i), &format!("Mock Album Track {}", i)))
+        .collect()
+}
+
+fn several_tracks(request: &MockRequest) -> Value {
+    let ids = request.query.get("ids").cloned().unwrap_or_default();
+
+    json!({
+        "tracks": ids.split(',').map(|id| track(id, "Mock Track")).collect::<Vec<Value>>()
+    })
+}
+
+fn track(id: &str, name: &str) -> Value {
+    json!({
+        "album": album("4aawyAB9vmqN3uQ7FjRGTy", "Mock Album"),
+        "artists": [simplified_artist("0TnOYISbd1XYRBk9myaseg", "Mock Artist")],
+        "available_markets": ["GB"],
+        "disc_number": 1,
+        "duration_ms": 180000,
+        "explicit": false,
+        "external_ids": { "isrc": "GBMOCK0000001" },
+        "external_urls": external_urls("track", id),
+        "href": format!("https://api.spotify.com/v1/tracks/{}", id),
+        "id": id,
+        "is_local": false,
+        "name": name,
+        "popularity": 50,
+        "preview_url": null,
+        "track_number": 1,
+        "type": "track",
+        "uri": format!("spotify:track:{}", id)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use oauth2::AccessToken;
+    use reqwest::{Method, StatusCode};
+    use rspotify_model::{FullTrack, Id as _, Page, SimplifiedPlaylist};
+
+    use super::*;
+    use crate::api::{
+        cache::SpotifyAPICache,
+        error::SpotifyAPIError,
+        models::{album::AlbumDetails, search::SearchResultsKind},
+        providers::oauth2::{SpotifyAPIOAuthConfig, SpotifyAPIOAuthError},
+        SpotifyAPI,
+    };
+
+    // Logs in with `refresh_token`, keeping responses out of the real cache.
+    async fn login(server: &SpotifyAPIMockServer, refresh_token: &str) -> SpotifyAPI {
+        let mut api = SpotifyAPI::new(
+            None,
+            Some(refresh_token.to_string()),
+            server.endpoints(),
+            SpotifyAPIOAuthConfig::default()
+        );
+
+        api.cache = SpotifyAPICache::new(None, 0);
+        api.login(None).await;
+
+        api
+    }
+
+    #[tokio::test]
+    async fn logs_in_with_refresh_token() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        assert!(api.is_authenticated());
+        assert_eq!(api.access_token().await.unwrap(), MOCK_ACCESS_TOKEN);
+        assert_eq!(api.get_current_user_profile().await.unwrap().display_name.as_deref(), Some("Mock User"));
+    }
+
+    #[tokio::test]
+    async fn stamps_stored_logins_with_their_session() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        assert_eq!(api.login_to_store().await, (0, Some(MOCK_REFRESH_TOKEN.to_string())));
+
+        // Still the old provider until the switch swaps it out, whatever
+        // it hands back now belongs to the session that's ended.
+        assert_eq!(api.end_session(), 1);
+        assert!(!api.is_authenticated());
+        assert_eq!(api.login_to_store().await.0, 0);
+
+        api.switch_account(Some(MOCK_REFRESH_TOKEN.to_string())).await;
+
+        assert!(api.is_authenticated());
+        assert_eq!(api.login_to_store().await.0, api.generation());
+    }
+
+    #[tokio::test]
+    async fn fails_login_with_bad_refresh_token() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, "not-a-refresh-token").await;
+
+        assert!(matches!(
+            api.get_state_error(),
+            Some(SpotifyAPIError::OAuth2Error(SpotifyAPIOAuthError::CodeExchangeFailure(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deserializes_responses() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        let track = api.get_track("11dFghVXANMlKmJXsNCbNl".to_string()).await.unwrap();
+
+        assert_eq!(track.name, "Mock Track");
+        assert_eq!(track.duration.num_milliseconds(), 180000);
+    }
+
+    #[tokio::test]
+    async fn maps_error_statuses() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        let result = api.request::<FullTrack>(Method::GET, "/not-a-route".to_string(), None).await;
+
+        match result {
+            Err(SpotifyAPIError::RequestError(err)) => assert_eq!(err.status(), Some(StatusCode::NOT_FOUND)),
+            other => panic!("Expected a request error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn refreshes_rejected_access_token() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        // As if Spotify revoked it before it was due to expire.
+        api.provider.lock().await.token
+            .as_mut()
+            .unwrap()
+            .set_access_token(AccessToken::new("revoked-access-token".to_string()));
+
+        assert!(api.get_current_user_profile().await.is_ok());
+        assert_eq!(api.access_token().await.unwrap(), MOCK_ACCESS_TOKEN);
+    }
+
+    #[tokio::test]
+    async fn refreshes_access_token_before_it_expires() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        assert_eq!(server.received("POST", "/api/token").len(), 1);
+
+        // Storing the login after a request doesn't refresh on its own.
+        api.provider.lock().await.token_expires_at = Some(Instant::now());
+
+        assert_eq!(api.login_to_store().await.1.as_deref(), Some(MOCK_REFRESH_TOKEN));
+        assert_eq!(server.received("POST", "/api/token").len(), 1);
+
+        // Within the margin, so it's refreshed before Spotify can turn it down.
+        assert!(api.get_current_user_profile().await.is_ok());
+        assert_eq!(server.received("POST", "/api/token").len(), 2);
+        assert_eq!(server.received("GET", "/v1/me").len(), 1);
+        assert!(!api.provider.lock().await.is_token_expiring());
+    }
+
+    #[tokio::test]
+    async fn shares_one_refresh_between_rejected_requests() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        api.provider.lock().await.token
+            .as_mut()
+            .unwrap()
+            .set_access_token(AccessToken::new("revoked-access-token".to_string()));
+
+        let results = futures::future::join_all(
+            (0..4).map(|_| api.get_current_user_profile())
+        ).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+
+        // The login, then a single refresh for all four 401s.
+        assert_eq!(server.received("POST", "/api/token").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fetches_every_page() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        let first = api.get_current_user_playlists(Some(2), None).await.unwrap();
+
+        assert_eq!(first.items.len(), 2);
+        assert!(first.next.is_some());
+
+        let all = api.fetch_all(first, |page: Page<SimplifiedPlaylist>| Some(page), None).await.unwrap();
+        let names = all.iter().map(|p| p.name.clone()).collect::<Vec<String>>();
+
+        assert_eq!(names, (1..=MOCK_USER_PLAYLISTS).map(|i| format!("Mock Playlist {}", i)).collect::<Vec<String>>());
+    }
+
+    #[tokio::test]
+    async fn fetches_albums_from_their_first_page_of_tracks() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        let details = AlbumDetails::fetch(&api, "4aawyAB9vmqN3uQ7FjRGTy".to_string()).await.unwrap();
+        let ids = details.tracks.iter().map(|t| t.id.as_ref().unwrap().id().to_string()).collect::<Vec<String>>();
+
+        assert_eq!(ids, (1..=MOCK_ALBUM_TRACKS).map(|i| format!("mockalbumtrack{:0>8}", i)).collect::<Vec<String>>());
+
+        // Only the page after the one that came with the album.
+        let pages = server.received("GET", "/v1/albums/4aawyAB9vmqN3uQ7FjRGTy/tracks");
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].query.get("offset").map(|o| o.as_str()), Some("20"));
+    }
+
+    #[tokio::test]
+    async fn fetches_up_to_max_items() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        let first = api.get_current_user_playlists(Some(2), None).await.unwrap();
+        let some = api.fetch_all(first, |page: Page<SimplifiedPlaylist>| Some(page), Some(3)).await.unwrap();
+
+        assert_eq!(some.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn searches_and_gets_more() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        let mut results = api.search("hello".to_string(), None, Some(20)).await.unwrap();
+
+        assert_eq!(results.tracks.as_ref().unwrap().items[0].name, "hello (Track 1)");
+        assert!(results.has_more(SearchResultsKind::Tracks));
+        assert!(!results.has_more(SearchResultsKind::Albums));
+
+        let more = results.get_more(&api, SearchResultsKind::Tracks).await.unwrap();
+
+        assert_eq!(more.tracks.as_ref().unwrap().items[0].name, "hello (Track 21)");
+
+        // Another search's results don't continue from the same page.
+        let other = api.search("goodbye".to_string(), None, Some(20)).await.unwrap();
+
+        assert!(other.next_page_url(SearchResultsKind::Tracks).is_some());
+        assert_ne!(other.next_page_url(SearchResultsKind::Tracks), results.next_page_url(SearchResultsKind::Tracks));
+
+        results.extend(more);
+
+        assert_eq!(results.tracks.as_ref().unwrap().items.len(), MOCK_SEARCH_TRACKS);
+        assert!(!results.has_more(SearchResultsKind::Tracks));
+        assert_eq!(results.next_page_url(SearchResultsKind::Tracks), None);
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_requests_after_retry_after() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        server.fail_next("GET", "/v1/me", "429 Too Many Requests", Some(1));
+
+        let started = std::time::Instant::now();
+
+        assert!(api.get_current_user_profile().await.is_ok());
+        assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+        assert_eq!(server.received("GET", "/v1/me").len(), 2);
+
+        // Spotify turned it away before acting on it, so even a POST goes again.
+        server.fail_next("POST", "/v1/me/player/next", "429 Too Many Requests", Some(0));
+
+        assert!(api.skip_to_next(None).await.is_ok());
+        assert_eq!(server.received("POST", "/v1/me/player/next").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_only_when_idempotent() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server, MOCK_REFRESH_TOKEN).await;
+
+        server.fail_next("GET", "/v1/me", "503 Service Unavailable", None);
+
+        assert!(api.get_current_user_profile().await.is_ok());
+        assert_eq!(server.received("GET", "/v1/me").len(), 2);
+
+        // It may have skipped already, doing it again would skip twice.
+        server.fail_next("POST", "/v1/me/player/next", "503 Service Unavailable", None);
+
+        assert!(matches!(api.skip_to_next(None).await, Err(SpotifyAPIError::RequestError(_))));
+        assert_eq!(server.received("POST", "/v1/me/player/next").len(), 1);
+    }
+}
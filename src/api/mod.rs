@@ -5,20 +5,34 @@ pub mod models;
 pub mod enums;
 pub mod utils;
 pub mod constants;
-
-use std::{borrow::Cow, collections::HashMap, rc::Rc, sync::{Arc, Mutex}, time::{Duration, SystemTime}};
-
-use constants::{SPOTIFY_ACCOUNTS_URL, SPOTIFY_API_URL, SPOTIFY_CATEGORIES_INTERNAL, SPOTIFY_CATEGORY_ID_MADE_FOR_YOU};
+pub mod endpoints;
+pub mod paginator;
+pub mod player;
+pub mod artists;
+pub mod library;
+pub mod scheduler;
+pub mod cache;
+#[cfg(test)]
+pub mod testing;
+#[cfg(any(test, feature = "mock-api"))]
+pub mod mock;
+
+use std::{borrow::Cow, collections::HashMap, rc::Rc, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex}, time::{Duration, SystemTime}};
+
+use constants::{SPOTIFY_ACCOUNTS_URL, SPOTIFY_API_MAX_RETRIES, SPOTIFY_CATEGORIES_INTERNAL, SPOTIFY_CATEGORY_ID_MADE_FOR_YOU};
 use data::{SpotifyAPIData};
+use endpoints::SpotifyAPIEndpoints;
 use easy_imgui::IntoCStr;
 use easy_imgui_window::winit::event_loop::EventLoopProxy;
 use enums::search::SpotifyAPISearchType;
 use error::SpotifyAPIError;
 use models::{recommendations::{BrowseRecommendationSections, BrowseRecommendations}, search::SearchResults, user::UserImpl as _};
 use oauth2::{basic::BasicTokenType, EmptyExtraTokenFields, RefreshToken, StandardTokenResponse, TokenResponse};
-use providers::oauth2::{SpotifyAPIOAuthClient, SpotifyAPIOAuthProvider};
-use reqwest::Method;
-use rspotify_model::{Category, CategoryPlaylists, FeaturedPlaylists, FullPlaylist, Page, PageCategory, PrivateUser, Recommendations, SearchResult, SimplifiedPlaylist};
+use providers::oauth2::{SpotifyAPIOAuthClient, SpotifyAPIOAuthConfig, SpotifyAPIOAuthError, SpotifyAPIOAuthProvider};
+use reqwest::{Method, StatusCode};
+use cache::{is_cacheable, parse_etag, SpotifyAPICache, SpotifyAPICacheEntry, SpotifyAPICachePolicy};
+use scheduler::{SpotifyAPIScheduler, backoff, parse_retry_after};
+use rspotify_model::{Category, CategoryPlaylists, FeaturedPlaylists, FullAlbum, FullPlaylist, FullTrack, FullTracks, Page, PageCategory, PlaylistItem, PrivateUser, Recommendations, SearchResult, SimplifiedPlaylist};
 use serde::Deserialize;
 use tracing::{error, info, warn};
 use url::Url;
@@ -43,22 +57,41 @@ impl SpotifyAPIState {
 
 #[derive(Clone)]
 pub struct SpotifyAPI {
-    pub event_loop: Arc<EventLoopProxy<AppEvent>>,
+    // Optional so the API can be used headless, e.g. against the mock server.
+    pub event_loop: Option<Arc<EventLoopProxy<AppEvent>>>,
+
+    pub endpoints: SpotifyAPIEndpoints,
+
+    // Takes effect the next time someone logs in.
+    pub oauth_config: Arc<Mutex<SpotifyAPIOAuthConfig>>,
 
     pub state: Arc<Mutex<Result<SpotifyAPIState, SpotifyAPIError>>>,
 
     pub provider: Arc<tokio::sync::Mutex<SpotifyAPIOAuthProvider>>,
 
     pub client: reqwest::Client,
+
+    pub scheduler: SpotifyAPIScheduler,
+
+    pub cache: SpotifyAPICache,
+
+    // Answer GET requests from the cache alone, without touching the network.
+    cache_only: bool,
+
+    // Fires to abandon a login that's still waiting on the browser.
+    login_cancel: Arc<Mutex<Option<tokio::sync::oneshot::Sender<()>>>>,
+
+    // Bumped whenever the session is torn down, see `end_session`.
+    generation: Arc<AtomicU64>,
 }
 
 impl SpotifyAPI {
-    pub fn create_new_provider(refresh_token: Option<String>) -> SpotifyAPIOAuthProvider {
-        SpotifyAPIOAuthProvider::new(refresh_token)
+    pub fn create_new_provider(refresh_token: Option<String>, endpoints: &SpotifyAPIEndpoints, oauth_config: &SpotifyAPIOAuthConfig) -> SpotifyAPIOAuthProvider {
+        SpotifyAPIOAuthProvider::new(refresh_token, endpoints, oauth_config)
     }
 
-    pub fn new(event_loop: Arc<EventLoopProxy<AppEvent>>, refresh_token: Option<String>) -> Self {
-        let provider = Arc::new(tokio::sync::Mutex::new(SpotifyAPI::create_new_provider(refresh_token.clone())));
+    pub fn new(event_loop: Option<Arc<EventLoopProxy<AppEvent>>>, refresh_token: Option<String>, endpoints: SpotifyAPIEndpoints, oauth_config: SpotifyAPIOAuthConfig) -> Self {
+        let provider = Arc::new(tokio::sync::Mutex::new(SpotifyAPI::create_new_provider(refresh_token.clone(), &endpoints, &oauth_config)));
         let state = Arc::new(Mutex::new(Ok(SpotifyAPIState::NotAuthenticated)));
 
         let user_agent = format!("{}/{}", UI_APP_NAME, UI_APP_VERSION.unwrap_or("0.0.0"));
@@ -69,16 +102,57 @@ impl SpotifyAPI {
             .build()
             .unwrap();
 
-        if refresh_token.clone().is_some() {
+        if let (Some(event_loop), Some(_)) = (&event_loop, &refresh_token) {
             event_loop.send_event(AppEvent::Login).ok();
         }
 
         SpotifyAPI {
             event_loop,
+            endpoints,
+            oauth_config: Arc::new(Mutex::new(oauth_config)),
 
             state,
             provider,
-            client
+            client,
+
+            scheduler: SpotifyAPIScheduler::default(),
+
+            cache: SpotifyAPICache::default(),
+            cache_only: false,
+
+            login_cancel: Arc::new(Mutex::new(None)),
+
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// A copy of the API that only answers from the cache, for showing
+    /// something straight away while the real requests are in flight.
+    pub fn cached(&self) -> SpotifyAPI {
+        SpotifyAPI {
+            cache_only: true,
+            ..self.clone()
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Stops treating the current login as ours, ahead of switching or
+    /// logging out. Anything still in flight for it is stamped with the
+    /// old generation, so what it sends back can be told apart.
+    pub fn end_session(&self) -> u64 {
+        self.cancel_login();
+
+        *self.state.lock().unwrap() = Ok(SpotifyAPIState::NotAuthenticated);
+
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn send_event(&self, event: AppEvent) {
+        if let Some(event_loop) = &self.event_loop {
+            event_loop.send_event(event).ok();
         }
     }
 
@@ -128,6 +202,13 @@ impl SpotifyAPI {
         }
     }
 
+    /// The logged in user's country, for endpoints that take a `market`.
+    pub fn market(&self) -> Option<String> {
+        self.state()
+            .and_then(|s| s.profile)
+            .and_then(|p| p.market())
+    }
+
     pub async fn token(&self, should_refresh: Option<bool>) -> Result<StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>, SpotifyAPIError> {
         let mut provider = self.provider.lock().await;
 
@@ -137,15 +218,40 @@ impl SpotifyAPI {
     }
 
     pub async fn access_token(&self) -> Result<String, SpotifyAPIError> {
-        let is_state_err = self.state.lock().unwrap().is_err();
-
-        Ok(self.token(Some(is_state_err))
+        Ok(self.token(None)
             .await?
             .access_token()
             .secret()
             .to_string())
     }
 
+    // The provider lock makes sure only one refresh happens at a time,
+    // anyone else who got a 401 meanwhile just picks up the new token.
+    async fn refresh_rejected_access_token(&self, rejected_access_token: &str) -> Result<String, SpotifyAPIError> {
+        let mut provider = self.provider.lock().await;
+
+        Ok(provider.refresh_rejected_token(rejected_access_token)
+            .await
+            .map_err(SpotifyAPIError::OAuth2Error)?
+            .access_token()
+            .secret()
+            .to_string())
+    }
+
+    // The refresh token to keep for next time, with the generation of the
+    // session it belongs to, read together under the provider lock. Only
+    // what we already have, storing it is no reason to refresh.
+    async fn login_to_store(&self) -> (u64, Option<String>) {
+        let provider = self.provider.lock().await;
+
+        let refresh_token = provider.token
+            .as_ref()
+            .and_then(|t| t.refresh_token())
+            .map(|t| t.secret().to_string());
+
+        (provider.generation, refresh_token)
+    }
+
     pub async fn refresh_token(&self) -> Result<Option<String>, SpotifyAPIError> {
         Ok(self.token(Some(false))
             .await?
@@ -153,21 +259,128 @@ impl SpotifyAPI {
     }
 
     pub async fn request<R: for<'a> Deserialize<'a>>(&self, method: Method, route: String, query: Option<HashMap<&str, Option<&String>>>) -> Result<R, SpotifyAPIError> {
+        self.request_with_body(method, route, query, None).await
+    }
+
+    pub async fn request_with_body<R: for<'a> Deserialize<'a>>(&self, method: Method, route: String, query: Option<HashMap<&str, Option<&String>>>, body: Option<serde_json::Value>) -> Result<R, SpotifyAPIError> {
         let time_start = SystemTime::now();
 
-        let mut url = Url::parse(SPOTIFY_API_URL)
-            .unwrap();
-        url = url.join(&route[1..]).unwrap();
+        let url = self.endpoints.api_url
+            .join(&route[1..])
+            .map_err(|_| SpotifyAPIError::Unknown("Invalid API route."))?;
 
-        let access_token = self.access_token().await?;
-        let refresh_token = self.refresh_token().await?;
+        let query = query.unwrap_or_default();
 
-        let response = self.client.request(method.clone(), url.as_str())
-            .bearer_auth(access_token)
-            .query(&query.unwrap_or([].into()))
-            .send()
-            .await
-            .map_err(|e| SpotifyAPIError::RequestError(Arc::new(e)))?;
+        let cache_key = (method == Method::GET && is_cacheable(&route)).then(|| self.cache.key(&url, &query));
+        let cached = match &cache_key {
+            Some(key) => self.cache.get(key).await,
+            None => None,
+        };
+
+        let parse_cached = |entry: &SpotifyAPICacheEntry| {
+            serde_json::de::from_str::<R>(&entry.body)
+                .map_err(|e| SpotifyAPIError::SerdeError(Arc::new(e)))
+        };
+
+        match &cached {
+            Some(entry) if self.cache_only || entry.is_fresh() => return parse_cached(entry),
+            None if self.cache_only => return Err(SpotifyAPIError::NotCached),
+            _ => {}
+        }
+
+        let mut access_token = self.access_token().await?;
+
+        let mut attempt = 0;
+        let mut replayed_unauthorized = false;
+
+        // Something like skipping a track mustn't happen twice because the reply
+        // was lost, those are only retried when Spotify can't have acted on them.
+        let idempotent = method.is_idempotent();
+
+        let response = loop {
+            let permit = self.scheduler.acquire().await;
+
+            let mut request_builder = self.client.request(method.clone(), url.as_str())
+                .bearer_auth(&access_token)
+                .query(&query);
+
+            if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_ref()) {
+                request_builder = request_builder.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+
+            // Spotify wants a Content-Length even on empty PUT and POST requests.
+            request_builder = match &body {
+                Some(body) => request_builder.json(body),
+                None if method != Method::GET => request_builder.header(reqwest::header::CONTENT_LENGTH, 0),
+                None => request_builder,
+            };
+
+            let result = request_builder.send().await;
+
+            drop(permit);
+
+            let can_retry = attempt < SPOTIFY_API_MAX_RETRIES;
+
+            match result {
+                // Our token may have been revoked or expired early, refresh it and replay once.
+                Ok(response) if response.status() == StatusCode::UNAUTHORIZED && !replayed_unauthorized => {
+                    warn!("{} {}: unauthorized, refreshing access token and retrying.", method, route);
+
+                    access_token = self.refresh_rejected_access_token(&access_token).await?;
+                    replayed_unauthorized = true;
+
+                    continue;
+                },
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = parse_retry_after(response.headers())
+                        .unwrap_or_else(|| backoff(attempt));
+
+                    // Everyone else backs off too, the limit applies to the whole app.
+                    self.scheduler.set_rate_limited(retry_after);
+
+                    if !can_retry {
+                        error!("{} {}: rate limited, giving up after {} attempts.", method, route, attempt + 1);
+
+                        return Err(SpotifyAPIError::RateLimited(retry_after));
+                    }
+
+                    warn!("{} {}: rate limited, retrying in {}s.", method, route, retry_after.as_secs());
+                },
+                Ok(response) if response.status().is_server_error() && idempotent && can_retry => {
+                    let delay = backoff(attempt);
+
+                    warn!("{} {}: {}, retrying in {:.2}s.", method, route, response.status().as_str(), delay.as_secs_f32());
+
+                    tokio::time::sleep(delay).await;
+                },
+                Ok(response) => break response,
+                Err(err) if (err.is_connect() || (err.is_timeout() && idempotent)) && can_retry => {
+                    let delay = backoff(attempt);
+
+                    warn!("{} {}: {}, retrying in {:.2}s.", method, route, err, delay.as_secs_f32());
+
+                    tokio::time::sleep(delay).await;
+                },
+                // Can't reach Spotify, show what we had last time instead.
+                Err(err) if cached.is_some() => {
+                    warn!("{} {}: {}, using cached response.", method, route, err);
+
+                    return parse_cached(cached.as_ref().unwrap());
+                },
+                Err(err) => return Err(SpotifyAPIError::RequestError(Arc::new(err))),
+            }
+
+            attempt += 1;
+        };
+
+        let cache_policy = SpotifyAPICachePolicy::from_headers(response.headers());
+        let etag = parse_etag(response.headers());
+
+        if let (StatusCode::NOT_MODIFIED, Some(key), Some(entry)) = (response.status(), &cache_key, cached) {
+            info!("{} {}: 304 Not Modified, using cached response.", method, route);
+
+            return parse_cached(&self.cache.revalidate(key, entry, cache_policy.expires_at()).await);
+        }
 
         let status = response.status();
         let response_url = response.url().clone();
@@ -213,32 +426,49 @@ impl SpotifyAPI {
             Err(err) => return handle_error(err, None)?
         };
 
-        let data = match serde_json::de::from_str::<R>(&text)
+        // Most player endpoints reply with 204 No Content, treat that
+        // as null so they can be deserialized into () or Option<T>.
+        let json_text = if text.trim().is_empty() { "null" } else { text.as_str() };
+
+        // Error bodies never look like what we asked for, so check the
+        // status first, or every error would come out as a SerdeError.
+        if let Some(err) = response_error {
+            let pretty_text = serde_json::ser::to_string_pretty(&text).unwrap_or(text);
+
+            return handle_error(err, Some(pretty_text))?
+        }
+
+        let data = match serde_json::de::from_str::<R>(json_text)
             .map_err(|e| SpotifyAPIError::SerdeError(Arc::new(e)))
         {
             Ok(t) => t,
             Err(err) => return handle_error(err, Some(text))?
         };
 
-        if response_error.is_some() {
-            let pretty_text = serde_json::ser::to_string_pretty(&text).unwrap_or(text);
-
-            return handle_error(
-                response_error.unwrap(),
-                Some(pretty_text)
-            )?
-        } else {
-            info!(
-                "{} {}{}: {} in {}ms",
-                method.clone(),
-                path,
-                query,
-                status.as_str(),
-                time_diff
-            );
+        info!(
+            "{} {}{}: {} in {}ms",
+            method.clone(),
+            path,
+            query,
+            status.as_str(),
+            time_diff
+        );
+
+        // Nothing to keep from a 204, and "" wouldn't parse when read back.
+        let has_body = !text.trim().is_empty();
+
+        if let (Some(key), false, true) = (&cache_key, cache_policy.no_store, has_body) {
+            self.cache.put(key, &SpotifyAPICacheEntry {
+                url: response_url.to_string(),
+                etag,
+                expires_at: cache_policy.expires_at(),
+                body: text,
+            }).await;
         }
 
-        self.event_loop.send_event(AppEvent::StoreToken(refresh_token)).ok();
+        let (generation, refresh_token) = self.login_to_store().await;
+
+        self.send_event(AppEvent::StoreToken(generation, refresh_token));
 
         Ok(data)
     }
@@ -247,6 +477,69 @@ impl SpotifyAPI {
         self.request::<PrivateUser>(Method::GET, "/me".to_string(), None).await
     }
 
+    pub async fn get_track(&self, track_id: String) -> Result<FullTrack, SpotifyAPIError> {
+        self.request::<FullTrack>(Method::GET, format!("/tracks/{}", track_id), None).await
+    }
+
+    pub async fn get_several_tracks(&self, track_ids: Vec<String>) -> Result<Vec<FullTrack>, SpotifyAPIError> {
+        let mut tracks = Vec::with_capacity(track_ids.len());
+
+        // Spotify only takes 50 IDs at a time.
+        for chunk in track_ids.chunks(50) {
+            let ids = chunk.join(",");
+
+            let query = utils::create_hashmap(&[
+                ("ids", Some(&ids))
+            ]);
+
+            tracks.extend(
+                self.request::<FullTracks>(Method::GET, "/tracks".to_string(), Some(query))
+                    .await?
+                    .tracks
+            );
+        }
+
+        Ok(tracks)
+    }
+
+    pub async fn get_album(&self, album_id: String) -> Result<FullAlbum, SpotifyAPIError> {
+        self.request::<FullAlbum>(Method::GET, format!("/albums/{}", album_id), None).await
+    }
+
+    pub async fn get_current_user_playlists(&self, limit: Option<u32>, offset: Option<u32>) -> Result<Page<SimplifiedPlaylist>, SpotifyAPIError> {
+        let limit = limit.map(|x| x.to_string());
+        let offset = offset.map(|x| x.to_string());
+
+        let query = utils::create_hashmap(&[
+            ("limit", limit.as_ref()),
+            ("offset", offset.as_ref())
+        ]);
+
+        self.request::<Page<SimplifiedPlaylist>>(Method::GET, "/me/playlists".to_string(), Some(query)).await
+    }
+
+    pub async fn get_all_current_user_playlists(&self) -> Result<Vec<SimplifiedPlaylist>, SpotifyAPIError> {
+        let first = self.get_current_user_playlists(Some(50), None).await?;
+
+        self.fetch_all(first, |page: Page<SimplifiedPlaylist>| Some(page), None).await
+    }
+
+    pub async fn get_playlist(&self, playlist_id: String) -> Result<FullPlaylist, SpotifyAPIError> {
+        self.request::<FullPlaylist>(Method::GET, format!("/playlists/{}", playlist_id), None).await
+    }
+
+    pub async fn get_playlist_items(&self, playlist_id: String, limit: Option<u32>, offset: Option<u32>) -> Result<Page<PlaylistItem>, SpotifyAPIError> {
+        let limit = limit.map(|x| x.to_string());
+        let offset = offset.map(|x| x.to_string());
+
+        let query = utils::create_hashmap(&[
+            ("limit", limit.as_ref()),
+            ("offset", offset.as_ref())
+        ]);
+
+        self.request::<Page<PlaylistItem>>(Method::GET, format!("/playlists/{}/tracks", playlist_id), Some(query)).await
+    }
+
     pub async fn search(&self, query: String, search_types: Option<SpotifyAPISearchType>, limit: Option<u32>) -> Result<SearchResults, SpotifyAPIError> {
         let search_type = search_types
             .unwrap_or(SpotifyAPISearchType::all())
@@ -356,8 +649,19 @@ impl SpotifyAPI {
         // Fetch stuff here
         let profile = self.get_current_user_profile().await?;
 
+        // Not worth failing the whole login over, keep what we had.
+        let playlists = match self.get_all_current_user_playlists().await {
+            Ok(playlists) => Some(playlists),
+            Err(err) => {
+                warn!("Failed to fetch the user's playlists: {:#?}", err);
+
+                self.state().and_then(|s| s.playlists)
+            }
+        };
+
         *self.state.lock().unwrap() = Ok(SpotifyAPIState::LoggedIn(SpotifyAPIData {
-            profile: Some(profile)
+            profile: Some(profile),
+            playlists
         }));
 
         // Only log that we've logged in if we don't have a session yet:
@@ -371,7 +675,7 @@ impl SpotifyAPI {
                 );
             }
 
-            self.event_loop.send_event(AppEvent::FirstTimeLogin).ok();
+            self.send_event(AppEvent::FirstTimeLogin);
         }
 
         Ok(())
@@ -388,22 +692,48 @@ impl SpotifyAPI {
         }
     }
 
+    /// Stops waiting on the browser for a login that's in progress,
+    /// which also frees up the provider for the next one.
+    pub fn cancel_login(&self) {
+        if let Some(cancel) = self.login_cancel.lock().unwrap().take() {
+            cancel.send(()).ok();
+        }
+    }
+
     pub async fn login(&self, force: Option<bool>) {
+        self.cancel_login();
+
+        let (cancel_send, cancel_recv) = tokio::sync::oneshot::channel();
+        *self.login_cancel.lock().unwrap() = Some(cancel_send);
+
         let mut provider = self.provider.lock().await;
 
         // If we don't have a refresh token ready
         // prompt the user to login through their browser.
-        if force.unwrap_or(false) || provider.init_refresh_token.is_none() {
-            let auth_url = provider.auth_url.as_str();
+        let listener = if force.unwrap_or(false) || provider.init_refresh_token.is_none() {
+            match provider.bind_callback_listener().await {
+                Ok(listener) => {
+                    prompt_open_url(provider.auth_url.to_string());
+
+                    Some(listener)
+                },
+                Err(err) => {
+                    error!("Failed to start OAuth2 callback server: {:#?}", err);
+
+                    *self.state.lock().unwrap() = Err(SpotifyAPIError::OAuth2Error(err));
 
-            prompt_open_url(auth_url.to_string());
+                    return;
+                }
+            }
         } else {
             info!("Authenticating with refresh token...");
-        }
+
+            None
+        };
 
         *self.state.lock().unwrap() = Ok(SpotifyAPIState::Authenticating);
 
-        match provider.update_client(force)
+        match provider.update_client(listener, cancel_recv)
             .await
             .map_err(SpotifyAPIError::OAuth2Error)
         {
@@ -412,7 +742,11 @@ impl SpotifyAPI {
 
                 *self.state.lock().unwrap() = Ok(SpotifyAPIState::Authenticated);
 
-                self.event_loop.send_event(AppEvent::Fetch(AppFetchType::All)).ok();
+                self.send_event(AppEvent::Fetch(AppFetchType::All));
+            },
+            // Whoever cancelled us is about to start over, leave the state to them.
+            Err(SpotifyAPIError::OAuth2Error(SpotifyAPIOAuthError::Cancelled)) => {
+                info!("Cancelled waiting for login.");
             },
             Err(err) => {
                 error!("Failed to update API client: {:#?}", err);
@@ -422,13 +756,38 @@ impl SpotifyAPI {
         }
     }
 
+    /// Drops the current session without forgetting its credentials, then logs in
+    /// as whoever `refresh_token` belongs to, or through the browser when None.
+    pub async fn switch_account(&self, refresh_token: Option<String>) {
+        self.cancel_login();
+
+        *self.state.lock().unwrap() = Ok(SpotifyAPIState::NotAuthenticated);
+
+        let force = refresh_token.is_none();
+        let oauth_config = self.oauth_config.lock().unwrap().clone();
+
+        let mut provider = SpotifyAPIOAuthProvider::new(refresh_token, &self.endpoints, &oauth_config);
+        provider.generation = self.generation();
+
+        *self.provider.lock().await = provider;
+
+        self.login(Some(force)).await;
+    }
+
     pub async fn logout(&self) {
+        self.cancel_login();
+
         let cached_profile = self.state()
             .and_then(|s| s.profile);
 
         *self.state.lock().unwrap() = Ok(SpotifyAPIState::NotAuthenticated);
 
-        *self.provider.lock().await = SpotifyAPIOAuthProvider::new(None);
+        let oauth_config = self.oauth_config.lock().unwrap().clone();
+
+        let mut provider = SpotifyAPIOAuthProvider::new(None, &self.endpoints, &oauth_config);
+        provider.generation = self.generation();
+
+        *self.provider.lock().await = provider;
 
         if let Some(profile) = cached_profile {
             info!(
@@ -438,7 +797,7 @@ impl SpotifyAPI {
             );
         }
 
-        self.event_loop.send_event(AppEvent::StoreToken(None)).ok();
+        self.send_event(AppEvent::StoreToken(self.generation(), None));
     }
 
     pub fn open_accounts_page(&self) -> std::io::Result<()> {
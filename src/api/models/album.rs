@@ -0,0 +1,50 @@
+use rspotify_model::{FullAlbum, FullTrack, Id as _, Page, SimplifiedTrack};
+
+use crate::{
+    api::{error::SpotifyAPIError, SpotifyAPI},
+    state::queue::PlaybackContext,
+};
+
+#[derive(Debug, Clone)]
+pub struct AlbumDetails {
+    pub album: FullAlbum,
+
+    // Full tracks rather than the album's simplified ones,
+    // so they can go straight into the queue.
+    pub tracks: Vec<FullTrack>,
+}
+
+impl AlbumDetails {
+    pub async fn fetch(api: &SpotifyAPI, album_id: String) -> Result<AlbumDetails, SpotifyAPIError> {
+        let album = api.get_album(album_id).await?;
+
+        // The album comes with its first page of tracks, only the rest need fetching.
+        let track_ids = api.fetch_all(album.tracks.clone(), |page: Page<SimplifiedTrack>| Some(page), None)
+            .await?
+            .into_iter()
+            .filter_map(|t| t.id.map(|id| id.id().to_string()))
+            .collect();
+
+        let tracks = api.get_several_tracks(track_ids).await?;
+
+        Ok(AlbumDetails { album, tracks })
+    }
+
+    pub fn id(&self) -> String {
+        self.album.id.id().to_string()
+    }
+
+    pub fn duration_ms(&self) -> u32 {
+        self.tracks
+            .iter()
+            .map(|t| t.duration.num_milliseconds().max(0) as u32)
+            .sum()
+    }
+
+    pub fn playback_context(&self) -> PlaybackContext {
+        PlaybackContext::Album {
+            id: self.id(),
+            name: self.album.name.clone(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use rspotify_model::{FullArtist, FullTrack, Page, SimplifiedAlbum};
+use tracing::warn;
+
+use crate::api::{
+    error::SpotifyAPIError,
+    paginator::{extend_page, has_next_page},
+    SpotifyAPI,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArtistAlbumGroup {
+    Album,
+    Single,
+    Compilation,
+    AppearsOn,
+}
+
+impl ArtistAlbumGroup {
+    pub const ALL: [ArtistAlbumGroup; 4] = [
+        ArtistAlbumGroup::Album,
+        ArtistAlbumGroup::Single,
+        ArtistAlbumGroup::Compilation,
+        ArtistAlbumGroup::AppearsOn,
+    ];
+
+    // What the API calls this group in `include_groups`.
+    pub fn include_group(&self) -> &'static str {
+        match self {
+            ArtistAlbumGroup::Album => "album",
+            ArtistAlbumGroup::Single => "single",
+            ArtistAlbumGroup::Compilation => "compilation",
+            ArtistAlbumGroup::AppearsOn => "appears_on",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ArtistAlbumGroup::Album => "Albums",
+            ArtistAlbumGroup::Single => "Singles and EPs",
+            ArtistAlbumGroup::Compilation => "Compilations",
+            ArtistAlbumGroup::AppearsOn => "Appears On",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtistDiscographySection {
+    pub group: ArtistAlbumGroup,
+    pub albums: Page<SimplifiedAlbum>,
+    pub fetching_more: bool,
+}
+
+impl ArtistDiscographySection {
+    pub fn has_more(&self) -> bool {
+        has_next_page(&self.albums)
+    }
+
+    pub async fn get_more(&self, api: &SpotifyAPI) -> Result<Option<Page<SimplifiedAlbum>>, SpotifyAPIError> {
+        api.get_next_page(&self.albums, |page: Page<SimplifiedAlbum>| Some(page)).await
+    }
+
+    pub fn extend(&mut self, more: Page<SimplifiedAlbum>) {
+        extend_page(&mut self.albums, more);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArtistDetails {
+    pub artist: FullArtist,
+    pub top_tracks: Vec<FullTrack>,
+    pub discography: Vec<ArtistDiscographySection>,
+    pub related: Vec<FullArtist>,
+    pub following: bool,
+}
+
+impl ArtistDetails {
+    pub async fn fetch(api: &SpotifyAPI, artist_id: String) -> Result<ArtistDetails, SpotifyAPIError> {
+        let (artist, top_tracks) = tokio::try_join!(
+            api.get_artist(artist_id.clone()),
+            api.get_artist_top_tracks(artist_id.clone(), api.market())
+        )?;
+
+        let groups = futures::future::join_all(
+            ArtistAlbumGroup::ALL
+                .iter()
+                .map(|group| api.get_artist_albums(artist_id.clone(), group.include_group().to_string(), Some(20)))
+        ).await;
+
+        let mut discography = Vec::new();
+
+        for (group, albums) in ArtistAlbumGroup::ALL.into_iter().zip(groups) {
+            let albums = albums?;
+
+            if !albums.items.is_empty() {
+                discography.push(ArtistDiscographySection {
+                    group,
+                    albums,
+                    fetching_more: false,
+                });
+            }
+        }
+
+        // Spotify has been turning related artists off for some apps,
+        // the rest of the page is still worth showing without them.
+        let related = api.get_related_artists(artist_id.clone())
+            .await
+            .unwrap_or_else(|err| {
+                warn!("Failed to get related artists for {}: {:#?}", artist_id, err);
+
+                Vec::new()
+            });
+
+        let following = api.is_following_artist(artist_id.clone())
+            .await
+            .unwrap_or_else(|err| {
+                warn!("Failed to check if we're following artist {}: {:#?}", artist_id, err);
+
+                false
+            });
+
+        Ok(ArtistDetails {
+            artist,
+            top_tracks,
+            discography,
+            related,
+            following,
+        })
+    }
+
+    pub fn section_mut(&mut self, group: ArtistAlbumGroup) -> Option<&mut ArtistDiscographySection> {
+        self.discography.iter_mut().find(|s| s.group == group)
+    }
+}
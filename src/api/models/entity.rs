@@ -0,0 +1,89 @@
+use rspotify_model::{FullTrack, Id as _, Page, PlaylistItem};
+
+use crate::{
+    api::{error::SpotifyAPIError, SpotifyAPI},
+    state::queue::PlaybackContext,
+};
+
+use super::{album::AlbumDetails, playlists::playlist_item_track, recommendations::BrowseRecommendationItem};
+
+/// Something in Spotify's catalogue the user can open or play,
+/// e.g. from a card on the home pane.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpotifyEntity {
+    Album { id: String, name: String },
+    Artist { id: String, name: String },
+    Playlist { id: String, name: String },
+    Track(FullTrack),
+}
+
+impl SpotifyEntity {
+    pub fn from_recommendation(item: &BrowseRecommendationItem) -> Option<SpotifyEntity> {
+        match item {
+            BrowseRecommendationItem::Album(album) => album.id.as_ref().map(|id| SpotifyEntity::Album {
+                id: id.id().to_string(),
+                name: album.name.clone(),
+            }),
+            BrowseRecommendationItem::Artist(artist) => Some(SpotifyEntity::Artist {
+                id: artist.id.id().to_string(),
+                name: artist.name.clone(),
+            }),
+            BrowseRecommendationItem::Playlist(playlist) => Some(SpotifyEntity::Playlist {
+                id: playlist.id.id().to_string(),
+                name: playlist.name.clone(),
+            }),
+            BrowseRecommendationItem::Track(track) => track.id.as_ref()
+                .map(|_| SpotifyEntity::Track(track.clone())),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpotifyEntity::Album { .. } => "album",
+            SpotifyEntity::Artist { .. } => "artist",
+            SpotifyEntity::Playlist { .. } => "playlist",
+            SpotifyEntity::Track(_) => "track",
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            SpotifyEntity::Album { id, .. } => id.clone(),
+            SpotifyEntity::Artist { id, .. } => id.clone(),
+            SpotifyEntity::Playlist { id, .. } => id.clone(),
+            SpotifyEntity::Track(track) => track.id
+                .as_ref()
+                .map(|id| id.id().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://open.spotify.com/{}/{}", self.kind(), self.id())
+    }
+
+    /// The tracks to queue up when playing this, and the context to show them under.
+    pub async fn tracks(&self, api: &SpotifyAPI) -> Result<(Option<PlaybackContext>, Vec<FullTrack>), SpotifyAPIError> {
+        match self {
+            SpotifyEntity::Track(track) => Ok((None, vec![track.clone()])),
+            SpotifyEntity::Artist { id, .. } => Ok((None, api.get_artist_top_tracks(id.clone(), api.market()).await?)),
+            SpotifyEntity::Playlist { id, name } => {
+                let first = api.get_playlist_items(id.clone(), None, None).await?;
+
+                let tracks = api.fetch_all(first, |page: Page<PlaylistItem>| Some(page), None)
+                    .await?
+                    .iter()
+                    .filter_map(playlist_item_track)
+                    .cloned()
+                    .collect();
+
+                Ok((Some(PlaybackContext::Playlist { id: id.clone(), name: name.clone() }), tracks))
+            },
+            SpotifyEntity::Album { id, .. } => {
+                let album = AlbumDetails::fetch(api, id.clone()).await?;
+
+                Ok((Some(album.playback_context()), album.tracks))
+            },
+        }
+    }
+}
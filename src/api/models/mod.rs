@@ -2,3 +2,6 @@ pub mod user;
 pub mod search;
 pub mod playlists;
 pub mod recommendations;
+pub mod entity;
+pub mod album;
+pub mod artist;
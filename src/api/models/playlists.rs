@@ -0,0 +1,19 @@
+use rspotify_model::{FullTrack, PlayableItem, PlaylistItem};
+
+use super::user::UserImpl as _;
+
+/// The track in a playlist item, playlists can hold podcast episodes too
+/// which we can't play, and local files which have no album art or ID.
+pub fn playlist_item_track(item: &PlaylistItem) -> Option<&FullTrack> {
+    match &item.track {
+        Some(PlayableItem::Track(track)) => Some(track),
+        _ => None,
+    }
+}
+
+pub fn playlist_item_added_by(item: &PlaylistItem) -> String {
+    item.added_by
+        .as_ref()
+        .map(|u| u.name())
+        .unwrap_or_default()
+}
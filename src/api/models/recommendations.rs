@@ -1,7 +1,12 @@
-use rspotify_model::{Category, FullArtist, FullTrack, Page, SimplifiedAlbum, SimplifiedPlaylist};
+use rspotify_model::{Category, CategoryPlaylists, FullArtist, FullTrack, Page, SimplifiedAlbum, SimplifiedPlaylist};
 use tracing::{error, warn};
 
-use crate::api::{constants::SPOTIFY_CATEGORY_ID_MADE_FOR_YOU, error::SpotifyAPIError, SpotifyAPI};
+use crate::api::{
+    constants::SPOTIFY_CATEGORY_ID_MADE_FOR_YOU,
+    error::SpotifyAPIError,
+    paginator::{extend_page, has_next_page},
+    SpotifyAPI,
+};
 
 #[derive(Debug, Clone)]
 pub enum BrowseRecommendationSections {
@@ -29,24 +34,72 @@ pub enum BrowseRecommendationItem {
 pub struct BrowseRecommendationSection {
     pub title: String,
     pub description: Option<String>,
-    pub items: Vec<BrowseRecommendationItem>
+    pub items: Vec<BrowseRecommendationItem>,
+
+    // The page the items came from, kept so "Show more" can fetch the next one.
+    pub playlists: Option<Page<SimplifiedPlaylist>>,
+    pub fetching_more: bool,
+}
+
+impl BrowseRecommendationSection {
+    pub fn has_more(&self) -> bool {
+        self.playlists.as_ref().is_some_and(has_next_page)
+    }
+
+    pub async fn get_more(&self, api: &SpotifyAPI) -> Result<Option<Page<SimplifiedPlaylist>>, SpotifyAPIError> {
+        match &self.playlists {
+            Some(page) => api.get_next_page(page, |r: CategoryPlaylists| Some(r.playlists)).await,
+            None => Ok(None),
+        }
+    }
+
+    pub fn extend(&mut self, more: Page<SimplifiedPlaylist>) {
+        self.items.extend(
+            more.items
+                .iter()
+                .cloned()
+                .map(BrowseRecommendationItem::Playlist)
+        );
+
+        if let Some(page) = &mut self.playlists {
+            extend_page(page, more);
+        }
+    }
 }
 
 impl BrowseRecommendations {
+    pub fn section_mut(&mut self, index: usize) -> Option<&mut BrowseRecommendationSection> {
+        match &mut self.sections {
+            Some(BrowseRecommendationSections::Sections(Ok(sections))) => sections.get_mut(index),
+            _ => None,
+        }
+    }
+
     pub async fn generate(&mut self, api: &SpotifyAPI, locale: String, playlists_limit: Option<u32>) {
         self.sections = Some(BrowseRecommendationSections::Sections({
+            // The scheduler caps how many of these are in flight at once.
+            let results = futures::future::join_all(
+                self.categories
+                    .iter()
+                    .map(|category| api.get_category_playlists(category.id.clone(), Some(playlists_limit.unwrap_or(5))))
+            ).await;
+
             let mut sections: Vec<BrowseRecommendationSection> = Vec::new();
 
-            for category in self.categories.clone() {
-                match api.get_category_playlists(category.id.clone(), Some(playlists_limit.unwrap_or(5))).await {
+            for (category, result) in self.categories.clone().into_iter().zip(results) {
+                match result {
                     Ok(playlists) => {
                         let section = BrowseRecommendationSection {
                             title: category.name,
                             description: None,
                             items: playlists.items
-                                .into_iter()
+                                .iter()
+                                .cloned()
                                 .map(BrowseRecommendationItem::Playlist)
-                                .collect()
+                                .collect(),
+
+                            playlists: Some(playlists),
+                            fetching_more: false,
                         };
 
                         sections.push(section);
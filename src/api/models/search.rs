@@ -1,7 +1,21 @@
 use rspotify_model::{FullArtist, FullTrack, Page, SearchResult, SimplifiedAlbum, SimplifiedPlaylist, SimplifiedTrack};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::api::{
+    error::SpotifyAPIError,
+    paginator::{extend_page, has_next_page},
+    SpotifyAPI,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchResultsKind {
+    Albums,
+    Artists,
+    Playlists,
+    Tracks,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct SearchResults {
     pub albums: Option<Page<SimplifiedAlbum>>,
     pub artists: Option<Page<FullArtist>>,
@@ -18,4 +32,65 @@ impl SearchResults {
             self.tracks.is_none()
         )
     }
-}
\ No newline at end of file
+
+    pub fn has_more(&self, kind: SearchResultsKind) -> bool {
+        match kind {
+            SearchResultsKind::Albums => self.albums.as_ref().is_some_and(has_next_page),
+            SearchResultsKind::Artists => self.artists.as_ref().is_some_and(has_next_page),
+            SearchResultsKind::Playlists => self.playlists.as_ref().is_some_and(has_next_page),
+            SearchResultsKind::Tracks => self.tracks.as_ref().is_some_and(has_next_page),
+        }
+    }
+
+    /// Where `get_more` would fetch the next page of `kind` from. A page that
+    /// comes back only follows on from these results if this hasn't changed.
+    pub fn next_page_url(&self, kind: SearchResultsKind) -> Option<&str> {
+        match kind {
+            SearchResultsKind::Albums => self.albums.as_ref().and_then(|p| p.next.as_deref()),
+            SearchResultsKind::Artists => self.artists.as_ref().and_then(|p| p.next.as_deref()),
+            SearchResultsKind::Playlists => self.playlists.as_ref().and_then(|p| p.next.as_deref()),
+            SearchResultsKind::Tracks => self.tracks.as_ref().and_then(|p| p.next.as_deref()),
+        }
+    }
+
+    /// Fetches the next page of one kind of result, the returned
+    /// results only contain that page, ready to pass to `extend`.
+    pub async fn get_more(&self, api: &SpotifyAPI, kind: SearchResultsKind) -> Result<SearchResults, SpotifyAPIError> {
+        let mut more = SearchResults::default();
+
+        match kind {
+            SearchResultsKind::Albums => if let Some(page) = &self.albums {
+                more.albums = api.get_next_page(page, |r: SearchResults| r.albums).await?;
+            },
+            SearchResultsKind::Artists => if let Some(page) = &self.artists {
+                more.artists = api.get_next_page(page, |r: SearchResults| r.artists).await?;
+            },
+            SearchResultsKind::Playlists => if let Some(page) = &self.playlists {
+                more.playlists = api.get_next_page(page, |r: SearchResults| r.playlists).await?;
+            },
+            SearchResultsKind::Tracks => if let Some(page) = &self.tracks {
+                more.tracks = api.get_next_page(page, |r: SearchResults| r.tracks).await?;
+            },
+        }
+
+        Ok(more)
+    }
+
+    pub fn extend(&mut self, more: SearchResults) {
+        if let (Some(page), Some(next)) = (&mut self.albums, more.albums) {
+            extend_page(page, next);
+        }
+
+        if let (Some(page), Some(next)) = (&mut self.artists, more.artists) {
+            extend_page(page, next);
+        }
+
+        if let (Some(page), Some(next)) = (&mut self.playlists, more.playlists) {
+            extend_page(page, next);
+        }
+
+        if let (Some(page), Some(next)) = (&mut self.tracks, more.tracks) {
+            extend_page(page, next);
+        }
+    }
+}
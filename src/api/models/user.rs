@@ -5,6 +5,7 @@ pub trait UserImpl {
     fn name(&self) -> String;
     fn email_safe(&self) -> String;
     fn country_safe(&self) -> String;
+    fn market(&self) -> Option<String>;
 }
 
 impl UserImpl for PublicUser {
@@ -20,6 +21,10 @@ impl UserImpl for PublicUser {
     fn country_safe(&self) -> String {
         "<none>".to_string()
     }
+
+    fn market(&self) -> Option<String> {
+        None
+    }
 }
 
 impl UserImpl for PrivateUser {
@@ -37,4 +42,12 @@ impl UserImpl for PrivateUser {
             .and_then(|c| format!("{:?}", c).into())
             .unwrap_or("<none>".to_string())
     }
-}
\ No newline at end of file
+
+    // The country as the ISO code the API takes for `market`,
+    // which is how it's serialized, unlike its Debug name.
+    fn market(&self) -> Option<String> {
+        self.country
+            .and_then(|c| serde_json::to_value(c).ok())
+            .and_then(|v| v.as_str().map(|c| c.to_string()))
+    }
+}
@@ -0,0 +1,146 @@
+use futures::{stream, Stream, TryStreamExt};
+use reqwest::Method;
+use rspotify_model::Page;
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use super::{constants::SPOTIFY_API_URL, endpoints::SpotifyAPIEndpoints, error::SpotifyAPIError, SpotifyAPI};
+
+enum SpotifyAPIPaginatorNext<T> {
+    Page(Page<T>),
+    Route(String),
+    Done,
+}
+
+/// Works out the route for the page after `page`, following its `next` link,
+/// or stepping the offset when Spotify leaves `next` out but there's more to get.
+pub fn next_page_route<T>(endpoints: &SpotifyAPIEndpoints, page: &Page<T>) -> Option<String> {
+    let next_url = match &page.next {
+        Some(next) => Url::parse(next).ok()?,
+        None => {
+            let next_offset = page.offset + page.items.len() as u32;
+
+            if page.items.is_empty() || next_offset >= page.total {
+                return None;
+            }
+
+            let mut url = Url::parse(&page.href).ok()?;
+
+            let query = url.query_pairs()
+                .filter(|(key, _)| key != "offset" && key != "limit")
+                .into_owned()
+                .collect::<Vec<(String, String)>>();
+
+            url.query_pairs_mut()
+                .clear()
+                .extend_pairs(query)
+                .append_pair("offset", &next_offset.to_string())
+                .append_pair("limit", &page.limit.to_string());
+
+            url
+        }
+    };
+
+    // Links always point at api.spotify.com, rebase them onto whatever
+    // API URL we're configured with.
+    let default_api_path = Url::parse(SPOTIFY_API_URL).unwrap().path().to_string();
+
+    let route = next_url.path()
+        .strip_prefix(endpoints.api_url.path())
+        .or(next_url.path().strip_prefix(default_api_path.as_str()))?;
+
+    Some(match next_url.query() {
+        Some(query) => format!("/{}?{}", route, query),
+        None => format!("/{}", route),
+    })
+}
+
+/// Returns whether there are more items to fetch after `page`.
+pub fn has_next_page<T>(page: &Page<T>) -> bool {
+    page.next.is_some() || (!page.items.is_empty() && page.offset + (page.items.len() as u32) < page.total)
+}
+
+impl SpotifyAPI {
+    /// Fetches the page after `page`, if there is one. `extract` pulls the page out
+    /// of the response, which for endpoints like /search is wrapped in another object.
+    pub async fn get_next_page<T, R>(&self, page: &Page<T>, extract: impl Fn(R) -> Option<Page<T>>) -> Result<Option<Page<T>>, SpotifyAPIError>
+    where
+        R: DeserializeOwned,
+    {
+        match next_page_route(&self.endpoints, page) {
+            Some(route) => Ok(extract(self.request::<R>(Method::GET, route, None).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lazily walks every page starting from `first`, yielding each page's items
+    /// as they come in. Stops after `max_items` when given, or on the first error.
+    pub fn paginate<T, R, F>(&self, first: Page<T>, extract: F, max_items: Option<usize>) -> impl Stream<Item = Result<Vec<T>, SpotifyAPIError>>
+    where
+        R: DeserializeOwned,
+        F: Fn(R) -> Option<Page<T>> + Clone,
+    {
+        let api = self.clone();
+
+        stream::unfold(
+            (SpotifyAPIPaginatorNext::Page(first), max_items),
+            move |(next, remaining)| {
+                let api = api.clone();
+                let extract = extract.clone();
+
+                async move {
+                    if remaining == Some(0) {
+                        return None;
+                    }
+
+                    let mut page = match next {
+                        SpotifyAPIPaginatorNext::Page(page) => page,
+                        SpotifyAPIPaginatorNext::Route(route) => {
+                            match api.request::<R>(Method::GET, route, None).await.map(&extract) {
+                                Ok(Some(page)) => page,
+                                Ok(None) => return None,
+                                Err(err) => return Some((Err(err), (SpotifyAPIPaginatorNext::Done, remaining))),
+                            }
+                        },
+                        SpotifyAPIPaginatorNext::Done => return None,
+                    };
+
+                    let next_route = next_page_route(&api.endpoints, &page);
+
+                    let mut items = std::mem::take(&mut page.items);
+
+                    if let Some(remaining) = remaining {
+                        items.truncate(remaining);
+                    }
+
+                    let remaining = remaining.map(|r| r - items.len());
+
+                    let next = match next_route {
+                        Some(route) if remaining != Some(0) => SpotifyAPIPaginatorNext::Route(route),
+                        _ => SpotifyAPIPaginatorNext::Done,
+                    };
+
+                    Some((Ok(items), (next, remaining)))
+                }
+            },
+        )
+    }
+
+    /// Collects every item starting from `first`, up to `max_items`.
+    pub async fn fetch_all<T, R, F>(&self, first: Page<T>, extract: F, max_items: Option<usize>) -> Result<Vec<T>, SpotifyAPIError>
+    where
+        R: DeserializeOwned,
+        F: Fn(R) -> Option<Page<T>> + Clone,
+    {
+        self.paginate(first, extract, max_items)
+            .try_concat()
+            .await
+    }
+}
+
+/// Appends `next` onto `page`, so a "Show more" can keep extending one page.
+pub fn extend_page<T>(page: &mut Page<T>, next: Page<T>) {
+    page.items.extend(next.items);
+    page.next = next.next;
+    page.total = next.total;
+}
@@ -0,0 +1,405 @@
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use rspotify_model::{CurrentPlaybackContext, CursorBasedPage, Device, DevicePayload, FullTrack, Id as _, PlayHistory};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::state::queue::{PlaybackContext, RepeatMode};
+
+use super::{error::SpotifyAPIError, utils, SpotifyAPI};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpotifyAPIPlayRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uris: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<SpotifyAPIPlayOffset>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_ms: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum SpotifyAPIPlayOffset {
+    Position { position: usize },
+    Uri { uri: String },
+}
+
+impl SpotifyAPIPlayRequest {
+    /// Plays `tracks` from `start_index`. Albums and playlists are played as
+    /// themselves, so the device carries on through them and shows where the
+    /// track came from, anything else is sent as a list of tracks.
+    pub fn from_context(context: Option<&PlaybackContext>, tracks: &[FullTrack], start_index: usize) -> Self {
+        let context_uri = match context {
+            Some(PlaybackContext::Album { id, .. }) => Some(format!("spotify:album:{}", id)),
+            Some(PlaybackContext::Playlist { id, .. }) => Some(format!("spotify:playlist:{}", id)),
+            _ => None,
+        };
+
+        if let Some(context_uri) = context_uri {
+            // Our copy of a playlist leaves out episodes, so positions
+            // don't line up with Spotify's, the URI always does.
+            let offset = tracks
+                .get(start_index)
+                .and_then(|t| t.id.as_ref())
+                .map(|id| SpotifyAPIPlayOffset::Uri { uri: id.uri() });
+
+            return SpotifyAPIPlayRequest {
+                context_uri: Some(context_uri),
+                offset,
+                ..Default::default()
+            };
+        }
+
+        // Tracks without IDs can't be sent, count where we start from
+        // among the ones left so the offset still lands on the same track.
+        let position = tracks
+            .iter()
+            .take(start_index)
+            .filter(|t| t.id.is_some())
+            .count();
+
+        let uris = tracks
+            .iter()
+            .filter_map(|t| t.id.as_ref().map(|id| id.uri()))
+            .collect::<Vec<String>>();
+
+        SpotifyAPIPlayRequest {
+            uris: Some(uris),
+            offset: Some(SpotifyAPIPlayOffset::Position { position }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Which side of a moment in time to page recently played tracks from,
+/// as a Unix timestamp in milliseconds. Spotify only takes one at a time.
+#[derive(Debug, Clone, Copy)]
+pub enum SpotifyAPIHistoryCursor {
+    Before(i64),
+    After(i64),
+}
+
+// Wrappers for the /me/player endpoints, these control whichever
+// Connect device is active on the account, not our own playback.
+impl SpotifyAPI {
+    /// Returns None when nothing is playing on any device.
+    pub async fn get_playback_state(&self) -> Result<Option<CurrentPlaybackContext>, SpotifyAPIError> {
+        self.request::<Option<CurrentPlaybackContext>>(Method::GET, "/me/player".to_string(), None).await
+    }
+
+    pub async fn get_available_devices(&self) -> Result<Vec<Device>, SpotifyAPIError> {
+        self.request::<DevicePayload>(Method::GET, "/me/player/devices".to_string(), None).await
+            .map(|r| r.devices)
+    }
+
+    pub async fn transfer_playback(&self, device_id: String, play: bool) -> Result<(), SpotifyAPIError> {
+        let body = json!({
+            "device_ids": [device_id],
+            "play": play
+        });
+
+        self.request_with_body::<()>(Method::PUT, "/me/player".to_string(), None, Some(body)).await
+    }
+
+    /// Resumes playback when `play_request` is None.
+    pub async fn start_playback(&self, device_id: Option<String>, play_request: Option<SpotifyAPIPlayRequest>) -> Result<(), SpotifyAPIError> {
+        let query = utils::create_hashmap(&[
+            ("device_id", device_id.as_ref())
+        ]);
+
+        let body = play_request
+            .map(|r| serde_json::to_value(r).map_err(|e| SpotifyAPIError::SerdeError(std::sync::Arc::new(e))))
+            .transpose()?;
+
+        self.request_with_body::<()>(Method::PUT, "/me/player/play".to_string(), Some(query), body).await
+    }
+
+    pub async fn pause_playback(&self, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let query = utils::create_hashmap(&[
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::PUT, "/me/player/pause".to_string(), Some(query)).await
+    }
+
+    pub async fn skip_to_next(&self, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let query = utils::create_hashmap(&[
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::POST, "/me/player/next".to_string(), Some(query)).await
+    }
+
+    pub async fn skip_to_previous(&self, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let query = utils::create_hashmap(&[
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::POST, "/me/player/previous".to_string(), Some(query)).await
+    }
+
+    pub async fn seek_to_position(&self, position_ms: u32, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let position_ms = position_ms.to_string();
+
+        let query = utils::create_hashmap(&[
+            ("position_ms", Some(&position_ms)),
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::PUT, "/me/player/seek".to_string(), Some(query)).await
+    }
+
+    /// `volume` is a fraction between 0.0 and 1.0, like SpotifyPlayback uses.
+    pub async fn set_playback_volume(&self, volume: f64, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let volume_percent = ((volume.clamp(0.0, 1.0) * 100.0).round() as u8).to_string();
+
+        let query = utils::create_hashmap(&[
+            ("volume_percent", Some(&volume_percent)),
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::PUT, "/me/player/volume".to_string(), Some(query)).await
+    }
+
+    pub async fn set_playback_shuffle(&self, shuffle: bool, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let state = shuffle.to_string();
+
+        let query = utils::create_hashmap(&[
+            ("state", Some(&state)),
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::PUT, "/me/player/shuffle".to_string(), Some(query)).await
+    }
+
+    pub async fn set_playback_repeat(&self, repeat: RepeatMode, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let state = match repeat {
+            RepeatMode::Off => "off",
+            RepeatMode::Context => "context",
+            RepeatMode::Track => "track",
+        }
+        .to_string();
+
+        let query = utils::create_hashmap(&[
+            ("state", Some(&state)),
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::PUT, "/me/player/repeat".to_string(), Some(query)).await
+    }
+
+    /// Spotify only remembers the last 50 or so plays, newest first.
+    pub async fn get_recently_played(&self, limit: Option<u32>, cursor: Option<SpotifyAPIHistoryCursor>) -> Result<CursorBasedPage<PlayHistory>, SpotifyAPIError> {
+        let limit = limit.map(|x| x.to_string());
+
+        let (before, after) = match cursor {
+            Some(SpotifyAPIHistoryCursor::Before(before)) => (Some(before.to_string()), None),
+            Some(SpotifyAPIHistoryCursor::After(after)) => (None, Some(after.to_string())),
+            None => (None, None),
+        };
+
+        let query = utils::create_hashmap(&[
+            ("limit", limit.as_ref()),
+            ("before", before.as_ref()),
+            ("after", after.as_ref())
+        ]);
+
+        self.request::<CursorBasedPage<PlayHistory>>(Method::GET, "/me/player/recently-played".to_string(), Some(query)).await
+    }
+
+    /// Pages back through recently played until it reaches `since`,
+    /// or Spotify runs out of history, whichever comes first.
+    pub async fn get_all_recently_played(&self, since: Option<DateTime<Utc>>) -> Result<Vec<PlayHistory>, SpotifyAPIError> {
+        let mut plays = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self.get_recently_played(Some(50), cursor).await?;
+
+            let reached_since = page.items
+                .iter()
+                .any(|p| since.is_some_and(|since| p.played_at <= since));
+
+            plays.extend(
+                page.items
+                    .into_iter()
+                    .filter(|p| since.is_none_or(|since| p.played_at > since))
+            );
+
+            let before = page.cursors
+                .and_then(|c| c.before)
+                .and_then(|b| b.parse::<i64>().ok());
+
+            match before {
+                Some(before) if !reached_since && page.next.is_some() => cursor = Some(SpotifyAPIHistoryCursor::Before(before)),
+                _ => break,
+            }
+        }
+
+        Ok(plays)
+    }
+
+    pub async fn add_item_to_queue(&self, uri: String, device_id: Option<String>) -> Result<(), SpotifyAPIError> {
+        let query = utils::create_hashmap(&[
+            ("uri", Some(&uri)),
+            ("device_id", device_id.as_ref())
+        ]);
+
+        self.request::<()>(Method::POST, "/me/player/queue".to_string(), Some(query)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::api::{
+        cache::SpotifyAPICache,
+        mock::{SpotifyAPIMockServer, MOCK_REFRESH_TOKEN},
+        providers::oauth2::SpotifyAPIOAuthConfig,
+        testing,
+    };
+
+    fn track(id: Option<&str>) -> FullTrack {
+        testing::track(id, "Track")
+    }
+
+    const FIRST: &str = "11dFghVXANMlKmJXsNCbNl";
+    const SECOND: &str = "4iV5W9uYEdYUVa79Axb7Rh";
+
+    #[test]
+    fn plays_albums_as_context() {
+        let context = PlaybackContext::Album { id: "4aawyAB9vmqN3uQ7FjRGTy".to_string(), name: "Album".to_string() };
+        let tracks = vec![track(Some(FIRST)), track(Some(SECOND))];
+
+        let request = SpotifyAPIPlayRequest::from_context(Some(&context), &tracks, 1);
+
+        assert_eq!(serde_json::to_value(request).unwrap(), json!({
+            "context_uri": "spotify:album:4aawyAB9vmqN3uQ7FjRGTy",
+            "offset": { "uri": format!("spotify:track:{}", SECOND) }
+        }));
+    }
+
+    #[test]
+    fn plays_playlists_as_context() {
+        let context = PlaybackContext::Playlist { id: "37i9dQZF1DXcBWIGoYBM5M".to_string(), name: "Playlist".to_string() };
+        let tracks = vec![track(Some(FIRST))];
+
+        let request = SpotifyAPIPlayRequest::from_context(Some(&context), &tracks, 0);
+
+        assert_eq!(request.context_uri.as_deref(), Some("spotify:playlist:37i9dQZF1DXcBWIGoYBM5M"));
+        assert!(request.uris.is_none());
+    }
+
+    #[test]
+    fn offsets_uris_past_tracks_without_ids() {
+        let tracks = vec![track(None), track(Some(FIRST)), track(None), track(Some(SECOND))];
+
+        let request = SpotifyAPIPlayRequest::from_context(Some(&PlaybackContext::LikedSongs), &tracks, 3);
+
+        assert_eq!(serde_json::to_value(request).unwrap(), json!({
+            "uris": [format!("spotify:track:{}", FIRST), format!("spotify:track:{}", SECOND)],
+            "offset": { "position": 1 }
+        }));
+    }
+
+    async fn login(server: &SpotifyAPIMockServer) -> SpotifyAPI {
+        let mut api = SpotifyAPI::new(
+            None,
+            Some(MOCK_REFRESH_TOKEN.to_string()),
+            server.endpoints(),
+            SpotifyAPIOAuthConfig::default()
+        );
+
+        api.cache = SpotifyAPICache::new(None, 0);
+        api.login(None).await;
+
+        api
+    }
+
+    #[tokio::test]
+    async fn gets_playback_state_and_devices() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server).await;
+
+        // 204 No Content, nothing is playing.
+        assert!(api.get_playback_state().await.unwrap().is_none());
+
+        let devices = api.get_available_devices().await.unwrap();
+
+        assert_eq!(devices.len(), 1);
+        assert!(devices[0].is_active);
+    }
+
+    #[tokio::test]
+    async fn doesnt_cache_playback_state() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut api = login(&server).await;
+        api.cache = SpotifyAPICache::new(Some(dir.path().to_path_buf()), 1024 * 1024);
+
+        for _ in 0..2 {
+            api.get_playback_state().await.unwrap();
+            api.get_available_devices().await.unwrap();
+        }
+
+        assert_eq!(server.received("GET", "/v1/me/player").len(), 2);
+        assert_eq!(server.received("GET", "/v1/me/player/devices").len(), 2);
+        assert_eq!(api.cache.size_bytes(), 0);
+
+        api.get_current_user_playlists(None, None).await.unwrap();
+
+        assert!(api.cache.size_bytes() > 0);
+    }
+
+    #[tokio::test]
+    async fn sends_playback_commands() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server).await;
+        let device_id = Some("mockdevice".to_string());
+
+        let play_request = SpotifyAPIPlayRequest {
+            uris: Some(vec![format!("spotify:track:{}", FIRST)]),
+            ..Default::default()
+        };
+
+        api.start_playback(device_id.clone(), Some(play_request)).await.unwrap();
+        api.pause_playback(device_id.clone()).await.unwrap();
+        api.seek_to_position(1500, device_id.clone()).await.unwrap();
+        api.set_playback_volume(0.5, device_id.clone()).await.unwrap();
+        api.set_playback_repeat(RepeatMode::Context, device_id.clone()).await.unwrap();
+        api.skip_to_next(device_id.clone()).await.unwrap();
+
+        let play = server.received("PUT", "/v1/me/player/play");
+
+        assert_eq!(play.len(), 1);
+        assert_eq!(play[0].query.get("device_id").map(|d| d.as_str()), Some("mockdevice"));
+        assert_eq!(serde_json::from_str::<Value>(&play[0].body).unwrap()["uris"][0], format!("spotify:track:{}", FIRST));
+
+        assert_eq!(server.received("PUT", "/v1/me/player/seek")[0].query.get("position_ms").map(|p| p.as_str()), Some("1500"));
+        assert_eq!(server.received("PUT", "/v1/me/player/volume")[0].query.get("volume_percent").map(|v| v.as_str()), Some("50"));
+        assert_eq!(server.received("PUT", "/v1/me/player/repeat")[0].query.get("state").map(|s| s.as_str()), Some("context"));
+        assert_eq!(server.received("PUT", "/v1/me/player/pause").len(), 1);
+        assert_eq!(server.received("POST", "/v1/me/player/next").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn gets_all_recently_played() {
+        let server = SpotifyAPIMockServer::start().unwrap();
+        let api = login(&server).await;
+
+        let plays = api.get_all_recently_played(None).await.unwrap();
+
+        assert_eq!(plays.len(), 2);
+        assert!(plays[0].played_at > plays[1].played_at);
+
+        let since = plays[1].played_at;
+
+        assert_eq!(api.get_all_recently_played(Some(since)).await.unwrap().len(), 1);
+    }
+}
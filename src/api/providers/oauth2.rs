@@ -1,16 +1,17 @@
-use std::{borrow::{Borrow, Cow}, cell::RefCell, collections::HashSet, default, fmt::Display, io::{BufRead, Write}, net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener}, rc::Rc, sync::{Arc, Mutex}};
+use std::{borrow::{Borrow, Cow}, cell::RefCell, collections::HashSet, default, fmt::Display, net::{IpAddr, Ipv4Addr, SocketAddr}, rc::Rc, sync::{Arc, Mutex}, time::Instant};
 
 use std::io;
-use std::io::BufReader;
+use tokio::{io::{AsyncBufReadExt, AsyncWriteExt}, net::{TcpListener, TcpStream}, sync::{mpsc, oneshot}};
 use chrono::Duration;
 use rand::Rng;
 use oauth2::{basic::{BasicClient, BasicErrorResponse, BasicErrorResponseType, BasicRequestTokenError, BasicTokenResponse, BasicTokenType}, reqwest::async_http_client, AuthUrl, AuthorizationCode, ClientId, CsrfToken, EmptyExtraTokenFields, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken, RefreshTokenRequest, RequestTokenError, Scope, StandardErrorResponse, StandardTokenResponse, TokenResponse, TokenUrl};
+use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use tracing::{error, info, warn};
 use librespot::{core::SessionConfig, protocol::authentication::AuthenticationType};
 use url::Url;
 
-use crate::{api::constants::{SPOTIFY_CLIENT_ID, SPOTIFY_OAUTH_AUTHORISE_URL, SPOTIFY_OAUTH_TOKEN_URL, SPOTIFY_SCOPES}, constants::{UI_APP_NAME, UI_APP_VERSION}};
+use crate::{api::{constants::{SPOTIFY_CLIENT_ID, SPOTIFY_SCOPES, SPOTIFY_OAUTH_CALLBACK_TIMEOUT_SECS, SPOTIFY_OAUTH_REDIRECT_HOST, SPOTIFY_OAUTH_REDIRECT_PORT, SPOTIFY_OAUTH_REDIRECT_PORT_FALLBACKS, SPOTIFY_OAUTH_REQUEST_TIMEOUT_SECS, SPOTIFY_TOKEN_REFRESH_MARGIN_SECS}, endpoints::SpotifyAPIEndpoints}, constants::{UI_APP_NAME, UI_APP_VERSION}};
 
 #[derive(Debug, Display, Default, Clone)]
 pub enum SpotifyAPIOAuthError {
@@ -25,7 +26,10 @@ pub enum SpotifyAPIOAuthError {
         oauth2::reqwest::AsyncHttpClientError,
         StandardErrorResponse<BasicErrorResponseType>,
     >>),
-    OAuth2ServerBindFailure(Arc<io::Error>),
+    OAuth2ServerBindFailure(String, Arc<io::Error>),
+    CsrfStateMismatch,
+    CallbackTimeout,
+    Cancelled,
 }
 
 #[derive(Debug)]
@@ -35,36 +39,120 @@ pub enum SpotifyAPIOAuthClient {
     Authenticating,
 }
 
+/// What to do when the redirect port is already taken.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "strategy", rename_all = "lowercase")]
+pub enum SpotifyAPIOAuthPortFallback {
+    // Only ever use the redirect port, e.g. when it's the one registered on your app.
+    None,
+    // Try each port in the range, all of them need registering as redirect URIs.
+    Range { start: u16, end: u16 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpotifyAPIOAuthConfig {
+    pub client_id: String,
+    pub redirect_host: String,
+    pub redirect_port: u16,
+    pub port_fallback: SpotifyAPIOAuthPortFallback,
+}
+
+impl Default for SpotifyAPIOAuthConfig {
+    fn default() -> Self {
+        SpotifyAPIOAuthConfig {
+            client_id: SPOTIFY_CLIENT_ID.to_string(),
+            redirect_host: SPOTIFY_OAUTH_REDIRECT_HOST.to_string(),
+            redirect_port: SPOTIFY_OAUTH_REDIRECT_PORT,
+            port_fallback: SpotifyAPIOAuthPortFallback::Range {
+                start: SPOTIFY_OAUTH_REDIRECT_PORT + 1,
+                end: SPOTIFY_OAUTH_REDIRECT_PORT + SPOTIFY_OAUTH_REDIRECT_PORT_FALLBACKS,
+            },
+        }
+    }
+}
+
+impl SpotifyAPIOAuthConfig {
+    pub fn redirect_uri(&self, port: u16) -> String {
+        format!("http://{}:{}/login", self.redirect_host, port)
+    }
+
+    /// Ports to try binding the callback server to, in order.
+    pub fn candidate_ports(&self) -> Vec<u16> {
+        let mut ports = vec![self.redirect_port];
+
+        if let SpotifyAPIOAuthPortFallback::Range { start, end } = self.port_fallback {
+            ports.extend((start..=end).filter(|p| *p != self.redirect_port));
+        }
+
+        ports
+    }
+
+    fn describe_ports(&self) -> String {
+        match self.port_fallback {
+            SpotifyAPIOAuthPortFallback::Range { start, end } if start <= end => {
+                format!("{}:{} or {}-{}", self.redirect_host, self.redirect_port, start, end)
+            },
+            _ => format!("{}:{}", self.redirect_host, self.redirect_port),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct SpotifyAPIOAuthProvider {
     pub client: Result<SpotifyAPIOAuthClient, SpotifyAPIOAuthError>,
     pub token: Option<StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>>,
+    pub token_expires_at: Option<Instant>,
     pub pkce_verifier: PkceCodeVerifier,
+    pub csrf_token: CsrfToken,
     pub auth_url: Url,
+    pub callback_timeout: std::time::Duration,
     pub port: u16,
-    pub init_refresh_token: Option<String>
+    pub config: SpotifyAPIOAuthConfig,
+    pub endpoints: SpotifyAPIEndpoints,
+    pub init_refresh_token: Option<String>,
+
+    // The API's session generation when this provider was made, so tokens
+    // it hands out can't be filed under whoever logs in next.
+    pub generation: u64,
 }
 
 impl Default for SpotifyAPIOAuthProvider {
     fn default() -> Self {
-        Self::new(None)
+        Self::new(None, &SpotifyAPIEndpoints::default(), &SpotifyAPIOAuthConfig::default())
     }
 }
 
 impl SpotifyAPIOAuthProvider {
-    pub fn new(init_refresh_token: Option<String>) -> Self {
-        let port = rand::thread_rng().gen_range(1000..65535);
-        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), port);
-        let redirect_uri = format!("http://{addr}/login");
+    pub fn new(init_refresh_token: Option<String>, endpoints: &SpotifyAPIEndpoints, config: &SpotifyAPIOAuthConfig) -> Self {
+        let port = config.redirect_port;
+        let (client, pkce_verifier, csrf_token, auth_url) = Self::create_client(endpoints, config, port);
+
+        Self {
+            client: Ok(SpotifyAPIOAuthClient::Unauthenticated(client)),
+            token: None,
+            token_expires_at: None,
+            pkce_verifier,
+            csrf_token,
+            auth_url,
+            callback_timeout: std::time::Duration::from_secs(SPOTIFY_OAUTH_CALLBACK_TIMEOUT_SECS),
+            port,
+            config: config.clone(),
+            endpoints: endpoints.clone(),
+            init_refresh_token,
 
+            generation: 0,
+        }
+    }
+
+    fn create_client(endpoints: &SpotifyAPIEndpoints, config: &SpotifyAPIOAuthConfig, port: u16) -> (BasicClient, PkceCodeVerifier, CsrfToken, Url) {
         let client =
             BasicClient::new(
-                ClientId::new(SPOTIFY_CLIENT_ID.to_string()),
+                ClientId::new(config.client_id.clone()),
                 None,
-                AuthUrl::new(SPOTIFY_OAUTH_AUTHORISE_URL.to_string()).unwrap(),
-                Some(TokenUrl::new(SPOTIFY_OAUTH_TOKEN_URL.to_string()).unwrap())
+                AuthUrl::from_url(endpoints.oauth_authorise_url.clone()),
+                Some(TokenUrl::from_url(endpoints.oauth_token_url.clone()))
             )
-                .set_redirect_uri(RedirectUrl::new(redirect_uri).unwrap());
+                .set_redirect_uri(RedirectUrl::new(config.redirect_uri(port)).unwrap());
 
         let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
 
@@ -79,18 +167,53 @@ impl SpotifyAPIOAuthProvider {
             .add_scopes(scopes)
             .url();
 
-        Self {
-            client: Ok(SpotifyAPIOAuthClient::Unauthenticated(client)),
-            token: None,
-            pkce_verifier,
-            auth_url,
-            port,
-            init_refresh_token
-        }
+        (client, pkce_verifier, csrf_token, auth_url)
     }
 
-    pub fn get_addr(&self) -> SocketAddr {
-        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), self.port)
+    /// Binds the callback server to the first free candidate port, and points the
+    /// redirect URI at it. Has to happen before we send anyone to `auth_url`.
+    pub async fn bind_callback_listener(&mut self) -> Result<TcpListener, SpotifyAPIOAuthError> {
+        if !matches!(self.client, Ok(SpotifyAPIOAuthClient::Unauthenticated(_))) {
+            return Err(SpotifyAPIOAuthError::BadOperation("Cannot log in from an already authenticated client!"));
+        }
+
+        let mut last_error = None;
+
+        for port in self.config.candidate_ports() {
+            match TcpListener::bind((self.config.redirect_host.as_str(), port)).await {
+                Ok(listener) => {
+                    if port != self.port {
+                        info!("Port {} is unavailable, redirecting OAuth2 login to port {} instead.", self.port, port);
+
+                        let (client, pkce_verifier, csrf_token, auth_url) = Self::create_client(&self.endpoints, &self.config, port);
+
+                        self.client = Ok(SpotifyAPIOAuthClient::Unauthenticated(client));
+                        self.pkce_verifier = pkce_verifier;
+                        self.csrf_token = csrf_token;
+                        self.auth_url = auth_url;
+                        self.port = port;
+                    }
+
+                    return Ok(listener);
+                },
+                Err(err) => {
+                    warn!("Failed to bind OAuth2 callback server to {}:{}: {}", self.config.redirect_host, port, err);
+
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        let err = last_error.unwrap_or(io::Error::new(io::ErrorKind::InvalidInput, "No ports to try."));
+
+        Err(SpotifyAPIOAuthError::OAuth2ServerBindFailure(
+            format!(
+                "Could not listen for the login redirect on {}: {}. Close whatever is using the port, or pick another in Preferences.",
+                self.config.describe_ports(),
+                err
+            ),
+            Arc::new(err)
+        ))
     }
 
     pub fn get_auth_error(&self) -> Option<SpotifyAPIOAuthError> {
@@ -102,19 +225,40 @@ impl SpotifyAPIOAuthProvider {
         opt_err.cloned()
     }
 
+    fn set_token(&mut self, mut token: StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>) {
+        // Spotify doesn't always hand back a new refresh token, keep using the old one.
+        if token.refresh_token().is_none() {
+            token.set_refresh_token(self.token.as_ref().and_then(|t| t.refresh_token().cloned()));
+        }
+
+        self.token_expires_at = token.expires_in().map(|expires_in| Instant::now() + expires_in);
+        self.token = Some(token);
+    }
+
+    /// Whether the access token has expired, or is about to.
+    pub fn is_token_expiring(&self) -> bool {
+        let margin = std::time::Duration::from_secs(SPOTIFY_TOKEN_REFRESH_MARGIN_SECS);
+
+        self.token_expires_at
+            .is_some_and(|expires_at| Instant::now() + margin >= expires_at)
+    }
+
     pub async fn token(&mut self, should_refresh: Option<bool>) -> Result<StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>, SpotifyAPIOAuthError> {
         match &self.client {
             Ok(SpotifyAPIOAuthClient::Authenticated(client)) => {
                 if let Some(token) = self.token.clone() {
-                    if should_refresh.unwrap_or(false) {
+                    if should_refresh.unwrap_or(false) || self.is_token_expiring() {
                         info!("Refreshing your access token...");
 
-                        let refresh_token = token.refresh_token();
+                        let refresh_token = token.refresh_token()
+                            .ok_or(SpotifyAPIOAuthError::AuthenticationFailure("No refresh token to refresh with.".to_string()))?;
 
-                        self.token = Some(client.exchange_refresh_token(refresh_token.unwrap())
+                        let token_result = client.exchange_refresh_token(refresh_token)
                             .request_async(async_http_client)
                             .await
-                            .map_err(|e| SpotifyAPIOAuthError::CodeExchangeFailure(Arc::new(e)))?);
+                            .map_err(|e| SpotifyAPIOAuthError::CodeExchangeFailure(Arc::new(e)))?;
+
+                        self.set_token(token_result);
                     }
 
                     return Ok(
@@ -131,6 +275,16 @@ impl SpotifyAPIOAuthProvider {
         }
     }
 
+    /// Refreshes the access token after `rejected_access_token` was turned down,
+    /// unless someone else already refreshed it while we waited for the lock.
+    pub async fn refresh_rejected_token(&mut self, rejected_access_token: &str) -> Result<StandardTokenResponse<EmptyExtraTokenFields, BasicTokenType>, SpotifyAPIOAuthError> {
+        let is_current = self.token
+            .as_ref()
+            .is_some_and(|t| t.access_token().secret() == rejected_access_token);
+
+        self.token(Some(is_current)).await
+    }
+
     pub async fn authenticate_client_from_code(
         &mut self,
         code: String,
@@ -147,6 +301,13 @@ impl SpotifyAPIOAuthProvider {
             Err(err) => return Err(err.clone()), // Clone the error for returning
         }
 
+        // Make sure this redirect came from the login we started, not someone else's.
+        if csrf_state != *self.csrf_token.secret() {
+            warn!("OAuth2 callback state does not match, ignoring the authorization code.");
+
+            return Err(SpotifyAPIOAuthError::CsrfStateMismatch);
+        }
+
         if let Ok(SpotifyAPIOAuthClient::Unauthenticated(client)) = std::mem::replace(
             &mut self.client,
             Ok(SpotifyAPIOAuthClient::Authenticating)
@@ -163,7 +324,7 @@ impl SpotifyAPIOAuthProvider {
                 .await
                 .map_err(|e| SpotifyAPIOAuthError::CodeExchangeFailure(Arc::new(e)))?;
 
-            self.token = Some(token_result);
+            self.set_token(token_result);
 
             Ok(SpotifyAPIOAuthClient::Authenticated(client))
         } else {
@@ -191,7 +352,7 @@ impl SpotifyAPIOAuthProvider {
                 .await
                 .map_err(|e| SpotifyAPIOAuthError::CodeExchangeFailure(Arc::new(e)))?;
 
-            self.token = Some(token_result);
+            self.set_token(token_result);
 
             Ok(SpotifyAPIOAuthClient::Authenticated(client))
         } else {
@@ -199,116 +360,337 @@ impl SpotifyAPIOAuthProvider {
         }
     }
 
-    pub async fn wait_for_oauth_code(&self) -> Result<(String, String), SpotifyAPIOAuthError> {
-        let addr = &self.get_addr();
-
-        let listener = TcpListener::bind(addr)
-            .map_err(|e| SpotifyAPIOAuthError::OAuth2ServerBindFailure(Arc::new(e)))?;
-
-        for mut stream in listener.incoming().flatten() {
-            let buf_reader = BufReader::new(&mut stream);
-            let http_request: Vec<String> = buf_reader
-                .lines()
-                .map(|result| result.unwrap())
-                .take_while(|line| !line.is_empty())
-                .collect();
-
-            if let Some(http_method_uri_line) = http_request.first().map(|ln| ln.split(" ").collect::<Vec<&str>>()) {
-                let http_method = http_method_uri_line[0].trim().to_lowercase();
-                let http_uri = http_method_uri_line[1].trim();
-
-                let mut http_message = |status: &'static str, body: String| {
-                    let safe_version = UI_APP_VERSION.unwrap_or("");
-
-                    let message = format!("HTTP/1.1 {status}\n\
-                        Content-Type: text/html\n\
-                        Connection: keep-alive\n\
-                        \n\
-                        <html>
-                        <head>
-                            <title>{UI_APP_NAME} {safe_version}</title>
-                            <style>html,body {{ font-family: system-ui, sans-serif; }}</style>
-                        </head>
-                        <body>
-                            <center>
-                                <h1>{status}</h1>
-                                <p>{body}</p>
-
-                                <hr>
-                                <small>{UI_APP_NAME} {safe_version}</small>
-                            </center>
-                        </body>
-                    ");
-
-                    stream.write_all(message.as_bytes()).ok();
-                };
-
-                if !http_method.is_empty() && !http_uri.is_empty() {
-                    if let Ok(http_uri_parsed) = Url::parse(&format!("http://localhost{}", http_uri)) {
-                        match http_uri_parsed.path() {
-                            "/login" => {
-                                let code = http_uri_parsed.query_pairs()
-                                    .find(|(key, value)| key == "code")
-                                    .map(|(key, value)| value);
-
-                                let csrf_state = http_uri_parsed.query_pairs()
-                                    .find(|(key, value)| key == "state")
-                                    .map(|(key, value)| value);
-
-                                let mut throw_error = |message: String| {
-                                    warn!(message);
-
-                                    let http_error_message = format!("{}<br><br>Please try logging in again from the app.", message.clone());
-
-                                    http_message("400 Bad Request", http_error_message);
-
-                                    Err(SpotifyAPIOAuthError::ServerFailure(message.clone()))
-                                };
-
-                                match (code, csrf_state) {
-                                    (Some(code), Some(csrf_state)) => {
-                                        info!("Received token from OAuth2 auth request ({} bytes).", code.as_bytes().len());
-
-                                        http_message(
-                                            "200 OK",
-                                            "Successfully authenticated with Spotify.<br><br>You can now close this page.".to_string()
-                                        );
-
-                                        return Ok((code.to_string(), csrf_state.to_string()));
-                                    },
-                                    (None, Some(_)) => return throw_error("No code parameter supplied with request.".to_string()),
-                                    (Some(_), None) => return throw_error("No csrf_token parameter supplied with request.".to_string()),
-                                    _ => {
-                                        let error = http_uri_parsed.query_pairs()
-                                            .find(|q| q.0 == *"error").map(|e| e.1)
-                                            .unwrap_or(Cow::from("Something went wrong, please retry the login flow again."));
-
-                                        return throw_error(error.clone().to_string());
-                                    }
-                                }
-                            },
-                            _ => {
-                                http_message("404 Not Found", "404 Not Found".to_string());
-                            }
+    pub async fn update_client(&mut self, listener: Option<TcpListener>, cancel: oneshot::Receiver<()>) -> Result<(), SpotifyAPIOAuthError> {
+        let client = match listener {
+            Some(listener) => {
+                let (code, csrf_state) = accept_oauth_callback(
+                    listener,
+                    self.csrf_token.secret().clone(),
+                    self.callback_timeout,
+                    cancel
+                ).await?;
+                self.authenticate_client_from_code(code, csrf_state).await?
+            },
+            None => {
+                let refresh_token = self.init_refresh_token
+                    .clone()
+                    .ok_or(SpotifyAPIOAuthError::AuthenticationFailure("No refresh token to log in with.".to_string()))?;
+
+                self.authenticate_client_from_refresh_token(refresh_token).await?
+            }
+        };
+
+        self.client = Ok(client);
+
+        Ok(())
+    }
+}
+
+fn oauth_http_message(status: &str, body: &str) -> String {
+    let safe_version = UI_APP_VERSION.unwrap_or("");
+
+    let html = format!("<html>
+<head>
+    <title>{UI_APP_NAME} {safe_version}</title>
+    <style>html,body {{ font-family: system-ui, sans-serif; }}</style>
+</head>
+<body>
+    <center>
+        <h1>{status}</h1>
+        <p>{body}</p>
+
+        <hr>
+        <small>{UI_APP_NAME} {safe_version}</small>
+    </center>
+</body>
+</html>
+");
+
+    format!(
+        "HTTP/1.1 {status}\r\n\
+        Content-Type: text/html; charset=utf-8\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\
+        \r\n\
+        {html}",
+        html.len()
+    )
+}
+
+// Reads the request line of one callback request, skipping over its headers.
+async fn read_oauth_request_target(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut reader = tokio::io::BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+
+    match (parts.next(), parts.next()) {
+        (Some(_method), Some(target)) => Ok(Some(target.to_string())),
+        _ => Ok(None),
+    }
+}
+
+// Works out what to reply to a callback request, and whether it ends the login flow.
+fn handle_oauth_request_target(target: &str, csrf_state: &str) -> (&'static str, String, Option<Result<(String, String), SpotifyAPIOAuthError>>) {
+    let Ok(url) = Url::parse(&format!("http://localhost{}", target)) else {
+        return ("400 Bad Request", "Malformed request.".to_string(), None);
+    };
+
+    if url.path() != "/login" {
+        return ("404 Not Found", "404 Not Found".to_string(), None);
+    }
+
+    let query = |name: &str| url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.to_string());
+
+    let throw_error = |message: String| {
+        warn!(message);
+
+        (
+            "400 Bad Request",
+            format!("{}<br><br>Please try logging in again from the app.", message),
+            Some(Err(SpotifyAPIOAuthError::ServerFailure(message))),
+        )
+    };
+
+    // Anything without our state didn't come from the login we started, so it
+    // doesn't get to end it either, keep waiting for the real redirect.
+    if query("state").as_deref() != Some(csrf_state) {
+        warn!("OAuth2 callback state does not match, ignoring the request.");
+
+        return (
+            "400 Bad Request",
+            "This login link doesn't belong to the current login.<br><br>Please use the login page opened by the app.".to_string(),
+            None,
+        );
+    }
+
+    if let Some(error) = query("error") {
+        return throw_error(match error.as_str() {
+            "access_denied" => "Spotify login was cancelled, access to your account was denied.".to_string(),
+            _ => format!("Spotify login failed: {}", error),
+        });
+    }
+
+    match query("code") {
+        Some(code) => {
+            info!("Received token from OAuth2 auth request ({} bytes).", code.as_bytes().len());
+
+            (
+                "200 OK",
+                "Successfully authenticated with Spotify.<br><br>You can now close this page.".to_string(),
+                Some(Ok((code, csrf_state.to_string()))),
+            )
+        },
+        None => throw_error("No code parameter supplied with request.".to_string()),
+    }
+}
+
+// Answers a single callback connection, passing on anything that ends the login flow.
+async fn serve_oauth_connection(
+    mut stream: TcpStream,
+    csrf_state: &str,
+    results: mpsc::Sender<Result<(String, String), SpotifyAPIOAuthError>>,
+) {
+    let request_timeout = std::time::Duration::from_secs(SPOTIFY_OAUTH_REQUEST_TIMEOUT_SECS);
+
+    let target = match tokio::time::timeout(request_timeout, read_oauth_request_target(&mut stream)).await {
+        Ok(Ok(Some(target))) => target,
+        Ok(_) => return,
+        Err(_) => {
+            warn!("OAuth2 callback connection sent nothing for {}s, dropping it.", SPOTIFY_OAUTH_REQUEST_TIMEOUT_SECS);
+            return;
+        }
+    };
+
+    let (status, body, result) = handle_oauth_request_target(&target, csrf_state);
+
+    stream.write_all(oauth_http_message(status, &body).as_bytes()).await.ok();
+    stream.shutdown().await.ok();
+
+    if let Some(result) = result {
+        results.send(result).await.ok();
+    }
+}
+
+/// Serves the OAuth2 redirect on `listener` until Spotify sends us back to /login
+/// with `csrf_state`, `timeout` passes, or `cancel` fires, e.g. because the user
+/// started over. Returns the code and the CSRF state from the redirect.
+pub async fn accept_oauth_callback(
+    listener: TcpListener,
+    csrf_state: String,
+    timeout: std::time::Duration,
+    mut cancel: oneshot::Receiver<()>,
+) -> Result<(String, String), SpotifyAPIOAuthError> {
+    let csrf_state = Arc::new(csrf_state);
+    let (results_tx, mut results_rx) = mpsc::channel(1);
+
+    let accept = async {
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let stream = match accepted {
+                        Ok((stream, _)) => stream,
+                        Err(err) => {
+                            warn!("Failed to accept OAuth2 callback connection: {:#?}", err);
+                            continue;
                         }
-                    }
-                }
+                    };
+
+                    // Each in its own task, so a browser holding a connection
+                    // open can't keep us from reading the next one.
+                    let csrf_state = Arc::clone(&csrf_state);
+                    let results_tx = results_tx.clone();
+
+                    tokio::spawn(async move {
+                        serve_oauth_connection(stream, &csrf_state, results_tx).await;
+                    });
+                },
+                Some(result) = results_rx.recv() => return result,
             }
         }
+    };
 
-        Err(SpotifyAPIOAuthError::ServerFailure("No response handled from OAuth2 server.".to_string()))
+    tokio::select! {
+        result = accept => result,
+        _ = tokio::time::sleep(timeout) => Err(SpotifyAPIOAuthError::CallbackTimeout),
+        _ = &mut cancel => Err(SpotifyAPIOAuthError::Cancelled),
     }
+}
 
-    pub async fn update_client(&mut self, force: Option<bool>) -> Result<(), SpotifyAPIOAuthError> {
-        let client = if force.unwrap_or(false) || self.init_refresh_token.is_none() {
-            let (code, csrf_state) = self.wait_for_oauth_code().await?;
-            self.authenticate_client_from_code(code, csrf_state).await?
-        } else {
-            self.authenticate_client_from_refresh_token(self.init_refresh_token.clone().unwrap().to_string()).await?
-        };
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
 
-        self.client = Ok(client);
+    use super::*;
 
-        Ok(())
+    const STATE: &str = "expected-state";
+
+    async fn listen() -> (TcpListener, u16) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        (listener, port)
     }
-}
\ No newline at end of file
+
+    fn serve(listener: TcpListener, timeout_ms: u64) -> (tokio::task::JoinHandle<Result<(String, String), SpotifyAPIOAuthError>>, oneshot::Sender<()>) {
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+
+        let handle = tokio::spawn(accept_oauth_callback(
+            listener,
+            STATE.to_string(),
+            std::time::Duration::from_millis(timeout_ms),
+            cancel_rx,
+        ));
+
+        (handle, cancel_tx)
+    }
+
+    // Sends a GET like a browser would, returning the status line of the reply.
+    async fn get(port: u16, target: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+
+        stream.write_all(format!(
+            "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nAccept: text/html\r\n\r\n",
+            target,
+            port
+        ).as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn returns_code_from_redirect() {
+        let (listener, port) = listen().await;
+        let (handle, _cancel) = serve(listener, 5000);
+
+        let status = get(port, &format!("/login?code=the-code&state={}", STATE)).await;
+
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(handle.await.unwrap().unwrap(), ("the-code".to_string(), STATE.to_string()));
+    }
+
+    #[tokio::test]
+    async fn keeps_listening_after_other_paths() {
+        let (listener, port) = listen().await;
+        let (handle, _cancel) = serve(listener, 5000);
+
+        assert_eq!(get(port, "/favicon.ico").await, "HTTP/1.1 404 Not Found");
+        assert_eq!(get(port, &format!("/login?code=the-code&state={}", STATE)).await, "HTTP/1.1 200 OK");
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_state_and_keeps_listening() {
+        let (listener, port) = listen().await;
+        let (handle, _cancel) = serve(listener, 5000);
+
+        assert_eq!(get(port, "/login?code=forged&state=other-state").await, "HTTP/1.1 400 Bad Request");
+        assert_eq!(get(port, "/login?error=access_denied").await, "HTTP/1.1 400 Bad Request");
+        assert!(!handle.is_finished());
+
+        assert_eq!(get(port, &format!("/login?code=the-code&state={}", STATE)).await, "HTTP/1.1 200 OK");
+        assert_eq!(handle.await.unwrap().unwrap().0, "the-code");
+    }
+
+    #[tokio::test]
+    async fn reports_error_param() {
+        let (listener, port) = listen().await;
+        let (handle, _cancel) = serve(listener, 5000);
+
+        let status = get(port, &format!("/login?error=access_denied&state={}", STATE)).await;
+
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+
+        match handle.await.unwrap() {
+            Err(SpotifyAPIOAuthError::ServerFailure(message)) => assert!(message.contains("denied")),
+            other => panic!("Expected a server failure, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_connection_does_not_block_redirect() {
+        let (listener, port) = listen().await;
+        let (handle, _cancel) = serve(listener, 5000);
+
+        // Browsers open speculative connections and send nothing on them.
+        let _idle = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+
+        let status = get(port, &format!("/login?code=the-code&state={}", STATE)).await;
+
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let (listener, _) = listen().await;
+        let (handle, _cancel) = serve(listener, 50);
+
+        assert!(matches!(handle.await.unwrap(), Err(SpotifyAPIOAuthError::CallbackTimeout)));
+    }
+
+    #[tokio::test]
+    async fn cancels() {
+        let (listener, _) = listen().await;
+        let (handle, cancel) = serve(listener, 5000);
+
+        cancel.send(()).unwrap();
+
+        assert!(matches!(handle.await.unwrap(), Err(SpotifyAPIOAuthError::Cancelled)));
+    }
+}
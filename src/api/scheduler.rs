@@ -0,0 +1,153 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use super::constants::{
+    SPOTIFY_API_BACKOFF_BASE_MS, SPOTIFY_API_BACKOFF_MAX_MS, SPOTIFY_API_MAX_CONCURRENT_REQUESTS,
+};
+
+/// Decides when requests to the Web API may go out: caps how many are
+/// in flight at once and holds everything back while we're rate limited.
+#[derive(Clone)]
+pub struct SpotifyAPIScheduler {
+    semaphore: Arc<Semaphore>,
+    rate_limited_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Default for SpotifyAPIScheduler {
+    fn default() -> Self {
+        Self::new(SPOTIFY_API_MAX_CONCURRENT_REQUESTS)
+    }
+}
+
+impl SpotifyAPIScheduler {
+    pub fn new(max_concurrent_requests: usize) -> Self {
+        SpotifyAPIScheduler {
+            semaphore: Arc::new(Semaphore::new(max_concurrent_requests.max(1))),
+            rate_limited_until: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Waits out any rate limit, then for a free request slot.
+    /// The slot is released when the returned permit is dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        loop {
+            if let Some(retry_in) = self.rate_limited_for() {
+                tokio::time::sleep(retry_in).await;
+            }
+
+            // Never closed, so this only fails if something's gone very wrong.
+            let permit = self.semaphore
+                .acquire()
+                .await
+                .expect("API scheduler semaphore closed");
+
+            // We may have been rate limited while waiting for the slot,
+            // give it up for someone else while we wait again.
+            if self.rate_limited_for().is_none() {
+                return permit;
+            }
+        }
+    }
+
+    /// How long until we're allowed to make requests again, if we're rate limited.
+    pub fn rate_limited_for(&self) -> Option<Duration> {
+        let mut rate_limited_until = self.rate_limited_until.lock().unwrap();
+
+        match *rate_limited_until {
+            Some(until) if until > Instant::now() => Some(until - Instant::now()),
+            Some(_) => {
+                *rate_limited_until = None;
+
+                None
+            },
+            None => None,
+        }
+    }
+
+    pub fn set_rate_limited(&self, retry_after: Duration) {
+        let until = Instant::now() + retry_after;
+
+        let mut rate_limited_until = self.rate_limited_until.lock().unwrap();
+
+        // Another request may have been told to wait longer already.
+        if rate_limited_until.map_or(true, |current| until > current) {
+            *rate_limited_until = Some(until);
+        }
+    }
+}
+
+/// Exponential backoff with full jitter: a random delay between
+/// zero and base * 2^attempt, capped so we never wait too long.
+pub fn backoff(attempt: u32) -> Duration {
+    let max_ms = SPOTIFY_API_BACKOFF_BASE_MS
+        .saturating_mul(2u64.saturating_pow(attempt))
+        .min(SPOTIFY_API_BACKOFF_MAX_MS);
+
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max_ms))
+}
+
+/// Spotify sends Retry-After as a number of seconds.
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_a_free_slot() {
+        let scheduler = SpotifyAPIScheduler::new(2);
+
+        let first = scheduler.acquire().await;
+        let _second = scheduler.acquire().await;
+
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+
+            async move {
+                let _third = scheduler.acquire().await;
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+
+        tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn holds_requests_while_rate_limited() {
+        let scheduler = SpotifyAPIScheduler::new(1);
+        scheduler.set_rate_limited(Duration::from_millis(200));
+
+        let started = Instant::now();
+        let _permit = scheduler.acquire().await;
+
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert!(scheduler.rate_limited_for().is_none());
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(3)));
+    }
+}
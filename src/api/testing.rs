@@ -0,0 +1,34 @@
+use rspotify_model::FullTrack;
+use serde_json::json;
+
+/// A bare track with only what deserializing needs, local when it has no ID.
+pub fn track(id: Option<&str>, name: &str) -> FullTrack {
+    serde_json::from_value(json!({
+        "album": {
+            "artists": [],
+            "available_markets": [],
+            "external_urls": {},
+            "href": null,
+            "id": null,
+            "images": [],
+            "name": "Album",
+            "type": "album"
+        },
+        "artists": [],
+        "available_markets": [],
+        "disc_number": 1,
+        "duration_ms": 1000,
+        "explicit": false,
+        "external_ids": {},
+        "external_urls": {},
+        "href": null,
+        "id": id,
+        "is_local": id.is_none(),
+        "name": name,
+        "popularity": 0,
+        "preview_url": null,
+        "track_number": 1,
+        "type": "track"
+    }))
+    .unwrap()
+}
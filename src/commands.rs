@@ -1,3 +1,12 @@
+use std::path::PathBuf;
+
+use rspotify_model::FullTrack;
+
+use crate::{
+    api::models::{artist::ArtistAlbumGroup, entity::SpotifyEntity, search::SearchResultsKind},
+    state::queue::{PlaybackContext, RepeatMode},
+};
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum AppCommand {
     About,
@@ -8,8 +17,52 @@ pub enum AppCommand {
     ZoomReset,
 
     Navigate(&'static str),
+    Open(SpotifyEntity),
+    Filter,
 
     DoSearch(String),
+    SearchMore(SearchResultsKind),
+    RecommendationsMore(usize),
+    ArtistAlbumsMore(String, ArtistAlbumGroup),
+
+    PlayTrack(FullTrack),
+    PlayContext(Option<PlaybackContext>, Vec<FullTrack>, usize),
+    PlayEntity(SpotifyEntity),
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Seek(u32),
+    SeekBy(i64),
+    NextTrack,
+    PreviousTrack,
+    SetVolume(f64),
+    VolumeBy(f64),
+    SetShuffle(bool),
+    ToggleShuffle,
+    SetRepeat(RepeatMode),
+    CycleRepeat,
+
+    AddToQueue(FullTrack),
+    PlayNext(FullTrack),
+
+    FollowArtist(String, bool),
+    SaveAlbum(String, bool),
+    SaveTrack(FullTrack, bool),
+    CheckSavedTrack(String),
+
+    FetchDevices,
+    TransferPlayback(String),
 
     OpenSpotifyAccount,
+    SwitchAccount(String),
+    AddAccount,
+    LogOut,
+
+    ClearCache,
+    SetCacheLimit(u64),
+
+    ScanLocalFiles,
+    AddLocalFolder(PathBuf),
+    RemoveLocalFolder(PathBuf),
 }
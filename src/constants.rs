@@ -55,10 +55,29 @@ pub const UI_DARK_CHROME_BG_COLOR: Color = Color {
 
 pub const UI_PLAYER_BAR_HEIGHT: f32 = 90.0;
 
+// How far the Playback menu's seek and volume items move each time.
+pub const UI_SEEK_STEP_MS: i64 = 5000;
+pub const UI_VOLUME_STEP: f64 = 0.1;
+
 pub const UI_ROUTE_DEFAULT: &str = "Home";
 pub const UI_ROUTE_SEARCH: &str = "Search";
 pub const UI_ROUTE_PREFERENCES: &str = "Preferences";
+pub const UI_ROUTE_QUEUE: &str = "Queue";
+pub const UI_ROUTE_LIKED_SONGS: &str = "Liked Songs";
+pub const UI_ROUTE_RECENTLY_PLAYED: &str = "Recently Played";
+pub const UI_ROUTE_YOUR_ARTISTS: &str = "Your Artists";
+pub const UI_ROUTE_YOUR_ALBUMS: &str = "Your Albums";
+pub const UI_ROUTE_YOUR_FILES: &str = "Your Files";
 
 pub const UI_ALBUM_ART_SIZE: f32 = 300.0;
+pub const UI_CARD_WIDTH: f32 = 180.0;
+
+// How much decoded cover art to keep on the GPU before evicting.
+pub const UI_IMAGE_CACHE_MAX_BYTES: usize = 128 * 1024 * 1024;
+// How many downloaded images to keep on disk before evicting.
+pub const UI_IMAGE_DISK_CACHE_MAX_BYTES: u64 = 200 * 1024 * 1024;
+// Images that fail to load are tried again after this, doubling each time.
+pub const UI_IMAGE_RETRY_BASE_SECS: u64 = 5;
+pub const UI_IMAGE_RETRY_MAX_SECS: u64 = 5 * 60;
 
 pub const UI_DEFAULT_LOCALE: &str = "en_US";
@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{CredentialStore, CredentialStoreError, CREDENTIALS_PASSPHRASE_ENV};
+
+const CREDENTIALS_FILE_VERSION: u32 = 1;
+
+// Where systemd and dbus keep a stable, per-install machine ID.
+const MACHINE_ID_PATHS: [&str; 2] = ["/etc/machine-id", "/var/lib/dbus/machine-id"];
+
+fn machine_id() -> Option<String> {
+    let id = MACHINE_ID_PATHS
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok());
+
+    #[cfg(target_os = "macos")]
+    let id = id.or_else(platform_uuid);
+
+    id.map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+}
+
+// macOS has no machine-id file, the hardware UUID IOKit
+// reports is the closest thing it has.
+#[cfg(target_os = "macos")]
+fn platform_uuid() -> Option<String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+
+    parse_platform_uuid(&String::from_utf8_lossy(&output.stdout))
+}
+
+// Picks the UUID out of ioreg's `"IOPlatformUUID" = "..."` line.
+#[cfg(any(target_os = "macos", test))]
+fn parse_platform_uuid(ioreg: &str) -> Option<String> {
+    ioreg.lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.split('=').nth(1))
+        .map(|uuid| uuid.trim().trim_matches('"').to_string())
+        .filter(|uuid| !uuid.is_empty())
+}
+
+/// What the file's encryption key is derived from.
+#[derive(Clone)]
+pub enum EncryptedFileCredentialKey {
+    Passphrase(String),
+    // Ties the file to this machine and user. Stops the secret being read
+    // off a backup or a copied home directory, but not by other programs
+    // running as you, use a passphrase or the keyring for that.
+    Machine,
+}
+
+impl EncryptedFileCredentialKey {
+    fn material(&self) -> Result<Vec<u8>, CredentialStoreError> {
+        match self {
+            EncryptedFileCredentialKey::Passphrase(passphrase) => Ok(passphrase.as_bytes().to_vec()),
+            EncryptedFileCredentialKey::Machine => {
+                let machine_id = machine_id().ok_or(CredentialStoreError::Unavailable(format!(
+                    "No machine ID to derive a key from, set {} to use a passphrase instead.",
+                    CREDENTIALS_PASSPHRASE_ENV
+                )))?;
+
+                let user = std::env::var("USER")
+                    .or_else(|_| std::env::var("USERNAME"))
+                    .unwrap_or_default();
+
+                Ok(format!("{}:{}", machine_id, user).into_bytes())
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFileContents {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Keeps every account's secret in one file, encrypted with XChaCha20-Poly1305
+/// under a key stretched from `key` with Argon2.
+pub struct EncryptedFileCredentialStore {
+    path: PathBuf,
+    key: EncryptedFileCredentialKey,
+
+    // Serialises read-modify-write cycles on the file.
+    lock: Arc<Mutex<()>>,
+
+    // The last key we derived and the salt it was for. Argon2 is slow on
+    // purpose, and writes keep the file's salt so this stays valid.
+    derived_key: Mutex<Option<(Vec<u8>, [u8; 32])>>,
+}
+
+impl EncryptedFileCredentialStore {
+    pub fn new(path: PathBuf, key: EncryptedFileCredentialKey) -> Self {
+        EncryptedFileCredentialStore {
+            path,
+            key,
+
+            lock: Arc::new(Mutex::new(())),
+
+            derived_key: Mutex::new(None),
+        }
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<XChaCha20Poly1305, CredentialStoreError> {
+        let mut derived_key = self.derived_key.lock().unwrap();
+
+        if let Some((derived_salt, key)) = derived_key.as_ref() {
+            if derived_salt == salt {
+                return Ok(XChaCha20Poly1305::new(&(*key).into()));
+            }
+        }
+
+        let mut key = [0u8; 32];
+
+        Argon2::default()
+            .hash_password_into(&self.key.material()?, salt, &mut key)
+            .map_err(|_| CredentialStoreError::CryptoError("Failed to derive encryption key."))?;
+
+        *derived_key = Some((salt.to_vec(), key));
+
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+
+    fn read(&self) -> Result<HashMap<String, String>, CredentialStoreError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(err) => return Err(CredentialStoreError::IoError(Arc::new(err))),
+        };
+
+        let file = serde_json::from_str::<EncryptedFileContents>(&contents)
+            .map_err(|e| CredentialStoreError::SerdeError(Arc::new(e)))?;
+
+        if file.version != CREDENTIALS_FILE_VERSION {
+            return Err(CredentialStoreError::CryptoError("Unsupported credentials file version."));
+        }
+
+        let decode = |value: &str| hex::decode(value)
+            .map_err(|_| CredentialStoreError::CryptoError("Credentials file is corrupt."));
+
+        let salt = decode(&file.salt)?;
+        let nonce = decode(&file.nonce)?;
+        let ciphertext = decode(&file.ciphertext)?;
+
+        if nonce.len() != 24 {
+            return Err(CredentialStoreError::CryptoError("Credentials file is corrupt."));
+        }
+
+        let plaintext = self.cipher(&salt)?
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| CredentialStoreError::CryptoError("Failed to decrypt credentials, the key may have changed."))?;
+
+        serde_json::from_slice(&plaintext)
+            .map_err(|e| CredentialStoreError::SerdeError(Arc::new(e)))
+    }
+
+    fn write(&self, secrets: &HashMap<String, String>) -> Result<(), CredentialStoreError> {
+        let mut rng = rand::thread_rng();
+
+        // Only the nonce has to be new each time, keeping the salt
+        // means keeping the key we already derived for it.
+        let cached_salt = self.derived_key.lock().unwrap().as_ref().map(|(salt, _)| salt.clone());
+
+        let salt = cached_salt.unwrap_or_else(|| {
+            let mut salt = [0u8; 16];
+            rng.fill(&mut salt);
+
+            salt.to_vec()
+        });
+
+        let mut nonce = [0u8; 24];
+        rng.fill(&mut nonce);
+
+        let plaintext = serde_json::to_vec(secrets)
+            .map_err(|e| CredentialStoreError::SerdeError(Arc::new(e)))?;
+
+        let ciphertext = self.cipher(&salt)?
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| CredentialStoreError::CryptoError("Failed to encrypt credentials."))?;
+
+        let contents = serde_json::to_string_pretty(&EncryptedFileContents {
+            version: CREDENTIALS_FILE_VERSION,
+            salt: hex::encode(salt),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+            .map_err(|e| CredentialStoreError::SerdeError(Arc::new(e)))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| CredentialStoreError::IoError(Arc::new(e)))?;
+        }
+
+        // Write alongside and rename over, so a crash never leaves half a file.
+        let tmp_path = self.path.with_extension("tmp");
+
+        let mut options = File::options();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        options.open(&tmp_path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| CredentialStoreError::IoError(Arc::new(e)))
+    }
+}
+
+impl CredentialStore for EncryptedFileCredentialStore {
+    fn name(&self) -> &'static str {
+        "encrypted credentials file"
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, CredentialStoreError> {
+        let _lock = self.lock.lock().unwrap();
+
+        Ok(self.read()?.remove(account))
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<(), CredentialStoreError> {
+        let _lock = self.lock.lock().unwrap();
+
+        let mut secrets = self.read()?;
+        secrets.insert(account.to_string(), secret.to_string());
+
+        self.write(&secrets)
+    }
+
+    fn delete(&self, account: &str) -> Result<(), CredentialStoreError> {
+        let _lock = self.lock.lock().unwrap();
+
+        let mut secrets = self.read()?;
+
+        if secrets.remove(account).is_some() {
+            self.write(&secrets)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(dir: &tempfile::TempDir, passphrase: &str) -> EncryptedFileCredentialStore {
+        EncryptedFileCredentialStore::new(
+            dir.path().join("credentials.enc"),
+            EncryptedFileCredentialKey::Passphrase(passphrase.to_string())
+        )
+    }
+
+    #[test]
+    fn round_trips_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(&dir, "passphrase");
+
+        assert_eq!(store.get("first").unwrap(), None);
+
+        store.set("first", "first-secret").unwrap();
+        store.set("second", "second-secret").unwrap();
+
+        assert_eq!(store.get("first").unwrap().as_deref(), Some("first-secret"));
+        assert_eq!(store.get("second").unwrap().as_deref(), Some("second-secret"));
+
+        store.delete("first").unwrap();
+
+        assert_eq!(store.get("first").unwrap(), None);
+        assert_eq!(store.get("second").unwrap().as_deref(), Some("second-secret"));
+    }
+
+    #[test]
+    fn derives_the_key_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let store = store(&dir, "passphrase");
+
+        let contents = || serde_json::from_str::<EncryptedFileContents>(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        store.set("first", "first-secret").unwrap();
+
+        let first = contents();
+
+        store.set("second", "second-secret").unwrap();
+
+        let second = contents();
+
+        // Same salt so the same key, but never the same nonce.
+        assert_eq!(first.salt, second.salt);
+        assert_ne!(first.nonce, second.nonce);
+
+        assert_eq!(
+            store.derived_key.lock().unwrap().as_ref().map(|(salt, _)| hex::encode(salt)),
+            Some(second.salt)
+        );
+
+        // And another store, starting from nothing, derives the same key from it.
+        assert_eq!(self::store(&dir, "passphrase").get("first").unwrap().as_deref(), Some("first-secret"));
+    }
+
+    #[test]
+    fn reads_platform_uuid_from_ioreg() {
+        let ioreg = r#"+-o J314sAP  <class IOPlatformExpertDevice, id 0x100000235, registered, matched, active, busy 0 (7 ms), retain 33>
+    {
+      "IOPlatformSerialNumber" = "C02XXXXXXXXX"
+      "IOPlatformUUID" = "0A1B2C3D-4E5F-6071-8293-A4B5C6D7E8F9"
+    }"#;
+
+        assert_eq!(parse_platform_uuid(ioreg).as_deref(), Some("0A1B2C3D-4E5F-6071-8293-A4B5C6D7E8F9"));
+        assert_eq!(parse_platform_uuid("{}"), None);
+    }
+
+    #[test]
+    fn keeps_secrets_out_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        store(&dir, "passphrase").set("account", "plaintext-secret").unwrap();
+
+        let contents = fs::read_to_string(dir.path().join("credentials.enc")).unwrap();
+
+        assert!(!contents.contains("plaintext-secret"));
+        assert!(!contents.contains("account"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_owner_can_read_the_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+
+        store(&dir, "passphrase").set("account", "secret").unwrap();
+
+        let mode = fs::metadata(dir.path().join("credentials.enc")).unwrap().permissions().mode();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn rejects_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+
+        store(&dir, "right").set("account", "secret").unwrap();
+
+        assert!(matches!(store(&dir, "wrong").get("account"), Err(CredentialStoreError::CryptoError(_))));
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+
+        store(&dir, "passphrase").set("account", "secret").unwrap();
+
+        let mut file = serde_json::from_str::<EncryptedFileContents>(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        let mut ciphertext = hex::decode(&file.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        file.ciphertext = hex::encode(ciphertext);
+
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+
+        assert!(matches!(store(&dir, "passphrase").get("account"), Err(CredentialStoreError::CryptoError(_))));
+    }
+
+    #[test]
+    fn refuses_to_overwrite_unreadable_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        store(&dir, "right").set("account", "secret").unwrap();
+
+        assert!(store(&dir, "wrong").set("other", "other-secret").is_err());
+        assert_eq!(store(&dir, "right").get("account").unwrap().as_deref(), Some("secret"));
+    }
+}
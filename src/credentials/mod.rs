@@ -0,0 +1,82 @@
+pub mod file;
+#[cfg(target_os = "linux")]
+pub mod secret_service;
+
+use std::{io, path::PathBuf, sync::Arc};
+
+use tracing::{info, warn};
+
+use file::{EncryptedFileCredentialStore, EncryptedFileCredentialKey};
+
+// Refresh tokens are stored under each account's Spotify user ID, this
+// slot is for a login we haven't matched to an account yet, like one
+// migrated from preferences.toml.
+pub const CREDENTIALS_DEFAULT_ACCOUNT: &str = "default";
+
+pub const CREDENTIALS_FILE_NAME: &str = "credentials.enc";
+
+// Set to encrypt the credentials file with a passphrase instead of the machine key.
+pub const CREDENTIALS_PASSPHRASE_ENV: &str = "SPOTTYFI_CREDENTIALS_PASSPHRASE";
+
+#[derive(Debug, Clone)]
+pub enum CredentialStoreError {
+    Unavailable(String),
+    IoError(Arc<io::Error>),
+    SerdeError(Arc<serde_json::Error>),
+    CryptoError(&'static str),
+}
+
+/// Somewhere to keep secrets like refresh tokens, that isn't preferences.toml.
+pub trait CredentialStore: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn get(&self, account: &str) -> Result<Option<String>, CredentialStoreError>;
+    fn set(&self, account: &str, secret: &str) -> Result<(), CredentialStoreError>;
+    fn delete(&self, account: &str) -> Result<(), CredentialStoreError>;
+}
+
+/// Uses the desktop keyring where there is one, falling back to an
+/// encrypted file in `config_dir` when it's missing or locked.
+pub fn open(config_dir: Option<PathBuf>) -> Option<Arc<dyn CredentialStore>> {
+    #[cfg(target_os = "linux")]
+    match secret_service::SecretServiceCredentialStore::open(crate::constants::UI_APP_NAME) {
+        Ok(store) => {
+            info!("Storing credentials in the Secret Service.");
+
+            return Some(Arc::new(store));
+        },
+        Err(err) => warn!("Secret Service unavailable, falling back to an encrypted file: {:#?}", err),
+    }
+
+    let path = config_dir?.join(CREDENTIALS_FILE_NAME);
+
+    let key = match std::env::var(CREDENTIALS_PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => EncryptedFileCredentialKey::Passphrase(passphrase),
+        _ => EncryptedFileCredentialKey::Machine,
+    };
+
+    info!("Storing credentials in {}.", path.display());
+
+    Some(Arc::new(EncryptedFileCredentialStore::new(path, key)))
+}
+
+/// Moves a refresh token left in preferences.toml by older versions into `store`.
+/// Returns whether the caller can now clear the plaintext copy.
+pub fn migrate_plaintext_secret(store: &dyn CredentialStore, secret: &str) -> bool {
+    if secret.trim().is_empty() {
+        return false;
+    }
+
+    match store.set(CREDENTIALS_DEFAULT_ACCOUNT, secret) {
+        Ok(_) => {
+            info!("Moved your saved login out of preferences.toml into the {}.", store.name());
+
+            true
+        },
+        Err(err) => {
+            warn!("Failed to migrate saved login into the {}: {:#?}", store.name(), err);
+
+            false
+        }
+    }
+}
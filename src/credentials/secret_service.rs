@@ -0,0 +1,58 @@
+use keyring::Entry;
+
+use super::{CredentialStore, CredentialStoreError};
+
+// Probed when opening, so we fall back to a file straight away
+// instead of failing the first time we need a token.
+const SECRET_SERVICE_PROBE_ACCOUNT: &str = "probe";
+
+pub struct SecretServiceCredentialStore {
+    service: String,
+}
+
+fn keyring_error(err: keyring::Error) -> CredentialStoreError {
+    CredentialStoreError::Unavailable(err.to_string())
+}
+
+impl SecretServiceCredentialStore {
+    pub fn open(service: &str) -> Result<Self, CredentialStoreError> {
+        let store = SecretServiceCredentialStore {
+            service: service.to_string(),
+        };
+
+        store.get(SECRET_SERVICE_PROBE_ACCOUNT)?;
+
+        Ok(store)
+    }
+
+    fn entry(&self, account: &str) -> Result<Entry, CredentialStoreError> {
+        Entry::new(&self.service, account).map_err(keyring_error)
+    }
+}
+
+impl CredentialStore for SecretServiceCredentialStore {
+    fn name(&self) -> &'static str {
+        "Secret Service"
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, CredentialStoreError> {
+        match self.entry(account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<(), CredentialStoreError> {
+        self.entry(account)?
+            .set_password(secret)
+            .map_err(keyring_error)
+    }
+
+    fn delete(&self, account: &str) -> Result<(), CredentialStoreError> {
+        match self.entry(account)?.delete_credential() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(keyring_error(err)),
+        }
+    }
+}
@@ -3,7 +3,7 @@
 use librespot::discovery::Credentials;
 use oauth2::RefreshToken;
 
-use crate::{commands::AppCommand, widget::theme::UITheme};
+use crate::{api::providers::oauth2::SpotifyAPIOAuthConfig, commands::AppCommand, playback::connect::SpotifyConnectEvent, widget::theme::UITheme};
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum AppFetchType {
@@ -11,7 +11,8 @@ pub enum AppFetchType {
     Volatile,
 
     Profile,
-    Recommendations
+    Recommendations,
+    RecentlyPlayed
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -19,12 +20,17 @@ pub enum AppEvent {
     Ping,
     Painted,
     SetTheme(UITheme),
+    SetOAuthConfig(SpotifyAPIOAuthConfig),
     InvalidateFontAtlas,
     SetInitialWindowState,
     Command(AppCommand),
     Focus,
     Login,
     Fetch(AppFetchType),
-    StoreToken(Option<String>),
-    FirstTimeLogin
+    // The session generation the token came from, and the token itself.
+    StoreToken(u64, Option<String>),
+    FirstTimeLogin,
+    EndOfTrack,
+    ImagesLoaded,
+    Connect(SpotifyConnectEvent)
 }
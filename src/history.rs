@@ -0,0 +1,99 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use rspotify_model::PlayHistory;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+const HISTORY_DIR_NAME: &str = "history";
+const HISTORY_FILE_VERSION: u32 = 1;
+
+// Enough for a few months of heavy listening without the file getting silly.
+pub const HISTORY_MAX_PLAYS: usize = 5000;
+
+#[derive(Serialize, Deserialize)]
+struct ListeningHistoryFile {
+    version: u32,
+    plays: Vec<PlayHistory>,
+}
+
+/// Keeps plays on disk once they've fallen out of the 50 or so
+/// Spotify remembers, one file per account in `config_dir`.
+#[derive(Clone)]
+pub struct ListeningHistory {
+    path: PathBuf,
+}
+
+impl ListeningHistory {
+    pub fn new(config_dir: PathBuf, user_id: &str) -> Self {
+        ListeningHistory {
+            path: config_dir
+                .join(HISTORY_DIR_NAME)
+                .join(format!("{}.json", user_id)),
+        }
+    }
+
+    /// Missing or unreadable history just starts over empty.
+    pub fn load(&self) -> Vec<PlayHistory> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(err) => {
+                warn!("Failed to read listening history at {}: {:#?}", self.path.display(), err);
+
+                return Vec::new();
+            }
+        };
+
+        match serde_json::from_str::<ListeningHistoryFile>(&contents) {
+            Ok(file) if file.version == HISTORY_FILE_VERSION => file.plays,
+            Ok(file) => {
+                warn!("Ignoring listening history with unknown version {}.", file.version);
+
+                Vec::new()
+            },
+            Err(err) => {
+                warn!("Failed to parse listening history at {}: {:#?}", self.path.display(), err);
+
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn save(&self, plays: &[PlayHistory]) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string(&ListeningHistoryFile {
+            version: HISTORY_FILE_VERSION,
+            plays: plays.to_vec(),
+        })?;
+
+        // Write alongside and swap in, so a crash can't leave half a file.
+        let temp_path = self.path.with_extension("json.tmp");
+
+        File::create(&temp_path)?.write_all(contents.as_bytes())?;
+        fs::rename(temp_path, &self.path)
+    }
+}
+
+/// Folds `new` plays into `plays`, newest first, dropping any we already
+/// had and the oldest past `HISTORY_MAX_PLAYS`.
+pub fn merge(plays: &mut Vec<PlayHistory>, new: Vec<PlayHistory>) {
+    // Market lists are most of a track's size and no use to us here.
+    plays.extend(new.into_iter().map(|mut play| {
+        play.track.available_markets.clear();
+        play.track.album.available_markets.clear();
+
+        play
+    }));
+
+    plays.sort_by(|a, b| b.played_at.cmp(&a.played_at));
+    plays.dedup_by(|a, b| a.played_at == b.played_at && a.track.id == b.track.id);
+
+    plays.truncate(HISTORY_MAX_PLAYS);
+}
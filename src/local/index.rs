@@ -0,0 +1,77 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use super::LocalTrack;
+
+const INDEX_FILE_NAME: &str = "local_files.json";
+const INDEX_FILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct LocalFilesIndexFile {
+    version: u32,
+    tracks: Vec<LocalTrack>,
+}
+
+/// What we last read from the local files folders, so Your Files shows up
+/// straight away and a rescan only has to look at what changed since.
+/// Shared by every account, the files belong to the machine.
+pub struct LocalFilesIndex {
+    path: PathBuf,
+}
+
+impl LocalFilesIndex {
+    pub fn new(config_dir: PathBuf) -> Self {
+        LocalFilesIndex {
+            path: config_dir.join(INDEX_FILE_NAME),
+        }
+    }
+
+    /// Like listening history, a missing or unreadable index starts over empty.
+    pub fn load(&self) -> Vec<LocalTrack> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(err) => {
+                warn!("Failed to read local files index at {}: {:#?}", self.path.display(), err);
+
+                return Vec::new();
+            }
+        };
+
+        match serde_json::from_str::<LocalFilesIndexFile>(&contents) {
+            Ok(file) if file.version == INDEX_FILE_VERSION => file.tracks,
+            Ok(file) => {
+                warn!("Ignoring local files index with unknown version {}.", file.version);
+
+                Vec::new()
+            },
+            Err(err) => {
+                warn!("Failed to parse local files index at {}: {:#?}", self.path.display(), err);
+
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn save(&self, tracks: &[LocalTrack]) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string(&LocalFilesIndexFile {
+            version: INDEX_FILE_VERSION,
+            tracks: tracks.to_vec(),
+        })?;
+
+        let temp_path = self.path.with_extension("json.tmp");
+
+        File::create(&temp_path)?.write_all(contents.as_bytes())?;
+        fs::rename(temp_path, &self.path)
+    }
+}
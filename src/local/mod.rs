@@ -0,0 +1,152 @@
+pub mod index;
+pub mod scanner;
+#[cfg(test)]
+pub mod testing;
+
+use std::{
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use rspotify_model::FullTrack;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+
+// Where a local track's path is kept in its external URLs, so it can
+// go through the queue and player like any other FullTrack.
+const LOCAL_URL_KEY: &str = "local";
+
+/// A music file from one of the folders in Preferences, as read from its tags.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocalTrack {
+    pub path: PathBuf,
+
+    // A rescan only rereads files where this has changed.
+    pub modified: SystemTime,
+
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub duration_ms: u64,
+}
+
+impl LocalTrack {
+    pub fn new(path: PathBuf, modified: SystemTime) -> Self {
+        LocalTrack {
+            path,
+            modified,
+
+            title: None,
+            artist: None,
+            album: None,
+            track_number: None,
+            disc_number: None,
+            duration_ms: 0,
+        }
+    }
+
+    /// The title from the tags, or the file name when there isn't one.
+    pub fn title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| {
+            self.path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Dresses the file up the way Spotify describes local files in
+    /// playlists, no IDs and a spotify:local URI.
+    pub fn to_full_track(&self) -> Option<FullTrack> {
+        let title = self.title();
+        let artist = self.artist.clone().unwrap_or_default();
+        let album = self.album.clone().unwrap_or_default();
+
+        let uri = format!(
+            "spotify:local:{}:{}:{}:{}",
+            urlencoding::encode(&artist),
+            urlencoding::encode(&album),
+            urlencoding::encode(&title),
+            self.duration_ms / 1000
+        );
+
+        let artists = match &self.artist {
+            Some(artist) => json!([{
+                "external_urls": {},
+                "href": null,
+                "id": null,
+                "name": artist,
+                "type": "artist",
+                "uri": null
+            }]),
+            None => json!([]),
+        };
+
+        let track = json!({
+            "album": {
+                "album_type": null,
+                "artists": artists,
+                "available_markets": [],
+                "external_urls": {},
+                "href": null,
+                "id": null,
+                "images": [],
+                "name": album,
+                "release_date": null,
+                "release_date_precision": null,
+                "type": "album",
+                "uri": null
+            },
+            "artists": artists,
+            "available_markets": [],
+            "disc_number": self.disc_number.unwrap_or(1),
+            "duration_ms": self.duration_ms,
+            "explicit": false,
+            "external_ids": {},
+            "external_urls": { LOCAL_URL_KEY: self.path.to_string_lossy() },
+            "href": null,
+            "id": null,
+            "is_local": true,
+            "name": title,
+            "popularity": 0,
+            "preview_url": null,
+            "track_number": self.track_number.unwrap_or(0),
+            "type": "track",
+            "uri": uri
+        });
+
+        match serde_json::from_value::<FullTrack>(track) {
+            Ok(track) => Some(track),
+            Err(err) => {
+                warn!("Failed to make a track out of {}: {:#?}", self.path.display(), err);
+
+                None
+            }
+        }
+    }
+}
+
+/// The file behind a track from `LocalTrack::to_full_track`, None for Spotify's own.
+pub fn track_path(track: &FullTrack) -> Option<PathBuf> {
+    if !track.is_local {
+        return None;
+    }
+
+    track.external_urls.get(LOCAL_URL_KEY).map(PathBuf::from)
+}
+
+/// Whether `a` and `b` are the same track, by ID for Spotify's, by file for
+/// local ones, which have no IDs to tell them apart.
+pub fn is_same_track(a: &FullTrack, b: &FullTrack) -> bool {
+    match (&a.id, &b.id) {
+        (Some(a_id), Some(b_id)) => a_id == b_id,
+        (None, None) => match (track_path(a), track_path(b)) {
+            (Some(a_path), Some(b_path)) => a_path == b_path,
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use symphonia::core::{
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey},
+    probe::Hint,
+};
+use tracing::{info, warn};
+
+use super::LocalTrack;
+
+// What symphonia is built to read, see Cargo.toml.
+pub const LOCAL_FILE_EXTENSIONS: &[&str] = &["flac", "mp3", "ogg", "oga", "wav"];
+
+pub fn is_supported_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| LOCAL_FILE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Every supported file under `folders`, and when it was last modified.
+fn find_files(folders: &[PathBuf]) -> Vec<(PathBuf, SystemTime)> {
+    let mut files = Vec::new();
+    let mut dirs = folders.to_vec();
+    let mut visited = HashSet::new();
+
+    while let Some(dir) = dirs.pop() {
+        // A symlink back up the tree, or two folders linking to the same
+        // place, would otherwise be scanned again and again.
+        match fs::canonicalize(&dir) {
+            Ok(real) if !visited.insert(real) => continue,
+            _ => {}
+        }
+
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Failed to read local files folder {}: {:#?}", dir.display(), err);
+
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            // Follows symlinks, so a linked folder is scanned like any other.
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else if is_supported_file(&path) {
+                files.push((path, metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)));
+            }
+        }
+    }
+
+    files
+}
+
+// "3/12" in ID3, plain "3" in Vorbis comments.
+fn parse_number(value: &str) -> Option<u32> {
+    value.split('/').next().and_then(|n| n.trim().parse().ok())
+}
+
+fn apply_tags(track: &mut LocalTrack, revision: &MetadataRevision) {
+    let mut album_artist = None;
+
+    for tag in revision.tags() {
+        let value = tag.value.to_string();
+
+        if value.trim().is_empty() {
+            continue;
+        }
+
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => track.title = Some(value),
+            Some(StandardTagKey::Artist) => track.artist = Some(value),
+            Some(StandardTagKey::AlbumArtist) => album_artist = Some(value),
+            Some(StandardTagKey::Album) => track.album = Some(value),
+            Some(StandardTagKey::TrackNumber) => track.track_number = parse_number(&value),
+            Some(StandardTagKey::DiscNumber) => track.disc_number = parse_number(&value),
+            _ => {}
+        }
+    }
+
+    if track.artist.is_none() {
+        track.artist = album_artist;
+    }
+}
+
+/// Reads the tags and length of a single file.
+pub fn read_track(path: &Path, modified: SystemTime) -> Result<LocalTrack, SymphoniaError> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+
+    let mut hint = Hint::new();
+
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        source,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+
+    let mut track = LocalTrack::new(path.to_path_buf(), modified);
+
+    // ID3v2 sits in front of the container, so it's found while probing.
+    // Vorbis comments and FLAC's own tags come from the format itself.
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            apply_tags(&mut track, revision);
+        }
+    }
+
+    if let Some(revision) = probed.format.metadata().current() {
+        apply_tags(&mut track, revision);
+    }
+
+    let Some(default_track) = probed.format.default_track() else {
+        return Err(SymphoniaError::Unsupported("no audio track"));
+    };
+
+    let track_id = default_track.id;
+    let params = default_track.codec_params.clone();
+
+    let Some(time_base) = params.time_base else {
+        return Ok(track);
+    };
+
+    // VBR MP3s without a Xing header don't say how long they are,
+    // count through the packets instead, no decoding needed.
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+
+            while let Ok(packet) = probed.format.next_packet() {
+                if packet.track_id() == track_id {
+                    frames += packet.dur;
+                }
+            }
+
+            frames
+        }
+    };
+
+    let time = time_base.calc_time(frames);
+
+    track.duration_ms = time.seconds * 1000 + (time.frac * 1000.0) as u64;
+
+    Ok(track)
+}
+
+/// Finds everything under `folders`, rereading tags only for files that are
+/// new or modified since `previous`. Files that fail to read are left out.
+pub fn scan(folders: &[PathBuf], previous: Vec<LocalTrack>) -> Vec<LocalTrack> {
+    let mut previous = previous
+        .into_iter()
+        .map(|track| (track.path.clone(), track))
+        .collect::<HashMap<PathBuf, LocalTrack>>();
+
+    let files = find_files(folders);
+    let mut read = 0;
+
+    let mut tracks = files
+        .into_iter()
+        .filter_map(|(path, modified)| {
+            if let Some(track) = previous.remove(&path).filter(|t| t.modified == modified) {
+                return Some(track);
+            }
+
+            read += 1;
+
+            match read_track(&path, modified) {
+                Ok(track) => Some(track),
+                Err(err) => {
+                    warn!("Failed to read local file {}: {}", path.display(), err);
+
+                    None
+                }
+            }
+        })
+        .collect::<Vec<LocalTrack>>();
+
+    info!("Found {} local files, read tags from {}.", tracks.len(), read);
+
+    // Like a record shelf, by artist then album in track order.
+    tracks.sort_by_cached_key(|t| (
+        t.artist.clone().unwrap_or_default().to_lowercase(),
+        t.album.clone().unwrap_or_default().to_lowercase(),
+        t.disc_number.unwrap_or(1),
+        t.track_number.unwrap_or(0),
+        t.title().to_lowercase(),
+    ));
+
+    tracks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::local::testing::write_wav;
+
+    use super::*;
+
+    fn set_modified(path: &Path, modified: SystemTime) {
+        File::options()
+            .append(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified))
+            .unwrap();
+    }
+
+    fn titles(tracks: &[LocalTrack]) -> Vec<String> {
+        tracks.iter().map(|t| t.title()).collect()
+    }
+
+    #[test]
+    fn rereads_only_new_and_modified_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let folders = vec![dir.path().to_path_buf()];
+
+        let first = dir.path().join("first.wav");
+        let second = dir.path().join("nested").join("second.wav");
+
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        write_wav(&first, 1);
+        write_wav(&second, 2);
+
+        // Not audio, whatever the extension says.
+        fs::write(dir.path().join("broken.mp3"), b"not an mp3").unwrap();
+        fs::write(dir.path().join("cover.jpg"), b"not audio either").unwrap();
+
+        let tracks = scan(&folders, vec![]);
+
+        assert_eq!(titles(&tracks), ["first", "second"]);
+        assert_eq!(tracks[0].duration_ms, 1000);
+        assert_eq!(tracks[1].duration_ms, 2000);
+
+        // Anything unchanged comes from what we pass in, not the file.
+        let previous = tracks
+            .into_iter()
+            .map(|mut t| {
+                t.title = Some(format!("{} (indexed)", t.title()));
+                t
+            })
+            .collect::<Vec<LocalTrack>>();
+
+        let tracks = scan(&folders, previous.clone());
+
+        assert_eq!(titles(&tracks), ["first (indexed)", "second (indexed)"]);
+
+        // A changed file is read again, a removed one is dropped.
+        write_wav(&first, 3);
+        set_modified(&first, previous[0].modified + Duration::from_secs(60));
+        fs::remove_file(&second).unwrap();
+
+        let tracks = scan(&folders, previous);
+
+        assert_eq!(titles(&tracks), ["first"]);
+        assert_eq!(tracks[0].duration_ms, 3000);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinked_folders_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let folders = vec![dir.path().to_path_buf()];
+
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        write_wav(&dir.path().join("nested").join("track.wav"), 1);
+
+        // One link loops back to the root, the other to a folder that's
+        // already scanned.
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("nested").join("loop")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("nested"), dir.path().join("again")).unwrap();
+
+        let files = find_files(&folders);
+
+        assert_eq!(files.len(), 1);
+        assert!(files[0].0.ends_with("track.wav"));
+    }
+}
@@ -0,0 +1,29 @@
+use std::{fs, path::Path};
+
+/// Writes `seconds` of silence as a 16-bit mono WAV at 8kHz, small and
+/// quick to decode, and at a rate that has to be resampled.
+pub fn write_wav(path: &Path, seconds: u32) {
+    let sample_rate: u32 = 8000;
+    let data_len = sample_rate * seconds * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // Mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+
+    fs::write(path, wav).unwrap();
+}
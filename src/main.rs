@@ -3,20 +3,33 @@
 mod api;
 mod commands;
 mod constants;
+mod credentials;
 mod event;
+mod history;
 mod imgui_additions;
+mod local;
+#[cfg(target_os = "linux")]
+mod mpris;
+mod playback;
 mod utils;
 mod state;
 mod widget;
 
 use std::{
-    cell::{Ref, RefCell}, rc::Rc, sync::{Arc, Mutex, RwLock}, thread, time::Duration
+    cell::{Ref, RefCell}, future::Future, path::PathBuf, rc::Rc, sync::{Arc, Mutex, RwLock}, thread, time::Duration
 };
 
-use api::{models::recommendations::{self, BrowseRecommendations}, SpotifyAPI};
+use credentials::{CredentialStore, CREDENTIALS_DEFAULT_ACCOUNT};
+use api::{
+    endpoints::SpotifyAPIEndpoints,
+    error::SpotifyAPIError,
+    models::{album::AlbumDetails, artist::ArtistDetails, entity::SpotifyEntity, recommendations::{self, BrowseRecommendations}},
+    player::SpotifyAPIPlayRequest,
+    SpotifyAPI
+};
 use commands::AppCommand;
 use constants::{
-    UI_APP_NAME, UI_DARK_WINDOW_BG_COLOR, UI_DEFAULT_LOCALE, UI_DEFAULT_SCALE, UI_LIGHT_WINDOW_BG_COLOR, UI_SCALE_STEP
+    UI_APP_NAME, UI_DARK_WINDOW_BG_COLOR, UI_DEFAULT_LOCALE, UI_DEFAULT_SCALE, UI_LIGHT_WINDOW_BG_COLOR, UI_ROUTE_LIKED_SONGS, UI_ROUTE_RECENTLY_PLAYED, UI_ROUTE_YOUR_ALBUMS, UI_ROUTE_YOUR_ARTISTS, UI_ROUTE_YOUR_FILES, UI_SCALE_STEP
 };
 use easy_imgui_window::{
     easy_imgui as imgui,
@@ -24,12 +37,18 @@ use easy_imgui_window::{
     AppHandler, Application, Args, EventResult,
 };
 use event::{AppEvent, AppFetchType};
+use futures::StreamExt;
+use history::ListeningHistory;
+use local::index::LocalFilesIndex;
+use playback::{connect::{self, SpotifyConnectEvent}, SpotifyPlayback};
+use rspotify_model::{FullTrack, Id as _, Page, PlaylistItem, SavedAlbum, SavedTrack};
 use semaphore::Semaphore;
-use state::{search::WidgetStateSearchResults, State};
-use tracing::{error, info};
+use state::{library::LibraryChange, search::WidgetStateSearchResults, State};
+use tokio::task::AbortHandle;
+use tracing::{error, info, warn};
 use widget::{
     components::modals::ModalType,
-    preferences::{Preferences, PreferencesCredentials, PreferencesWindowState},
+    preferences::{Preferences, PreferencesCache, PreferencesLocalFiles, PreferencesWindowState},
     theme::{self, UITheme},
     Widget,
 };
@@ -65,6 +84,539 @@ pub struct App {
     widget: WidgetRc,
     event_loop_proxy: Arc<EventLoopProxy<AppEvent>>,
     api: Arc<SpotifyAPI>,
+    playback: Arc<SpotifyPlayback>,
+
+    // None when there's nowhere to keep credentials, we won't remember logins.
+    credentials: Option<Arc<dyn CredentialStore>>,
+    stored_refresh_token: Option<String>,
+
+    // Fetches that write the current account's data into State, cancelled
+    // when the session is reset so they can't land in the next account's.
+    session_tasks: Vec<AbortHandle>,
+
+    #[cfg(feature = "mock-api")]
+    mock_api: Option<api::mock::SpotifyAPIMockServer>,
+}
+
+async fn fetch_remote_playback(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>) {
+    if state.lock().unwrap().devices.remote_device.is_none() {
+        return;
+    }
+
+    match api.get_playback_state().await {
+        Ok(playback) => state.lock().unwrap().devices.set_remote_playback(playback),
+        Err(err) => error!("Failed to fetch remote playback state: {:#?}", err),
+    }
+}
+
+async fn fetch_album(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>, album_id: String) {
+    let album = AlbumDetails::fetch(&api, album_id.clone()).await;
+
+    if let Err(err) = &album {
+        error!("Failed to fetch album {}: {:#?}", album_id, err);
+    }
+
+    // The pane might have been closed while we were waiting.
+    if let Some(pane) = state.lock().unwrap().albums.iter_mut().find(|a| a.id == album_id) {
+        pane.album = Some(album);
+    }
+}
+
+async fn fetch_artist(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>, artist_id: String) {
+    let artist = ArtistDetails::fetch(&api, artist_id.clone()).await;
+
+    if let Err(err) = &artist {
+        error!("Failed to fetch artist {}: {:#?}", artist_id, err);
+    }
+
+    if let Some(pane) = state.lock().unwrap().artists.iter_mut().find(|a| a.id == artist_id) {
+        pane.artist = Some(artist);
+    }
+}
+
+async fn fetch_playlist(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>, playlist_id: String) {
+    let playlist = match api.get_playlist(playlist_id.clone()).await {
+        Ok(playlist) => playlist,
+        Err(err) => {
+            error!("Failed to fetch playlist {}: {:#?}", playlist_id, err);
+
+            if let Some(pane) = state.lock().unwrap().playlists.iter_mut().find(|p| p.id == playlist_id) {
+                pane.playlist = Some(Err(err));
+            }
+
+            return;
+        }
+    };
+
+    let first = playlist.tracks.clone();
+
+    if let Some(pane) = state.lock().unwrap().playlists.iter_mut().find(|p| p.id == playlist_id) {
+        pane.playlist = Some(Ok(Arc::new(playlist)));
+        pane.fetching = true;
+    }
+
+    // Show each page as it arrives rather than waiting on all of them.
+    let mut pages = Box::pin(api.paginate(first, |page: Page<PlaylistItem>| Some(page), None));
+
+    while let Some(items) = pages.next().await {
+        let mut state = state.lock().unwrap();
+
+        // Stop if the pane was closed.
+        let Some(pane) = state.playlists.iter_mut().find(|p| p.id == playlist_id) else {
+            return;
+        };
+
+        match items {
+            Ok(items) => pane.extend(items),
+            Err(err) => {
+                error!("Failed to fetch items for playlist {}: {:#?}", playlist_id, err);
+
+                break;
+            }
+        }
+    }
+
+    if let Some(pane) = state.lock().unwrap().playlists.iter_mut().find(|p| p.id == playlist_id) {
+        pane.fetching = false;
+    }
+}
+
+async fn fetch_liked_songs(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>) {
+    let first = match api.get_saved_tracks(Some(50), None).await {
+        Ok(first) => first,
+        Err(err) => {
+            error!("Failed to fetch liked songs: {:#?}", err);
+
+            let mut state = state.lock().unwrap();
+
+            state.liked_songs.tracks = Some(Err(err));
+            state.liked_songs.fetching = false;
+
+            return;
+        }
+    };
+
+    // Like playlists, show each page as it arrives.
+    let mut pages = Box::pin(api.paginate(first, |page: Page<SavedTrack>| Some(page), None));
+
+    while let Some(items) = pages.next().await {
+        match items {
+            Ok(items) => state.lock().unwrap()
+                .liked_songs
+                .extend(items.into_iter().map(|i| i.track).collect()),
+            Err(err) => {
+                error!("Failed to fetch more liked songs: {:#?}", err);
+
+                break;
+            }
+        }
+    }
+
+    let mut state = state.lock().unwrap();
+
+    // An empty library never gets a page of items to extend with.
+    if state.liked_songs.tracks.is_none() {
+        state.liked_songs.tracks = Some(Ok(Arc::new(Vec::new())));
+    }
+
+    state.liked_songs.fetching = false;
+}
+
+async fn fetch_saved_albums(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>) {
+    let first = match api.get_saved_albums(Some(50), None).await {
+        Ok(first) => first,
+        Err(err) => {
+            error!("Failed to fetch saved albums: {:#?}", err);
+
+            let mut state = state.lock().unwrap();
+
+            state.your_albums.items = Some(Err(err));
+            state.your_albums.fetching = false;
+
+            return;
+        }
+    };
+
+    let mut pages = Box::pin(api.paginate(first, |page: Page<SavedAlbum>| Some(page), None));
+
+    while let Some(items) = pages.next().await {
+        match items {
+            Ok(items) => state.lock().unwrap().your_albums.extend(items),
+            Err(err) => {
+                error!("Failed to fetch more saved albums: {:#?}", err);
+
+                break;
+            }
+        }
+    }
+
+    let mut state = state.lock().unwrap();
+
+    if state.your_albums.items.is_none() {
+        state.your_albums.extend(Vec::new());
+    }
+
+    state.your_albums.fetching = false;
+}
+
+async fn fetch_followed_artists(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>) {
+    let artists = api.get_all_followed_artists().await;
+
+    let mut state = state.lock().unwrap();
+
+    match artists {
+        Ok(artists) => state.your_artists.extend(artists),
+        Err(err) => {
+            error!("Failed to fetch followed artists: {:#?}", err);
+
+            state.your_artists.items = Some(Err(err));
+        }
+    }
+
+    state.your_artists.fetching = false;
+}
+
+async fn fetch_recently_played(api: Arc<SpotifyAPI>, state: Arc<Mutex<State>>, config_dir: Option<PathBuf>) {
+    // We need to know whose history it is before we can keep it.
+    let mut history = config_dir
+        .zip(api.state().and_then(|s| s.profile))
+        .map(|(dir, profile)| ListeningHistory::new(dir, profile.id.id()));
+
+    let (mut plays, history_loaded) = {
+        let state = state.lock().unwrap();
+
+        (state.recently_played.plays.to_vec(), state.recently_played.history_loaded)
+    };
+
+    if let (Some(loading), false) = (history.clone(), history_loaded) {
+        match tokio::task::spawn_blocking(move || loading.load()).await {
+            Ok(loaded) => history::merge(&mut plays, loaded),
+            Err(err) => {
+                error!("Failed to load listening history: {:#?}", err);
+
+                // Saving now would write over everything we couldn't read.
+                history = None;
+            }
+        }
+    }
+
+    let since = plays.first().map(|p| p.played_at);
+
+    let result = api.get_all_recently_played(since).await;
+
+    let changed = match result {
+        Ok(new) => {
+            let changed = !new.is_empty() || !history_loaded;
+
+            history::merge(&mut plays, new);
+
+            changed
+        },
+        Err(err) => {
+            error!("Failed to fetch recently played: {:#?}", err);
+
+            // Still show what we had on disk.
+            if plays.is_empty() {
+                let mut state = state.lock().unwrap();
+
+                state.recently_played.fetching = false;
+                state.recently_played.days = Some(Err(err));
+
+                return;
+            }
+
+            false
+        }
+    };
+
+    let plays = Arc::new(plays);
+
+    {
+        let mut state = state.lock().unwrap();
+
+        state.recently_played.history_loaded |= history.is_some();
+        state.recently_played.set_plays(Arc::clone(&plays));
+    }
+
+    // Written without holding State, the UI shouldn't wait on the disk. We're
+    // still fetching until it's done so another fetch can't save over it.
+    if let (Some(history), true) = (history, changed) {
+        let saved = tokio::task::spawn_blocking(move || history.save(&plays)).await;
+
+        match saved {
+            Ok(Err(err)) => error!("Failed to save listening history: {:#?}", err),
+            Err(err) => error!("Failed to save listening history: {:#?}", err),
+            _ => {}
+        }
+    }
+
+    state.lock().unwrap().recently_played.fetching = false;
+}
+
+async fn scan_local_files(
+    state: Arc<Mutex<State>>,
+    event_loop: Arc<EventLoopProxy<AppEvent>>,
+    config_dir: Option<PathBuf>,
+    folders: Vec<PathBuf>
+) {
+    let index = config_dir.map(LocalFilesIndex::new);
+
+    let (mut files, index_loaded) = {
+        let state = state.lock().unwrap();
+
+        (state.local_files.files.clone(), state.local_files.index_loaded)
+    };
+
+    // Show what we found last time while we look for changes.
+    if let (Some(index), false) = (&index, index_loaded) {
+        files = index.load();
+
+        let mut state = state.lock().unwrap();
+
+        state.local_files.index_loaded = true;
+        state.local_files.set_files(files.clone());
+    }
+
+    let result = tokio::task::spawn_blocking(move || local::scanner::scan(&folders, files)).await;
+
+    let files = match result {
+        Ok(files) => {
+            state.lock().unwrap().local_files.set_files(files.clone());
+
+            Some(files)
+        },
+        Err(err) => {
+            error!("Failed to scan local files: {:#?}", err);
+
+            None
+        }
+    };
+
+    // Written without holding State, the UI shouldn't wait on the disk. We're
+    // still scanning until it's done so another scan can't save over it.
+    if let (Some(index), Some(files)) = (index, files) {
+        let saved = tokio::task::spawn_blocking(move || index.save(&files)).await;
+
+        match saved {
+            Ok(Err(err)) => error!("Failed to save local files index: {:#?}", err),
+            Err(err) => error!("Failed to save local files index: {:#?}", err),
+            _ => {}
+        }
+    }
+
+    let mut state = state.lock().unwrap();
+
+    state.local_files.scanning = false;
+
+    if std::mem::take(&mut state.local_files.rescan) {
+        event_loop.send_event(AppEvent::Command(AppCommand::ScanLocalFiles)).ok();
+    }
+}
+
+fn set_following_artist(state: &Mutex<State>, artist_id: &str, following: bool) -> LibraryChange {
+    let mut state = state.lock().unwrap();
+
+    let artist = state.artists
+        .iter_mut()
+        .find(|a| a.id == artist_id)
+        .and_then(|a| a.artist.as_mut())
+        .and_then(|a| a.as_mut().ok());
+
+    let details = artist.map(|artist| {
+        artist.following = following;
+        artist.artist.clone()
+    });
+
+    // Keep Your Artists in step, wherever the follow came from.
+    state.your_artists.set_saved(details, artist_id, following)
+}
+
+// Puts the follow button and Your Artists back how they were, unless the
+// artist's been followed or unfollowed again since.
+fn revert_following_artist(state: &Mutex<State>, change: LibraryChange) {
+    let mut state = state.lock().unwrap();
+
+    if !state.your_artists.revert(&change) {
+        return;
+    }
+
+    let artist = state.artists
+        .iter_mut()
+        .find(|a| a.id == change.id)
+        .and_then(|a| a.artist.as_mut())
+        .and_then(|a| a.as_mut().ok());
+
+    if let Some(artist) = artist {
+        artist.following = !change.saved;
+    }
+}
+
+impl App {
+    // Where the current account's refresh token lives in the credential store,
+    // logins we haven't put a name to yet go in the default slot.
+    fn credentials_account(&self) -> String {
+        self.widget.borrow()
+            .accounts
+            .current_id()
+            .unwrap_or(CREDENTIALS_DEFAULT_ACCOUNT.to_string())
+    }
+
+    // Tears down everything belonging to the current account, ready for the next.
+    fn reset_session(&mut self, account_id: Option<String>) {
+        self.playback.disconnect();
+        self.api.end_session();
+
+        for task in self.session_tasks.drain(..) {
+            task.abort();
+        }
+
+        let mut widget = self.widget.borrow_mut();
+
+        self.api.cache.set_namespace(account_id.clone());
+        widget.accounts.set_current(account_id);
+
+        let overrides = widget.accounts.current().and_then(|a| a.overrides.clone());
+        widget.preferences.set_overrides(overrides);
+
+        if let Some(max_size_mb) = widget.preferences.get().and_then(|p| p.cache).and_then(|c| c.max_size_mb) {
+            self.api.cache.set_max_bytes(max_size_mb * 1024 * 1024);
+        }
+
+        let mut state = widget.state.lock().unwrap();
+        let current_theme = state.current_theme;
+
+        if let Some(task) = state.search.search_task.take() {
+            task.abort();
+        }
+
+        // Your Files belong to the machine, not the account.
+        let local_files = std::mem::take(&mut state.local_files);
+
+        *state = State {
+            current_theme,
+            local_files,
+            ..Default::default()
+        };
+    }
+
+    // Anything that talks to the API on the current account's behalf goes
+    // through here, so switching accounts can't leave it running.
+    fn spawn_session_task<F>(&mut self, future: F) -> AbortHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = tokio::task::spawn(future).abort_handle();
+
+        self.session_tasks.retain(|task| !task.is_finished());
+        self.session_tasks.push(task.clone());
+
+        task
+    }
+
+    fn remote_device_id(&self) -> Option<String> {
+        self.widget.borrow().state.lock().unwrap()
+            .devices
+            .remote_device
+            .as_ref()
+            .and_then(|d| d.id.clone())
+    }
+
+    // Sends a command to the remote device we're controlling, if any,
+    // then refreshes its playback state so the player bar catches up.
+    // Returns false when the command should be handled locally.
+    fn remote_command<F, Fut>(&mut self, command: F) -> bool
+    where
+        F: FnOnce(Arc<SpotifyAPI>, String) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), SpotifyAPIError>> + Send,
+    {
+        let Some(device_id) = self.remote_device_id() else {
+            return false;
+        };
+
+        let api_arc = Arc::clone(&self.api);
+        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+        self.spawn_session_task(async move {
+            if let Err(err) = command(Arc::clone(&api_arc), device_id).await {
+                error!("Failed to control remote device: {:#?}", err);
+            }
+
+            fetch_remote_playback(api_arc, state_arc).await;
+        });
+
+        true
+    }
+
+    // Other devices can't get at local files, so those always play here.
+    // Pauses the remote we were controlling, if any, and stops controlling it.
+    fn take_back_from_remote(&mut self) {
+        if self.remote_command(|api, device_id| async move {
+            api.pause_playback(Some(device_id)).await
+        }) {
+            let state_arc = Arc::clone(&self.widget.borrow().state);
+            let mut state = state_arc.lock().unwrap();
+
+            state.devices.remote_device = None;
+            state.devices.remote_playback = None;
+        }
+    }
+
+    fn local_folders(&self) -> Vec<PathBuf> {
+        self.widget.borrow()
+            .preferences
+            .get()
+            .and_then(|p| p.local_files)
+            .map(|l| l.folders())
+            .unwrap_or_default()
+    }
+
+    fn set_local_folders(&self, folders: Vec<PathBuf>) {
+        match PreferencesLocalFiles::from_folders(&folders) {
+            Ok(local_files) => {
+                self.widget.borrow_mut().preferences.set(Preferences {
+                    local_files: Some(local_files),
+                    ..Default::default()
+                });
+
+                self.scan_local_files();
+            },
+            Err(err) => error!("Failed to save local files folders: {:#?}", err),
+        }
+    }
+
+    fn scan_local_files(&self) {
+        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+        {
+            let mut state = state_arc.lock().unwrap();
+
+            if state.local_files.scanning {
+                state.local_files.rescan = true;
+
+                return;
+            }
+
+            state.local_files.scanning = true;
+        }
+
+        tokio::task::spawn(scan_local_files(
+            state_arc,
+            Arc::clone(&self.event_loop_proxy),
+            self.widget.borrow().preferences.config_dir(),
+            self.local_folders()
+        ));
+    }
+
+    fn play_queue_track(&self, track: Option<FullTrack>) {
+        let result = match track {
+            Some(track) => self.playback.load(track, true, 0),
+            None => self.playback.stop(),
+        };
+
+        if let Err(err) = result {
+            error!("Failed to play track from queue: {:?}", err);
+        }
+    }
 }
 
 impl Application for App {
@@ -75,17 +627,97 @@ impl Application for App {
         let mut widget = Rc::new(RefCell::new(Widget::new()));
         let event_loop_proxy = Arc::new(args.data.clone());
 
-        let refresh_token = widget
+        let credentials = credentials::open(widget.borrow().preferences.config_dir());
+
+        let plaintext_secret = widget
             .borrow()
             .preferences
             .get()
             .and_then(|p| p.credentials)
             .and_then(|c| c.secret)
-            .and_then(|c| if c.trim().is_empty() { None } else { Some(c) });
+            .filter(|c| !c.trim().is_empty());
+
+        // Older versions kept the refresh token in preferences.toml in the clear.
+        if let (Some(store), Some(secret)) = (&credentials, &plaintext_secret) {
+            if credentials::migrate_plaintext_secret(store.as_ref(), secret) {
+                widget.borrow_mut().preferences.remove_credentials();
+            }
+        }
+
+        let credentials_account = widget
+            .borrow()
+            .accounts
+            .current_id()
+            .unwrap_or(CREDENTIALS_DEFAULT_ACCOUNT.to_string());
+
+        let refresh_token = match &credentials {
+            Some(store) => store.get(&credentials_account)
+                .unwrap_or_else(|err| {
+                    error!("Failed to read saved login from the {}: {:#?}", store.name(), err);
+
+                    None
+                })
+                .or(plaintext_secret),
+            None => plaintext_secret,
+        }
+            .filter(|c| !c.trim().is_empty());
+
+        #[allow(unused_mut)]
+        let mut endpoints = SpotifyAPIEndpoints::from_env();
+
+        // Builds with the mock-api feature can run entirely against the bundled mock API.
+        #[cfg(feature = "mock-api")]
+        let mock_api = std::env::var(api::mock::MOCK_API_ENV)
+            .ok()
+            .and_then(|_| match api::mock::SpotifyAPIMockServer::start() {
+                Ok(server) => Some(server),
+                Err(err) => {
+                    error!("Failed to start mock Spotify API: {:#?}", err);
+
+                    None
+                }
+            });
+
+        #[cfg(feature = "mock-api")]
+        if let Some(mock_api) = &mock_api {
+            endpoints = mock_api.endpoints();
+        }
+
+        let oauth_config = widget
+            .borrow()
+            .preferences
+            .get()
+            .and_then(|p| p.oauth)
+            .unwrap_or_default()
+            .into();
 
         let api = Arc::new(SpotifyAPI::new(
-            Arc::clone(&event_loop_proxy),
-            refresh_token.clone()
+            Some(Arc::clone(&event_loop_proxy)),
+            refresh_token.clone(),
+            endpoints,
+            oauth_config
+        ));
+
+        api.cache.set_namespace(widget.borrow().accounts.current_id());
+
+        if let Some(max_size_mb) = widget.borrow().preferences.get().and_then(|p| p.cache).and_then(|c| c.max_size_mb) {
+            api.cache.set_max_bytes(max_size_mb * 1024 * 1024);
+        }
+
+        let audio_prefs = widget
+            .borrow()
+            .preferences
+            .get()
+            .and_then(|p| p.audio)
+            .unwrap_or_default();
+
+        let device_id = connect::load_device_id(widget.borrow().preferences.config_dir().as_deref());
+
+        let playback = Arc::new(SpotifyPlayback::new(
+            Some(Arc::clone(&event_loop_proxy)),
+            device_id,
+            audio_prefs.backend,
+            audio_prefs.device
         ));
 
         if let Some(token) = refresh_token.clone() {
@@ -98,10 +730,37 @@ impl Application for App {
         widget.borrow_mut()
             .init_window_state(&event_loop_proxy, Arc::clone(&api));
 
+        let discovery_playback = Arc::clone(&playback);
+
+        tokio::task::spawn(async move {
+            discovery_playback.run_discovery().await;
+        });
+
+        #[cfg(target_os = "linux")]
+        {
+            let mpris_event_loop = Arc::clone(&event_loop_proxy);
+            let mpris_send: mpris::MprisEventSender = Arc::new(move |event| {
+                mpris_event_loop.send_event(event).ok();
+            });
+
+            tokio::task::spawn(mpris::run(
+                mpris_send,
+                Arc::clone(&playback),
+                Arc::clone(&widget.borrow().state)
+            ));
+        }
+
         App {
             widget,
             event_loop_proxy,
-            api
+            api,
+            playback,
+            credentials,
+            stored_refresh_token: refresh_token,
+            session_tasks: Vec::new(),
+
+            #[cfg(feature = "mock-api")]
+            mock_api
         }
     }
 
@@ -164,27 +823,57 @@ impl Application for App {
                 let all = matches!(r#type, AppFetchType::All);
                 let volatile = matches!(r#type, AppFetchType::Volatile);
 
+                if volatile {
+                    self.spawn_session_task(fetch_remote_playback(
+                        Arc::clone(&self.api),
+                        Arc::clone(&self.widget.borrow().state)
+                    ));
+                }
+
                 if all || volatile || matches!(r#type, AppFetchType::Profile) {
                     let api_arc = Arc::clone(&self.api);
 
                     let locale = self.widget.borrow().locale();
 
-                    tokio::task::spawn(async move {
+                    self.spawn_session_task(async move {
                         api_arc.fetch_data_wrapper(locale).await;
                     });
                 }
 
+                if all || volatile || matches!(r#type, AppFetchType::RecentlyPlayed) {
+                    let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                    let start = !std::mem::replace(&mut state_arc.lock().unwrap().recently_played.fetching, true);
+
+                    if start {
+                        self.spawn_session_task(fetch_recently_played(
+                            Arc::clone(&self.api),
+                            state_arc,
+                            self.widget.borrow().preferences.config_dir()
+                        ));
+                    }
+                }
+
                 if all || matches!(r#type, AppFetchType::Recommendations) {
                     let api_arc = Arc::clone(&self.api);
                     let state_arc = Arc::clone(&self.widget.borrow_mut().state);
 
                     let locale = self.widget.borrow().locale();
 
-                    tokio::task::spawn(async move {
+                    self.spawn_session_task(async move {
+                        // Show last time's recommendations while we fetch today's.
+                        if let Ok(recommendations) = api_arc.cached().get_browse_recommendations(locale.clone()).await {
+                            let mut state = state_arc.lock().unwrap();
+
+                            if state.recommendations.is_none() {
+                                state.recommendations = Some(Arc::new(recommendations));
+                            }
+                        }
+
                         match api_arc.get_browse_recommendations(locale).await {
                             Ok(recommendations) => {
                                 if let Ok(mut state) = state_arc.lock() {
-                                    state.recommendations = Some(recommendations);
+                                    state.recommendations = Some(Arc::new(recommendations));
                                 }
                             },
                             Err(err) => {
@@ -195,30 +884,77 @@ impl Application for App {
                 }
             },
             AppEvent::FirstTimeLogin => {
-                if self.api.is_logged_in() {
-                    self.widget.borrow_mut().state.lock().unwrap().home_visible = true;
-                } else {
-                    self.widget.borrow_mut().state.lock().unwrap().preferences.visible = true;
-                }
+                if let Some(profile) = self.api.state().and_then(|s| s.profile) {
+                    let previous_account = self.credentials_account();
 
-                args.window
-                    .main_window()
-                    .window()
+                    self.widget.borrow_mut().accounts.upsert(profile);
+                    self.api.cache.set_namespace(self.widget.borrow().accounts.current_id());
+
+                    let account = self.credentials_account();
+
+                    // A fresh login's token went in the default slot, file it under the account.
+                    if let (Some(store), Some(refresh_token)) = (&self.credentials, &self.stored_refresh_token) {
+                        if previous_account != account {
+                            match store.set(&account, refresh_token) {
+                                Ok(_) => { store.delete(&previous_account).ok(); },
+                                Err(err) => error!("Failed to save login to the {}: {:#?}", store.name(), err),
+                            }
+                        }
+                    }
+
+                    let overrides = self.widget.borrow().accounts.current().and_then(|a| a.overrides.clone());
+                    self.widget.borrow_mut().preferences.set_overrides(overrides);
+                }
+
+                if self.api.is_logged_in() {
+                    self.widget.borrow_mut().state.lock().unwrap().home_visible = true;
+
+                    let api_arc = Arc::clone(&self.api);
+                    let playback_arc = Arc::clone(&self.playback);
+
+                    self.spawn_session_task(async move {
+                        match api_arc.access_token().await {
+                            Ok(access_token) => playback_arc.connect(access_token).await,
+                            Err(err) => error!("Failed to get access token for playback: {:#?}", err),
+                        }
+                    });
+                } else {
+                    self.widget.borrow_mut().state.lock().unwrap().preferences.visible = true;
+                }
+
+                args.window
+                    .main_window()
+                    .window()
                     .focus_window();
             },
-            AppEvent::StoreToken(refresh_token) => {
-                let credentials = PreferencesCredentials {
-                    secret: Some(refresh_token.unwrap_or("".to_string()))
-                };
+            AppEvent::StoreToken(generation, refresh_token) => {
+                // From a session that's since been switched away from, it isn't this account's.
+                if generation != self.api.generation() {
+                    return;
+                }
 
-                self
-                    .widget
-                    .borrow_mut()
-                    .preferences
-                    .set(Preferences {
-                        credentials: Some(credentials),
-                        ..Default::default()
-                    });
+                if refresh_token.is_none() {
+                    self.playback.disconnect();
+                }
+
+                // Every successful request sends this, only touch the store when it changes.
+                if refresh_token.is_some() && refresh_token == self.stored_refresh_token {
+                    return;
+                }
+
+                if let Some(store) = &self.credentials {
+                    let account = self.credentials_account();
+
+                    let result = match &refresh_token {
+                        Some(refresh_token) => store.set(&account, refresh_token),
+                        None => store.delete(&account),
+                    };
+
+                    match result {
+                        Ok(_) => self.stored_refresh_token = refresh_token,
+                        Err(err) => error!("Failed to save login to the {}: {:#?}", store.name(), err),
+                    }
+                }
             },
             AppEvent::SetInitialWindowState => {
                 info!("Setting initial window state...");
@@ -247,7 +983,74 @@ impl Application for App {
 
                 self.widget.borrow_mut().ready_for_window_events = true;
             }
+            AppEvent::Focus => {
+                args.window
+                    .main_window()
+                    .window()
+                    .focus_window();
+            },
+            AppEvent::Connect(connect_event) => {
+                match connect_event {
+                    SpotifyConnectEvent::Activated { controller } => {
+                        info!(
+                            "Spotify Connect playback started from {}.",
+                            controller.unwrap_or("a remote device".to_string())
+                        );
+                    },
+                    SpotifyConnectEvent::Deactivated => {
+                        info!("Spotify Connect playback handed back to local queue.");
+                    },
+                    SpotifyConnectEvent::TrackChanged { track_id } => {
+                        let api_arc = Arc::clone(&self.api);
+                        let playback_arc = Arc::clone(&self.playback);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        self.spawn_session_task(async move {
+                            match api_arc.get_track(track_id).await {
+                                Ok(track) => {
+                                    playback_arc.set_remote_track(track.clone());
+                                    state_arc.lock().unwrap().queue.set_remote_track(track);
+                                },
+                                Err(err) => error!("Failed to fetch track played over Spotify Connect: {:#?}", err),
+                            }
+                        });
+                    },
+                    SpotifyConnectEvent::ShuffleChanged(shuffle) => {
+                        self.widget.borrow().state.lock().unwrap().queue.set_shuffle(shuffle);
+                    },
+                    SpotifyConnectEvent::RepeatChanged(repeat) => {
+                        self.widget.borrow().state.lock().unwrap().queue.repeat = repeat;
+                    },
+                }
+            },
+            AppEvent::EndOfTrack => {
+                let track = self.widget.borrow().state.lock().unwrap()
+                    .queue
+                    .next_track(false);
+
+                self.play_queue_track(track);
+            },
+            AppEvent::ImagesLoaded => {
+                self.widget
+                    .borrow_mut()
+                    .images
+                    .upload_ready(args.window.renderer().gl_context());
+
+                args.window.main_window().window().request_redraw();
+            },
             AppEvent::SetTheme(theme) => self.widget.borrow_mut().set_theme(theme, true),
+            AppEvent::SetOAuthConfig(oauth_config) => {
+                *self.api.oauth_config.lock().unwrap() = oauth_config.clone();
+
+                self
+                    .widget
+                    .borrow_mut()
+                    .preferences
+                    .set(Preferences {
+                        oauth: Some(oauth_config.into()),
+                        ..Default::default()
+                    });
+            },
             AppEvent::Command(command) => {
                 info!("Handling application command: {:?}", event.clone());
 
@@ -255,7 +1058,52 @@ impl Application for App {
                 match command {
                     AppCommand::About => self.widget.borrow_mut().open_modal(ModalType::About),
 
-                    AppCommand::Navigate(route) => self.widget.borrow_mut().router(route),
+                    AppCommand::Navigate(route) => {
+                        self.widget.borrow_mut().router(route);
+
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        if route == UI_ROUTE_LIKED_SONGS && state_arc.lock().unwrap().liked_songs.start_fetch() {
+                            self.spawn_session_task(fetch_liked_songs(Arc::clone(&self.api), state_arc));
+                        } else if route == UI_ROUTE_YOUR_ALBUMS && state_arc.lock().unwrap().your_albums.start_fetch() {
+                            self.spawn_session_task(fetch_saved_albums(Arc::clone(&self.api), state_arc));
+                        } else if route == UI_ROUTE_YOUR_ARTISTS && state_arc.lock().unwrap().your_artists.start_fetch() {
+                            self.spawn_session_task(fetch_followed_artists(Arc::clone(&self.api), state_arc));
+                        } else if route == UI_ROUTE_RECENTLY_PLAYED {
+                            self.event_loop_proxy.send_event(AppEvent::Fetch(AppFetchType::RecentlyPlayed)).ok();
+                        } else if route == UI_ROUTE_YOUR_FILES && state_arc.lock().unwrap().local_files.tracks.is_none() {
+                            self.scan_local_files();
+                        }
+                    },
+                    AppCommand::Filter => self.widget.borrow_mut().focus_filter(),
+
+                    AppCommand::Open(SpotifyEntity::Album { id, name }) => {
+                        if self.widget.borrow_mut().open_album(id.clone(), name) {
+                            let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                            self.spawn_session_task(fetch_album(Arc::clone(&self.api), state_arc, id));
+                        }
+                    },
+                    AppCommand::Open(SpotifyEntity::Artist { id, name }) => {
+                        if self.widget.borrow_mut().open_artist(id.clone(), name) {
+                            let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                            self.spawn_session_task(fetch_artist(Arc::clone(&self.api), state_arc, id));
+                        }
+                    },
+                    AppCommand::Open(SpotifyEntity::Playlist { id, name }) => {
+                        if self.widget.borrow_mut().open_playlist(id.clone(), name) {
+                            let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                            self.spawn_session_task(fetch_playlist(Arc::clone(&self.api), state_arc, id));
+                        }
+                    },
+                    AppCommand::Open(entity) => {
+                        // Tracks don't get a pane of their own.
+                        if let Err(err) = self.widget.borrow().open_shell_url(&entity.url()) {
+                            error!("Failed to open {} in the browser: {:#?}", entity.kind(), err);
+                        }
+                    },
 
                     AppCommand::ZoomIn => {
                         let mut widget_mut = self.widget.borrow_mut();
@@ -282,6 +1130,93 @@ impl Application for App {
                         .borrow_mut()
                         .set_ui_scale(&self.event_loop_proxy, UI_DEFAULT_SCALE),
 
+                    AppCommand::SwitchAccount(account_id) => {
+                        if self.widget.borrow().accounts.current_id().as_ref() == Some(&account_id) {
+                            return;
+                        }
+
+                        info!("Switching to account {}...", account_id);
+
+                        let refresh_token = self.credentials
+                            .as_ref()
+                            .and_then(|store| store.get(&account_id).ok().flatten());
+
+                        self.reset_session(Some(account_id));
+                        self.stored_refresh_token = refresh_token.clone();
+
+                        let api_arc = Arc::clone(&self.api);
+
+                        tokio::task::spawn(async move {
+                            api_arc.switch_account(refresh_token).await;
+                        });
+                    },
+                    AppCommand::AddAccount => {
+                        self.reset_session(None);
+                        self.stored_refresh_token = None;
+
+                        self.widget.borrow().state.lock().unwrap().preferences.visible = true;
+
+                        let api_arc = Arc::clone(&self.api);
+
+                        tokio::task::spawn(async move {
+                            api_arc.switch_account(None).await;
+                        });
+                    },
+                    AppCommand::LogOut => {
+                        let account_id = self.widget.borrow().accounts.current_id();
+
+                        if let Some(account_id) = &account_id {
+                            if let Some(store) = &self.credentials {
+                                if let Err(err) = store.delete(account_id) {
+                                    error!("Failed to remove login from the {}: {:#?}", store.name(), err);
+                                }
+                            }
+
+                            self.widget.borrow_mut().accounts.remove(account_id);
+                        }
+
+                        self.reset_session(None);
+                        self.stored_refresh_token = None;
+
+                        self.widget.borrow().state.lock().unwrap().preferences.visible = true;
+
+                        let api_arc = Arc::clone(&self.api);
+
+                        tokio::task::spawn(async move {
+                            api_arc.logout().await;
+                        });
+                    },
+
+                    AppCommand::ClearCache => self.api.cache.clear(),
+                    AppCommand::SetCacheLimit(max_size_mb) => {
+                        self.api.cache.set_max_bytes(max_size_mb * 1024 * 1024);
+
+                        self.widget.borrow_mut().set_account_preferences(Preferences {
+                            cache: Some(PreferencesCache {
+                                max_size_mb: Some(max_size_mb),
+                            }),
+                            ..Default::default()
+                        });
+                    },
+
+                    AppCommand::ScanLocalFiles => self.scan_local_files(),
+                    AppCommand::AddLocalFolder(folder) => {
+                        let mut folders = self.local_folders();
+
+                        if !folders.contains(&folder) {
+                            folders.push(folder);
+
+                            self.set_local_folders(folders);
+                        }
+                    },
+                    AppCommand::RemoveLocalFolder(folder) => {
+                        let mut folders = self.local_folders();
+
+                        folders.retain(|f| *f != folder);
+
+                        self.set_local_folders(folders);
+                    },
+
                     AppCommand::OpenSpotifyAccount => {
                         match self.api.open_accounts_page() {
                             Ok(_) => {}
@@ -292,8 +1227,19 @@ impl Application for App {
                     AppCommand::DoSearch(value) => {
                         let mut state_arc = Arc::clone(&self.widget.borrow().state);
 
-                        if let Some(handle) = &state_arc.lock().unwrap().search.search_task {
-                            handle.abort();
+                        {
+                            let mut state = state_arc.lock().unwrap();
+
+                            if let Some(handle) = &state.search.search_task {
+                                handle.abort();
+                            }
+
+                            // More of the old results would be no use to the new ones.
+                            if let Some(handle) = state.search.more_task.take() {
+                                handle.abort();
+                            }
+
+                            state.search.fetching_more = None;
                         }
 
                         if !value.is_empty() {
@@ -301,7 +1247,7 @@ impl Application for App {
 
                             state_arc.lock().unwrap().search.search_results = WidgetStateSearchResults::Fetching;
 
-                            let task = tokio::task::spawn(async move {
+                            let task = self.spawn_session_task(async move {
                                 state_arc.lock().unwrap().search.search_results = WidgetStateSearchResults::Fetched(api_arc.search(
                                     value,
                                     None,
@@ -313,12 +1259,566 @@ impl Application for App {
 
                             let mut state_arc = Arc::clone(&self.widget.borrow().state);
 
-                            state_arc.lock().unwrap().search.search_task = Some(task.abort_handle());
+                            state_arc.lock().unwrap().search.search_task = Some(task);
                         } else {
                             state_arc.lock().unwrap().search.search_results = WidgetStateSearchResults::None;
                         }
                     },
 
+                    AppCommand::SearchMore(kind) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let mut state = state_arc.lock().unwrap();
+
+                        let results = match &state.search.search_results {
+                            WidgetStateSearchResults::Fetched(Ok(results)) if state.search.fetching_more.is_none() => {
+                                Some(results.clone())
+                            },
+                            _ => None,
+                        };
+
+                        if let Some(results) = results {
+                            let task_state_arc = Arc::clone(&state_arc);
+
+                            let task = self.spawn_session_task(async move {
+                                let more = results.get_more(&api_arc, kind).await;
+
+                                let mut state = task_state_arc.lock().unwrap();
+                                let search = &mut state.search;
+
+                                // The results may have been replaced by another search while we
+                                // fetched, or the task aborted after the page had already come back.
+                                let WidgetStateSearchResults::Fetched(Ok(current)) = &mut search.search_results else {
+                                    return;
+                                };
+
+                                if current.next_page_url(kind) != results.next_page_url(kind) {
+                                    return;
+                                }
+
+                                search.fetching_more = None;
+                                search.more_task = None;
+
+                                match more {
+                                    Ok(more) => current.extend(more),
+                                    Err(err) => error!("Failed to fetch more search results: {:#?}", err),
+                                }
+                            });
+
+                            state.search.fetching_more = Some(kind);
+                            state.search.more_task = Some(task);
+                        }
+                    },
+
+                    AppCommand::RecommendationsMore(index) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let section = state_arc.lock().unwrap()
+                            .recommendations
+                            .as_mut()
+                            .and_then(|r| Arc::make_mut(r).section_mut(index))
+                            .filter(|s| !s.fetching_more)
+                            .map(|s| {
+                                s.fetching_more = true;
+                                s.clone()
+                            });
+
+                        if let Some(section) = section {
+                            self.spawn_session_task(async move {
+                                let more = section.get_more(&api_arc).await;
+
+                                let mut state = state_arc.lock().unwrap();
+
+                                if let Some(section) = state.recommendations.as_mut().and_then(|r| Arc::make_mut(r).section_mut(index)) {
+                                    section.fetching_more = false;
+
+                                    match more {
+                                        Ok(Some(more)) => section.extend(more),
+                                        Ok(None) => {},
+                                        Err(err) => error!("Failed to fetch more playlists for '{}': {:#?}", section.title, err),
+                                    }
+                                }
+                            });
+                        }
+                    },
+                    AppCommand::ArtistAlbumsMore(artist_id, group) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let section = state_arc.lock().unwrap()
+                            .artists
+                            .iter_mut()
+                            .find(|a| a.id == artist_id)
+                            .and_then(|a| a.artist.as_mut())
+                            .and_then(|a| a.as_mut().ok())
+                            .and_then(|a| a.section_mut(group))
+                            .filter(|s| !s.fetching_more)
+                            .map(|s| {
+                                s.fetching_more = true;
+                                s.clone()
+                            });
+
+                        if let Some(section) = section {
+                            self.spawn_session_task(async move {
+                                let more = section.get_more(&api_arc).await;
+
+                                let mut state = state_arc.lock().unwrap();
+
+                                let section = state.artists
+                                    .iter_mut()
+                                    .find(|a| a.id == artist_id)
+                                    .and_then(|a| a.artist.as_mut())
+                                    .and_then(|a| a.as_mut().ok())
+                                    .and_then(|a| a.section_mut(group));
+
+                                if let Some(section) = section {
+                                    section.fetching_more = false;
+
+                                    match more {
+                                        Ok(Some(more)) => section.extend(more),
+                                        Ok(None) => {},
+                                        Err(err) => error!("Failed to fetch more {} for artist {}: {:#?}", group.title(), artist_id, err),
+                                    }
+                                }
+                            });
+                        }
+                    },
+                    AppCommand::FollowArtist(artist_id, follow) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let change = set_following_artist(&state_arc, &artist_id, follow);
+
+                        self.spawn_session_task(async move {
+                            if let Err(err) = api_arc.set_following_artist(artist_id.clone(), follow).await {
+                                error!("Failed to {} artist {}: {:#?}", if follow { "follow" } else { "unfollow" }, artist_id, err);
+
+                                revert_following_artist(&state_arc, change);
+                            }
+                        });
+                    },
+                    AppCommand::SaveAlbum(album_id, saved) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let change = state_arc.lock().unwrap().your_albums.set_saved(None, &album_id, saved);
+
+                        self.spawn_session_task(async move {
+                            if let Err(err) = api_arc.set_saved_albums(vec![album_id.clone()], saved).await {
+                                error!("Failed to {} album {}: {:#?}", if saved { "save" } else { "remove" }, album_id, err);
+
+                                // Unless it's been saved or removed again since.
+                                state_arc.lock().unwrap().your_albums.revert(&change);
+                            }
+                        });
+                    },
+                    AppCommand::SaveTrack(track, saved) => {
+                        let Some(track_id) = track.id.as_ref().map(|id| id.id().to_string()) else {
+                            return;
+                        };
+
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let change = state_arc.lock().unwrap().liked_songs.set_saved(&track, saved);
+
+                        self.spawn_session_task(async move {
+                            if let Err(err) = api_arc.set_saved_tracks(vec![track_id.clone()], saved).await {
+                                error!("Failed to {} track {}: {:#?}", if saved { "save" } else { "remove" }, track_id, err);
+
+                                // Put the heart and Liked Songs back how they were.
+                                if let Some(change) = change {
+                                    state_arc.lock().unwrap().liked_songs.revert(change);
+                                }
+                            }
+                        });
+                    },
+                    AppCommand::CheckSavedTrack(track_id) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        self.spawn_session_task(async move {
+                            match api_arc.contains_saved_tracks(vec![track_id.clone()]).await {
+                                Ok(saved) => {
+                                    state_arc.lock().unwrap()
+                                        .liked_songs
+                                        .saved
+                                        .insert(track_id, saved.first().copied());
+                                },
+                                // Leave it unknown rather than asking again every frame,
+                                // the heart stays disabled for this track.
+                                Err(err) => error!("Failed to check whether track {} is saved: {:#?}", track_id, err),
+                            }
+                        });
+                    },
+
+                    AppCommand::PlayTrack(track) => {
+                        if track.is_local {
+                            self.take_back_from_remote();
+                        } else if let Some(uri) = track.id.as_ref().map(|id| id.uri()) {
+                            let play_request = SpotifyAPIPlayRequest {
+                                uris: Some(vec![uri]),
+                                ..Default::default()
+                            };
+
+                            if self.remote_command(|api, device_id| async move {
+                                api.start_playback(Some(device_id), Some(play_request)).await
+                            }) {
+                                return;
+                            }
+                        }
+
+                        let track = self.widget.borrow().state.lock().unwrap()
+                            .queue
+                            .set_context(None, vec![track], 0);
+
+                        self.play_queue_track(track);
+                    },
+                    AppCommand::PlayContext(context, tracks, start_index) => {
+                        let play_request = SpotifyAPIPlayRequest::from_context(context.as_ref(), &tracks, start_index);
+
+                        if tracks.iter().any(|t| local::track_path(t).is_some()) {
+                            self.take_back_from_remote();
+                        } else if self.remote_command(|api, device_id| async move {
+                            api.start_playback(Some(device_id), Some(play_request)).await
+                        }) {
+                            return;
+                        }
+
+                        let track = self.widget.borrow().state.lock().unwrap()
+                            .queue
+                            .set_context(context, tracks, start_index);
+
+                        self.play_queue_track(track);
+                    },
+                    AppCommand::PlayEntity(entity) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let event_loop = Arc::clone(&self.event_loop_proxy);
+
+                        self.spawn_session_task(async move {
+                            match entity.tracks(&api_arc).await {
+                                Ok((context, tracks)) if !tracks.is_empty() => {
+                                    event_loop
+                                        .send_event(AppEvent::Command(AppCommand::PlayContext(context, tracks, 0)))
+                                        .ok();
+                                },
+                                Ok(_) => warn!("Nothing to play in {} {}", entity.kind(), entity.id()),
+                                Err(err) => error!("Failed to get tracks for {} {}: {:#?}", entity.kind(), entity.id(), err),
+                            }
+                        });
+                    },
+                    AppCommand::NextTrack => {
+                        if self.remote_command(|api, device_id| async move {
+                            api.skip_to_next(Some(device_id)).await
+                        }) {
+                            return;
+                        }
+
+                        if let Some(result) = self.playback.connect.command(|t| t.next()) {
+                            if let Err(err) = result {
+                                error!("Failed to skip track over Spotify Connect: {:?}", err);
+                            }
+
+                            return;
+                        }
+
+                        let track = self.widget.borrow().state.lock().unwrap()
+                            .queue
+                            .next_track(true);
+
+                        self.play_queue_track(track);
+                    },
+                    AppCommand::PreviousTrack => {
+                        if self.remote_command(|api, device_id| async move {
+                            api.skip_to_previous(Some(device_id)).await
+                        }) {
+                            return;
+                        }
+
+                        if let Some(result) = self.playback.connect.command(|t| t.prev()) {
+                            if let Err(err) = result {
+                                error!("Failed to go back over Spotify Connect: {:?}", err);
+                            }
+
+                            return;
+                        }
+
+                        // Restart the current track first, like most players do.
+                        if self.playback.status().current_position_ms() > 3000 {
+                            self.playback.seek(0).ok();
+                        } else {
+                            let track = self.widget.borrow().state.lock().unwrap()
+                                .queue
+                                .previous_track();
+
+                            self.play_queue_track(track);
+                        }
+                    },
+                    AppCommand::SetShuffle(shuffle) => {
+                        let widget = self.widget.borrow();
+                        let mut state = widget.state.lock().unwrap();
+                        let queue = &mut state.queue;
+
+                        if shuffle && !queue.is_shuffled() {
+                            queue.set_shuffle_seed(rand::random());
+                        }
+
+                        queue.set_shuffle(shuffle);
+
+                        drop(state);
+                        drop(widget);
+
+                        self.playback.connect.command(|t| t.set_shuffle(shuffle));
+
+                        self.remote_command(move |api, device_id| async move {
+                            api.set_playback_shuffle(shuffle, Some(device_id)).await
+                        });
+                    },
+                    AppCommand::SetRepeat(repeat) => {
+                        self.widget.borrow().state.lock().unwrap().queue.repeat = repeat;
+
+                        self.playback.connect.command(|t| t.set_repeat(repeat));
+
+                        self.remote_command(move |api, device_id| async move {
+                            api.set_playback_repeat(repeat, Some(device_id)).await
+                        });
+                    },
+                    AppCommand::ToggleShuffle => {
+                        let widget = self.widget.borrow();
+                        let mut state = widget.state.lock().unwrap();
+                        let queue = &mut state.queue;
+
+                        if !queue.is_shuffled() {
+                            queue.set_shuffle_seed(rand::random());
+                        }
+
+                        let shuffle = !queue.is_shuffled();
+                        queue.set_shuffle(shuffle);
+
+                        drop(state);
+                        drop(widget);
+
+                        self.playback.connect.command(|t| t.set_shuffle(shuffle));
+
+                        self.remote_command(move |api, device_id| async move {
+                            api.set_playback_shuffle(shuffle, Some(device_id)).await
+                        });
+                    },
+                    AppCommand::AddToQueue(track) => {
+                        if let Some(uri) = track.id.as_ref().map(|id| id.uri()) {
+                            if self.remote_command(|api, device_id| async move {
+                                api.add_item_to_queue(uri, Some(device_id)).await
+                            }) {
+                                return;
+                            }
+                        }
+
+                        self.widget.borrow().state.lock().unwrap()
+                            .queue
+                            .add_to_queue(track);
+                    },
+                    AppCommand::PlayNext(track) => {
+                        // The Web API only has the one queue, next is as close as it gets.
+                        if let Some(uri) = track.id.as_ref().map(|id| id.uri()) {
+                            if self.remote_command(|api, device_id| async move {
+                                api.add_item_to_queue(uri, Some(device_id)).await
+                            }) {
+                                return;
+                            }
+                        }
+
+                        self.widget.borrow().state.lock().unwrap()
+                            .queue
+                            .play_next(track);
+                    },
+                    AppCommand::CycleRepeat => {
+                        let widget = self.widget.borrow();
+                        let mut state = widget.state.lock().unwrap();
+                        let queue = &mut state.queue;
+
+                        queue.repeat = queue.repeat.cycle();
+
+                        let repeat = queue.repeat;
+
+                        drop(state);
+                        drop(widget);
+
+                        self.playback.connect.command(|t| t.set_repeat(repeat));
+
+                        self.remote_command(move |api, device_id| async move {
+                            api.set_playback_repeat(repeat, Some(device_id)).await
+                        });
+                    },
+                    AppCommand::PlayPause => {
+                        let remote_playing = self.widget.borrow().state.lock().unwrap()
+                            .devices
+                            .remote_playback
+                            .as_ref()
+                            .map(|p| p.is_playing)
+                            .unwrap_or(false);
+
+                        if self.remote_command(move |api, device_id| async move {
+                            if remote_playing {
+                                api.pause_playback(Some(device_id)).await
+                            } else {
+                                api.start_playback(Some(device_id), None).await
+                            }
+                        }) {
+                            return;
+                        }
+
+                        if let Err(err) = self.playback.play_pause() {
+                            error!("Failed to toggle playback: {:?}", err);
+                        }
+                    },
+                    AppCommand::Play => {
+                        if self.remote_command(|api, device_id| async move {
+                            api.start_playback(Some(device_id), None).await
+                        }) {
+                            return;
+                        }
+
+                        if let Err(err) = self.playback.play() {
+                            error!("Failed to resume playback: {:?}", err);
+                        }
+                    },
+                    AppCommand::Pause => {
+                        if self.remote_command(|api, device_id| async move {
+                            api.pause_playback(Some(device_id)).await
+                        }) {
+                            return;
+                        }
+
+                        if let Err(err) = self.playback.pause() {
+                            error!("Failed to pause playback: {:?}", err);
+                        }
+                    },
+                    AppCommand::Stop => {
+                        if let Err(err) = self.playback.stop() {
+                            error!("Failed to stop playback: {:?}", err);
+                        }
+                    },
+                    AppCommand::SeekBy(offset_ms) => {
+                        let remote_progress = self.widget.borrow().state.lock().unwrap()
+                            .devices
+                            .remote_playback
+                            .as_ref()
+                            .and_then(|p| p.progress)
+                            .map(|p| p.num_milliseconds());
+
+                        if let Some(progress_ms) = remote_progress {
+                            let position_ms = (progress_ms + offset_ms).max(0) as u32;
+
+                            if self.remote_command(move |api, device_id| async move {
+                                api.seek_to_position(position_ms, Some(device_id)).await
+                            }) {
+                                return;
+                            }
+                        }
+
+                        let status = self.playback.status();
+
+                        let position_ms = (status.current_position_ms() as i64 + offset_ms)
+                            .clamp(0, status.duration_ms() as i64) as u32;
+
+                        if let Err(err) = self.playback.seek(position_ms) {
+                            error!("Failed to seek: {:?}", err);
+                        }
+                    },
+                    AppCommand::SetVolume(volume) => {
+                        if self.remote_command(move |api, device_id| async move {
+                            api.set_playback_volume(volume, Some(device_id)).await
+                        }) {
+                            return;
+                        }
+
+                        if let Err(err) = self.playback.set_volume(volume) {
+                            error!("Failed to set volume: {:?}", err);
+                        }
+                    },
+                    AppCommand::VolumeBy(offset) => {
+                        // Step from the remote device's volume when something else is playing.
+                        let remote_volume = self.widget.borrow().state.lock().unwrap()
+                            .devices
+                            .remote_playback
+                            .as_ref()
+                            .and_then(|p| p.device.volume_percent)
+                            .map(|v| v as f64 / 100.0);
+
+                        let volume = (remote_volume.unwrap_or_else(|| self.playback.volume()) + offset).clamp(0.0, 1.0);
+
+                        self.event_loop_proxy
+                            .send_event(AppEvent::Command(AppCommand::SetVolume(volume)))
+                            .ok();
+                    },
+                    AppCommand::Seek(position_ms) => {
+                        if self.remote_command(move |api, device_id| async move {
+                            api.seek_to_position(position_ms, Some(device_id)).await
+                        }) {
+                            return;
+                        }
+
+                        if let Err(err) = self.playback.seek(position_ms) {
+                            error!("Failed to seek: {:?}", err);
+                        }
+                    },
+
+                    AppCommand::FetchDevices => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        state_arc.lock().unwrap().devices.fetching = true;
+
+                        self.spawn_session_task(async move {
+                            let devices = api_arc.get_available_devices().await;
+
+                            if let Err(err) = &devices {
+                                error!("Failed to fetch Spotify Connect devices: {:#?}", err);
+                            }
+
+                            let mut state = state_arc.lock().unwrap();
+
+                            state.devices.fetching = false;
+                            state.devices.devices = Some(devices);
+                        });
+                    },
+                    AppCommand::TransferPlayback(device_id) => {
+                        let api_arc = Arc::clone(&self.api);
+                        let state_arc = Arc::clone(&self.widget.borrow().state);
+
+                        let device = state_arc.lock().unwrap()
+                            .devices
+                            .devices
+                            .as_ref()
+                            .and_then(|d| d.as_ref().ok())
+                            .and_then(|d| d.iter().find(|d| d.id.as_ref() == Some(&device_id)).cloned());
+
+                        let is_local = device.as_ref().is_some_and(|d| connect::is_local_device(d, &self.playback.device_id));
+
+                        // Don't keep playing here while the music moves elsewhere.
+                        if !is_local {
+                            self.playback.pause().ok();
+                        }
+
+                        self.spawn_session_task(async move {
+                            match api_arc.transfer_playback(device_id, true).await {
+                                Ok(_) => {
+                                    {
+                                        let mut state = state_arc.lock().unwrap();
+
+                                        state.devices.remote_device = if is_local { None } else { device };
+                                        state.devices.remote_playback = None;
+                                    }
+
+                                    fetch_remote_playback(api_arc, state_arc).await;
+                                },
+                                Err(err) => error!("Failed to transfer playback: {:#?}", err),
+                            }
+                        });
+                    },
+
                     AppCommand::Quit => args.event_loop.exit(),
                     _ => {}
                 }
@@ -387,7 +1887,12 @@ impl imgui::UiBuilder for App {
         widget::style::push_style(&self.widget, ui, || {
             self.widget
                 .borrow_mut()
-                .paint_ui(&self.event_loop_proxy, ui, Arc::clone(&self.api));
+                .paint_ui(
+                    &self.event_loop_proxy,
+                    ui,
+                    Arc::clone(&self.api),
+                    Arc::clone(&self.playback)
+                );
         });
     }
 }
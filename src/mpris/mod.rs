@@ -0,0 +1,562 @@
+use std::{collections::HashMap, sync::{Arc, Mutex}, time::Duration};
+
+use rspotify_model::Id as _;
+use tracing::{error, info};
+use zbus::{
+    connection, interface,
+    object_server::SignalContext,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection,
+};
+
+use crate::{
+    commands::AppCommand,
+    constants::UI_APP_NAME,
+    event::AppEvent,
+    local,
+    playback::{status::SpotifyPlaybackStatus, SpotifyPlayback},
+    state::{queue::RepeatMode, State},
+};
+
+pub const MPRIS_BUS_NAME: &str = "org.mpris.MediaPlayer2.spottyfi";
+pub const MPRIS_OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+const MPRIS_NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+// Anything the D-Bus interfaces want the app to do goes through here,
+// so they can be driven without a window in tests.
+pub type MprisEventSender = Arc<dyn Fn(AppEvent) + Send + Sync>;
+
+fn track_object_path(status: &SpotifyPlaybackStatus) -> OwnedObjectPath {
+    let path = status.track
+        .as_ref()
+        .and_then(|t| t.id.as_ref())
+        .map(|id| format!("/org/mpris/MediaPlayer2/Track/{}", id.id()))
+        .unwrap_or(MPRIS_NO_TRACK.to_string());
+
+    ObjectPath::try_from(path)
+        .unwrap_or(ObjectPath::from_static_str_unchecked(MPRIS_NO_TRACK))
+        .into()
+}
+
+fn owned<'a>(value: impl Into<Value<'a>>) -> OwnedValue {
+    value.into()
+        .try_to_owned()
+        .expect("Metadata values never contain file descriptors")
+}
+
+pub struct MprisRoot {
+    send: MprisEventSender,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl MprisRoot {
+    fn raise(&self) {
+        (self.send)(AppEvent::Focus);
+    }
+
+    fn quit(&self) {
+        (self.send)(AppEvent::Command(AppCommand::Quit));
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        UI_APP_NAME.to_string()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        UI_APP_NAME.to_lowercase()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        vec![]
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+pub struct MprisPlayer {
+    send: MprisEventSender,
+    playback: Arc<SpotifyPlayback>,
+    state: Arc<Mutex<State>>,
+}
+
+impl MprisPlayer {
+    fn command(&self, command: AppCommand) {
+        (self.send)(AppEvent::Command(command));
+    }
+
+    // Spotify's tracks need a session, Your Files play without one.
+    fn has_playable(&self) -> bool {
+        if self.playback.is_connected() || self.playback.status().track.is_some_and(|t| t.is_local) {
+            return true;
+        }
+
+        let state = self.state.lock().unwrap();
+        let queue = &state.queue;
+
+        queue.now_playing()
+            .into_iter()
+            .chain(queue.user_queue.iter())
+            .chain(queue.context_tracks().iter())
+            .any(|t| local::track_path(t).is_some())
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl MprisPlayer {
+    fn next(&self) {
+        self.command(AppCommand::NextTrack);
+    }
+
+    fn previous(&self) {
+        self.command(AppCommand::PreviousTrack);
+    }
+
+    fn pause(&self) {
+        self.command(AppCommand::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.command(AppCommand::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.command(AppCommand::Stop);
+    }
+
+    fn play(&self) {
+        self.command(AppCommand::Play);
+    }
+
+    // MPRIS positions are in microseconds, ours are in milliseconds.
+    fn seek(&self, offset: i64) {
+        self.command(AppCommand::SeekBy(offset / 1000));
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        let status = self.playback.status();
+
+        // The spec says to ignore stale requests for a different track.
+        if track_object_path(&status).as_str() != track_id.as_str() || position < 0 {
+            return;
+        }
+
+        self.command(AppCommand::Seek((position / 1000) as u32));
+    }
+
+    fn open_uri(&self, _uri: String) {}
+
+    #[zbus(signal)]
+    async fn seeked(ctxt: &SignalContext<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        let status = self.playback.status();
+
+        if status.playing {
+            "Playing"
+        } else if status.track.is_some() {
+            "Paused"
+        } else {
+            "Stopped"
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn loop_status(&self) -> String {
+        match self.state.lock().unwrap().queue.repeat {
+            RepeatMode::Off => "None",
+            RepeatMode::Context => "Playlist",
+            RepeatMode::Track => "Track",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn set_loop_status(&mut self, value: String) {
+        let repeat = match value.as_str() {
+            "Playlist" => RepeatMode::Context,
+            "Track" => RepeatMode::Track,
+            _ => RepeatMode::Off,
+        };
+
+        self.command(AppCommand::SetRepeat(repeat));
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn set_rate(&mut self, _value: f64) {}
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn shuffle(&self) -> bool {
+        self.state.lock().unwrap().queue.is_shuffled()
+    }
+
+    #[zbus(property)]
+    fn set_shuffle(&mut self, value: bool) {
+        self.command(AppCommand::SetShuffle(value));
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let status = self.playback.status();
+
+        let mut metadata = HashMap::new();
+
+        metadata.insert("mpris:trackid".to_string(), owned(track_object_path(&status)));
+
+        if let Some(track) = &status.track {
+            metadata.insert("mpris:length".to_string(), owned(status.duration_ms() as i64 * 1000));
+            metadata.insert("xesam:title".to_string(), owned(track.name.clone()));
+            metadata.insert("xesam:album".to_string(), owned(track.album.name.clone()));
+            metadata.insert(
+                "xesam:artist".to_string(),
+                owned(track.artists.iter().map(|a| a.name.clone()).collect::<Vec<String>>())
+            );
+
+            if let Some(image) = track.album.images.first() {
+                metadata.insert("mpris:artUrl".to_string(), owned(image.url.clone()));
+            }
+
+            if let Some(id) = &track.id {
+                metadata.insert("xesam:url".to_string(), owned(id.url()));
+            }
+        }
+
+        metadata
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.playback.volume()
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, value: f64) {
+        self.command(AppCommand::SetVolume(value));
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.playback.status().current_position_ms() as i64 * 1000
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.has_playable()
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.has_playable()
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.has_playable()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.has_playable()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.playback.status().track.is_some()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Serves the MPRIS interfaces on the session bus, or on `address`
+/// if given, e.g. a private dbus-daemon.
+pub async fn serve(
+    address: Option<&str>,
+    send: MprisEventSender,
+    playback: Arc<SpotifyPlayback>,
+    state: Arc<Mutex<State>>,
+) -> zbus::Result<Connection> {
+    let builder = match address {
+        Some(address) => connection::Builder::address(address)?,
+        None => connection::Builder::session()?,
+    };
+
+    let connection = builder
+        .name(MPRIS_BUS_NAME)?
+        .serve_at(MPRIS_OBJECT_PATH, MprisRoot { send: Arc::clone(&send) })?
+        .serve_at(MPRIS_OBJECT_PATH, MprisPlayer { send, playback, state })?
+        .build()
+        .await?;
+
+    info!("Registered {} on D-Bus.", MPRIS_BUS_NAME);
+
+    Ok(connection)
+}
+
+#[derive(PartialEq)]
+struct MprisSnapshot {
+    track: OwnedObjectPath,
+    playing: bool,
+    shuffle: bool,
+    loop_status: String,
+    volume: i64,
+    can_play: bool,
+    can_pause: bool,
+    can_go_next: bool,
+    can_go_previous: bool,
+}
+
+// Seeking moves the position by more than the poll interval would,
+// a new track starting from the top isn't a seek though.
+fn is_seek(last: Option<(&MprisSnapshot, i64)>, snapshot: &MprisSnapshot, position: i64) -> bool {
+    let Some((last_snapshot, last_position)) = last else {
+        return false;
+    };
+
+    if last_snapshot.track != snapshot.track {
+        return false;
+    }
+
+    let expected = last_position + if snapshot.playing { 1000 } else { 0 };
+
+    (position - expected).abs() > 1500
+}
+
+// zbus has no way of knowing when our playback state changes,
+// so poll it and emit PropertiesChanged when something differs.
+pub async fn watch(connection: Connection) -> zbus::Result<()> {
+    let player_ref = connection
+        .object_server()
+        .interface::<_, MprisPlayer>(MPRIS_OBJECT_PATH)
+        .await?;
+
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+    let mut last: Option<(MprisSnapshot, i64)> = None;
+
+    loop {
+        interval.tick().await;
+
+        let player = player_ref.get().await;
+        let ctxt = player_ref.signal_context();
+
+        let status = player.playback.status();
+        let position = status.current_position_ms() as i64;
+
+        let snapshot = MprisSnapshot {
+            track: track_object_path(&status),
+            playing: status.playing,
+            shuffle: player.shuffle(),
+            loop_status: player.loop_status(),
+            volume: (player.volume() * 100.0).round() as i64,
+            can_play: player.can_play(),
+            can_pause: player.can_pause(),
+            can_go_next: player.can_go_next(),
+            can_go_previous: player.can_go_previous(),
+        };
+
+        if is_seek(last.as_ref().map(|(s, p)| (s, *p)), &snapshot, position) {
+            MprisPlayer::seeked(ctxt, position * 1000).await?;
+        }
+
+        if last.as_ref().map(|(s, _)| s) != Some(&snapshot) {
+            player.playback_status_changed(ctxt).await?;
+            player.metadata_changed(ctxt).await?;
+            player.shuffle_changed(ctxt).await?;
+            player.loop_status_changed(ctxt).await?;
+            player.volume_changed(ctxt).await?;
+            player.can_seek_changed(ctxt).await?;
+            player.can_play_changed(ctxt).await?;
+            player.can_pause_changed(ctxt).await?;
+            player.can_go_next_changed(ctxt).await?;
+            player.can_go_previous_changed(ctxt).await?;
+        }
+
+        last = Some((snapshot, position));
+    }
+}
+
+pub async fn run(send: MprisEventSender, playback: Arc<SpotifyPlayback>, state: Arc<Mutex<State>>) {
+    match serve(None, send, playback, state).await {
+        Ok(connection) => {
+            if let Err(err) = watch(connection).await {
+                error!("MPRIS property watcher stopped: {:#?}", err);
+            }
+        },
+        Err(err) => error!("Failed to register MPRIS interface on D-Bus: {:#?}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        time::SystemTime,
+    };
+
+    use zbus::proxy::CacheProperties;
+
+    use super::*;
+    use crate::local::LocalTrack;
+
+    // A bus of our own, so tests neither need nor touch the session bus.
+    struct DbusDaemon {
+        child: Child,
+        address: String,
+    }
+
+    impl DbusDaemon {
+        fn start() -> Option<Self> {
+            let mut child = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+
+            BufReader::new(child.stdout.take()?).read_line(&mut address).ok()?;
+
+            Some(DbusDaemon { child, address: address.trim().to_string() })
+        }
+    }
+
+    impl Drop for DbusDaemon {
+        fn drop(&mut self) {
+            self.child.kill().ok();
+            self.child.wait().ok();
+        }
+    }
+
+    fn snapshot(track: &'static str, playing: bool) -> MprisSnapshot {
+        MprisSnapshot {
+            track: ObjectPath::from_static_str_unchecked(track).into(),
+            playing,
+            shuffle: false,
+            loop_status: "None".to_string(),
+            volume: 100,
+            can_play: true,
+            can_pause: true,
+            can_go_next: true,
+            can_go_previous: true,
+        }
+    }
+
+    #[test]
+    fn detects_seeks() {
+        let last = snapshot("/org/mpris/MediaPlayer2/Track/a", true);
+        let current = snapshot("/org/mpris/MediaPlayer2/Track/a", true);
+
+        assert!(!is_seek(None, &current, 5000));
+        assert!(!is_seek(Some((&last, 5000)), &current, 6000));
+        assert!(is_seek(Some((&last, 5000)), &current, 60000));
+        assert!(is_seek(Some((&last, 5000)), &current, 0));
+    }
+
+    #[test]
+    fn ignores_new_track_starting() {
+        let last = snapshot("/org/mpris/MediaPlayer2/Track/a", true);
+        let current = snapshot("/org/mpris/MediaPlayer2/Track/b", true);
+
+        assert!(!is_seek(Some((&last, 120000)), &current, 0));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon installed, run with --ignored"]
+    async fn serves_player_on_private_bus() {
+        let daemon = DbusDaemon::start().expect("Failed to start dbus-daemon");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_send = Arc::clone(&events);
+
+        let send: MprisEventSender = Arc::new(move |event| {
+            events_send.lock().unwrap().push(event);
+        });
+
+        let playback = Arc::new(SpotifyPlayback::new(None, "0".repeat(40), None, None));
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let _server = serve(Some(&daemon.address), send, playback, Arc::clone(&state)).await.unwrap();
+
+        let client = connection::Builder::address(daemon.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let player = zbus::proxy::Builder::<zbus::Proxy>::new(&client)
+            .destination(MPRIS_BUS_NAME).unwrap()
+            .path(MPRIS_OBJECT_PATH).unwrap()
+            .interface("org.mpris.MediaPlayer2.Player").unwrap()
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(player.get_property::<String>("PlaybackStatus").await.unwrap(), "Stopped");
+
+        // Not logged in and nothing of our own queued, there's nothing to play.
+        assert!(!player.get_property::<bool>("CanPlay").await.unwrap());
+        assert!(!player.get_property::<bool>("CanGoNext").await.unwrap());
+
+        let local_track = LocalTrack::new("/music/song.flac".into(), SystemTime::now())
+            .to_full_track()
+            .unwrap();
+
+        state.lock().unwrap().queue.set_context(None, vec![local_track], 0);
+
+        assert!(player.get_property::<bool>("CanPlay").await.unwrap());
+        assert!(player.get_property::<bool>("CanGoPrevious").await.unwrap());
+
+        player.call_method("Next", &()).await.unwrap();
+        player.call_method("Seek", &(2_000_000i64)).await.unwrap();
+        player.set_property("LoopStatus", "Track").await.unwrap();
+
+        assert_eq!(*events.lock().unwrap(), vec![
+            AppEvent::Command(AppCommand::NextTrack),
+            AppEvent::Command(AppCommand::SeekBy(2000)),
+            AppEvent::Command(AppCommand::SetRepeat(RepeatMode::Track)),
+        ]);
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use librespot::{connect::spirc::Spirc, playback::player::PlayerEvent};
+use rspotify_model::Device;
+use tracing::warn;
+
+use crate::{
+    event::AppEvent,
+    state::queue::RepeatMode,
+};
+
+use super::error::SpotifyPlaybackError;
+
+// Changes made by whoever is controlling us over Spotify Connect,
+// main applies these to the player bar and the queue in State.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpotifyConnectEvent {
+    Activated { controller: Option<String> },
+    Deactivated,
+    TrackChanged { track_id: String },
+    ShuffleChanged(bool),
+    RepeatChanged(RepeatMode),
+}
+
+pub type SpotifyConnectEventSender = Arc<dyn Fn(AppEvent) + Send + Sync>;
+
+const DEVICE_ID_FILE_NAME: &str = "device_id";
+
+fn is_device_id(id: &str) -> bool {
+    id.len() == 40 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Device ID we announce ourselves with. Made up the first time and kept in
+/// `config_dir`, so phones don't see a new device every time we launch, and
+/// two computers running the app never look like the same device.
+pub fn load_device_id(config_dir: Option<&Path>) -> String {
+    let path = config_dir.map(|dir| dir.join(DEVICE_ID_FILE_NAME));
+
+    let saved = path.as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .filter(|id| is_device_id(id));
+
+    if let Some(id) = saved {
+        return id;
+    }
+
+    let id = hex::encode(rand::random::<[u8; 20]>());
+
+    if let Some(path) = path {
+        let written = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, &id));
+
+        if let Err(err) = written {
+            warn!("Failed to save device ID to {}, we'll get a new one next launch: {:#?}", path.display(), err);
+        }
+    }
+
+    id
+}
+
+/// Whether a device from the Web API's device list is us. Goes by ID alone,
+/// other computers running the app share our name.
+pub fn is_local_device(device: &Device, device_id: &str) -> bool {
+    device.id.as_deref() == Some(device_id)
+}
+
+/// The commands we send back over Spotify Connect while a remote
+/// is in control. Implemented by librespot's Spirc, and by fakes
+/// so the receiver can be driven without a Spotify session.
+pub trait SpotifyConnectTransport: Send + Sync {
+    fn play(&self) -> Result<(), SpotifyPlaybackError>;
+    fn pause(&self) -> Result<(), SpotifyPlaybackError>;
+    fn play_pause(&self) -> Result<(), SpotifyPlaybackError>;
+    fn next(&self) -> Result<(), SpotifyPlaybackError>;
+    fn prev(&self) -> Result<(), SpotifyPlaybackError>;
+    fn seek(&self, position_ms: u32) -> Result<(), SpotifyPlaybackError>;
+    fn set_volume(&self, volume: u16) -> Result<(), SpotifyPlaybackError>;
+    fn set_shuffle(&self, shuffle: bool) -> Result<(), SpotifyPlaybackError>;
+    fn set_repeat(&self, repeat: RepeatMode) -> Result<(), SpotifyPlaybackError>;
+    fn shutdown(&self) -> Result<(), SpotifyPlaybackError>;
+}
+
+fn spirc_result(result: Result<(), librespot::core::Error>) -> Result<(), SpotifyPlaybackError> {
+    result.map_err(|e| SpotifyPlaybackError::SessionError(Arc::new(e)))
+}
+
+impl SpotifyConnectTransport for Spirc {
+    fn play(&self) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::play(self))
+    }
+
+    fn pause(&self) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::pause(self))
+    }
+
+    fn play_pause(&self) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::play_pause(self))
+    }
+
+    fn next(&self) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::next(self))
+    }
+
+    fn prev(&self) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::prev(self))
+    }
+
+    fn seek(&self, position_ms: u32) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::set_position_ms(self, position_ms))
+    }
+
+    fn set_volume(&self, volume: u16) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::set_volume(self, volume))
+    }
+
+    fn set_shuffle(&self, shuffle: bool) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::shuffle(self, shuffle))
+    }
+
+    fn set_repeat(&self, repeat: RepeatMode) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::repeat(self, repeat != RepeatMode::Off))?;
+        spirc_result(Spirc::repeat_track(self, repeat == RepeatMode::Track))
+    }
+
+    fn shutdown(&self) -> Result<(), SpotifyPlaybackError> {
+        spirc_result(Spirc::shutdown(self))
+    }
+}
+
+#[derive(Clone)]
+pub struct SpotifyConnectReceiver {
+    // Optional so the receiver can run headless.
+    send: Option<SpotifyConnectEventSender>,
+
+    transport: Arc<Mutex<Option<Box<dyn SpotifyConnectTransport>>>>,
+
+    // Whether a remote device started what's currently playing,
+    // while active, playback commands are sent back over Connect.
+    active: Arc<Mutex<bool>>,
+    controller: Arc<Mutex<Option<String>>>,
+}
+
+impl SpotifyConnectReceiver {
+    pub fn new(send: Option<SpotifyConnectEventSender>) -> Self {
+        SpotifyConnectReceiver {
+            send,
+
+            transport: Arc::new(Mutex::new(None)),
+
+            active: Arc::new(Mutex::new(false)),
+            controller: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.transport.lock().unwrap().is_some()
+    }
+
+    pub fn is_active(&self) -> bool {
+        *self.active.lock().unwrap()
+    }
+
+    pub fn controller(&self) -> Option<String> {
+        self.controller.lock().unwrap().clone()
+    }
+
+    fn emit(&self, event: SpotifyConnectEvent) {
+        if let Some(send) = &self.send {
+            send(AppEvent::Connect(event));
+        }
+    }
+
+    pub fn attach(&self, transport: Box<dyn SpotifyConnectTransport>) {
+        self.detach();
+
+        *self.transport.lock().unwrap() = Some(transport);
+    }
+
+    pub fn detach(&self) {
+        if let Some(transport) = self.transport.lock().unwrap().take() {
+            transport.shutdown().ok();
+        }
+
+        self.deactivate();
+    }
+
+    /// Hands control back to the local queue, e.g. when the user
+    /// picks something to play from within the app.
+    pub fn deactivate(&self) {
+        let was_active = std::mem::replace(&mut *self.active.lock().unwrap(), false);
+
+        if was_active {
+            self.emit(SpotifyConnectEvent::Deactivated);
+        }
+    }
+
+    fn activate(&self) {
+        let was_active = std::mem::replace(&mut *self.active.lock().unwrap(), true);
+
+        if !was_active {
+            self.emit(SpotifyConnectEvent::Activated {
+                controller: self.controller(),
+            });
+        }
+    }
+
+    /// Runs `command` against the transport if a remote is in control.
+    /// Returns None when the command should be handled locally instead.
+    pub fn command(
+        &self,
+        command: impl FnOnce(&dyn SpotifyConnectTransport) -> Result<(), SpotifyPlaybackError>,
+    ) -> Option<Result<(), SpotifyPlaybackError>> {
+        if !self.is_active() {
+            return None;
+        }
+
+        self.transport
+            .lock()
+            .unwrap()
+            .as_deref()
+            .map(command)
+    }
+
+    fn handle_track_changed(&self, local_track_id: Option<&str>, track_id: String) {
+        if local_track_id == Some(track_id.as_str()) {
+            return;
+        }
+
+        self.activate();
+        self.emit(SpotifyConnectEvent::TrackChanged { track_id });
+    }
+
+    /// `local_track_id` is the track we last loaded ourselves, anything else
+    /// the player starts must have come from a remote device.
+    pub fn handle_player_event(&self, local_track_id: Option<&str>, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::SessionClientChanged { client_name, .. } => {
+                *self.controller.lock().unwrap() = Some(client_name.clone())
+                    .filter(|n| !n.is_empty());
+            },
+            PlayerEvent::TrackChanged { audio_item } => {
+                if let Ok(track_id) = audio_item.track_id.to_base62() {
+                    self.handle_track_changed(local_track_id, track_id);
+                }
+            },
+            PlayerEvent::ShuffleChanged { shuffle } if self.is_active() => {
+                self.emit(SpotifyConnectEvent::ShuffleChanged(*shuffle));
+            },
+            PlayerEvent::RepeatChanged { context, track } if self.is_active() => {
+                let repeat = match (context, track) {
+                    (_, true) => RepeatMode::Track,
+                    (true, false) => RepeatMode::Context,
+                    (false, false) => RepeatMode::Off,
+                };
+
+                self.emit(SpotifyConnectEvent::RepeatChanged(repeat));
+            },
+            PlayerEvent::SessionDisconnected { .. } => {
+                *self.controller.lock().unwrap() = None;
+
+                self.deactivate();
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::testing::FakeTransport;
+
+    fn receiver() -> (SpotifyConnectReceiver, FakeTransport, Arc<Mutex<Vec<AppEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_send = Arc::clone(&events);
+
+        let receiver = SpotifyConnectReceiver::new(Some(Arc::new(move |event| {
+            events_send.lock().unwrap().push(event);
+        })));
+
+        let transport = FakeTransport::default();
+
+        receiver.attach(Box::new(transport.clone()));
+
+        (receiver, transport, events)
+    }
+
+    fn connect_events(events: &Arc<Mutex<Vec<AppEvent>>>) -> Vec<SpotifyConnectEvent> {
+        events.lock().unwrap()
+            .iter()
+            .filter_map(|e| match e {
+                AppEvent::Connect(event) => Some(event.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn handles_commands_locally_until_a_remote_takes_over() {
+        let (receiver, transport, _) = receiver();
+
+        assert!(receiver.command(|t| t.play()).is_none());
+
+        receiver.handle_track_changed(None, "remote".to_string());
+
+        assert!(receiver.is_active());
+        assert!(receiver.command(|t| t.seek(1000)).unwrap().is_ok());
+        assert_eq!(transport.calls(), vec!["seek 1000"]);
+    }
+
+    #[test]
+    fn ignores_tracks_we_loaded_ourselves() {
+        let (receiver, _, events) = receiver();
+
+        receiver.handle_track_changed(Some("ours"), "ours".to_string());
+
+        assert!(!receiver.is_active());
+        assert!(connect_events(&events).is_empty());
+    }
+
+    #[test]
+    fn emits_activation_once() {
+        let (receiver, _, events) = receiver();
+
+        receiver.handle_track_changed(None, "first".to_string());
+        receiver.handle_track_changed(None, "second".to_string());
+
+        assert_eq!(connect_events(&events), vec![
+            SpotifyConnectEvent::Activated { controller: None },
+            SpotifyConnectEvent::TrackChanged { track_id: "first".to_string() },
+            SpotifyConnectEvent::TrackChanged { track_id: "second".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn only_follows_shuffle_and_repeat_while_active() {
+        let (receiver, _, events) = receiver();
+
+        receiver.handle_player_event(None, &PlayerEvent::ShuffleChanged { shuffle: true });
+
+        assert!(connect_events(&events).is_empty());
+
+        receiver.handle_track_changed(None, "remote".to_string());
+        receiver.handle_player_event(None, &PlayerEvent::ShuffleChanged { shuffle: true });
+        receiver.handle_player_event(None, &PlayerEvent::RepeatChanged { context: true, track: false });
+        receiver.handle_player_event(None, &PlayerEvent::RepeatChanged { context: true, track: true });
+
+        assert_eq!(connect_events(&events)[2..], [
+            SpotifyConnectEvent::ShuffleChanged(true),
+            SpotifyConnectEvent::RepeatChanged(RepeatMode::Context),
+            SpotifyConnectEvent::RepeatChanged(RepeatMode::Track),
+        ]);
+    }
+
+    #[test]
+    fn deactivates_once() {
+        let (receiver, _, events) = receiver();
+
+        receiver.handle_track_changed(None, "remote".to_string());
+        receiver.deactivate();
+        receiver.deactivate();
+
+        assert!(receiver.command(|t| t.pause()).is_none());
+        assert_eq!(connect_events(&events).last(), Some(&SpotifyConnectEvent::Deactivated));
+        assert_eq!(connect_events(&events).iter().filter(|e| **e == SpotifyConnectEvent::Deactivated).count(), 1);
+    }
+
+    #[test]
+    fn shuts_down_replaced_transports() {
+        let (receiver, first, _) = receiver();
+        let second = FakeTransport::default();
+
+        receiver.attach(Box::new(second.clone()));
+
+        assert_eq!(first.calls(), vec!["shutdown"]);
+
+        receiver.detach();
+
+        assert_eq!(second.calls(), vec!["shutdown"]);
+        assert!(!receiver.is_attached());
+    }
+
+    #[test]
+    fn keeps_device_id_across_launches() {
+        let dir = tempfile::tempdir().unwrap();
+        let other_dir = tempfile::tempdir().unwrap();
+
+        let id = load_device_id(Some(dir.path()));
+
+        assert!(is_device_id(&id));
+        assert_eq!(load_device_id(Some(dir.path())), id);
+        assert_ne!(load_device_id(Some(other_dir.path())), id);
+    }
+
+    #[test]
+    fn replaces_invalid_device_id() {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(dir.path().join(DEVICE_ID_FILE_NAME), "not an id").unwrap();
+
+        let id = load_device_id(Some(dir.path()));
+
+        assert!(is_device_id(&id));
+        assert_eq!(fs::read_to_string(dir.path().join(DEVICE_ID_FILE_NAME)).unwrap(), id);
+    }
+
+    #[test]
+    fn matches_local_device_by_id() {
+        let device = |id: &str, name: &str| serde_json::from_value::<Device>(serde_json::json!({
+            "id": id,
+            "is_active": false,
+            "is_private_session": false,
+            "is_restricted": false,
+            "name": name,
+            "type": "Computer",
+            "volume_percent": 100
+        })).unwrap();
+
+        let id = "a".repeat(40);
+
+        assert!(is_local_device(&device(&id, "Elsewhere"), &id));
+        assert!(!is_local_device(&device(&"b".repeat(40), crate::api::constants::SPOTIFY_DEVICE_NAME), &id));
+    }
+}
@@ -0,0 +1,9 @@
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+pub enum SpotifyPlaybackError {
+    NotConnected,
+    NoAudioBackend(Option<String>),
+    SessionError(Arc<librespot::core::Error>),
+    InvalidTrack(String),
+}
@@ -0,0 +1,437 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use easy_imgui_window::winit::event_loop::EventLoopProxy;
+use librespot::playback::{
+    audio_backend::{self, Sink},
+    config::AudioFormat,
+    convert::Converter,
+    decoder::AudioPacket,
+    mixer::{mappings::MappedCtrl, MixerConfig},
+    SAMPLE_RATE,
+};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions},
+    errors::Error as SymphoniaError,
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+    units::Time,
+};
+use tracing::{error, info, warn};
+
+use crate::event::AppEvent;
+
+use super::status::SpotifyPlaybackStatus;
+
+enum LocalPlayerCommand {
+    Load { path: PathBuf, start_playing: bool, position_ms: u32 },
+    Play,
+    Pause,
+    Seek(u32),
+    Stop,
+}
+
+// One file being decoded, turned into the interleaved stereo
+// at librespot's sample rate that its sinks expect.
+struct LocalDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    sample_rate: u32,
+
+    // Frames decoded so far, for working out where we are.
+    frames: u64,
+
+    // Linear resampling carries over between packets, `resample_at` counts
+    // in source frames from `resample_last`, the last frame of the packet before.
+    resample_at: f64,
+    resample_last: [f64; 2],
+}
+
+impl LocalDecoder {
+    fn open(path: &Path) -> Result<Self, SymphoniaError> {
+        let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+
+        let mut hint = Hint::new();
+
+        if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            source,
+            &FormatOptions { enable_gapless: true, ..Default::default() },
+            &MetadataOptions::default(),
+        )?;
+
+        let track = probed.format
+            .default_track()
+            .ok_or(SymphoniaError::Unsupported("no audio track"))?;
+
+        let decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+        Ok(LocalDecoder {
+            track_id: track.id,
+            sample_rate: track.codec_params.sample_rate.unwrap_or(SAMPLE_RATE),
+            format: probed.format,
+            decoder,
+
+            frames: 0,
+
+            resample_at: 1.0,
+            resample_last: [0.0; 2],
+        })
+    }
+
+    fn position_ms(&self) -> u32 {
+        (self.frames * 1000 / self.sample_rate as u64) as u32
+    }
+
+    fn seek(&mut self, position_ms: u32) -> Result<(), SymphoniaError> {
+        let seeked = self.format.seek(SeekMode::Coarse, SeekTo::Time {
+            time: Time::from(position_ms as f64 / 1000.0),
+            track_id: Some(self.track_id),
+        })?;
+
+        self.decoder.reset();
+
+        // Timestamps count frames for every format we build symphonia with.
+        self.frames = seeked.actual_ts;
+        self.resample_at = 1.0;
+        self.resample_last = [0.0; 2];
+
+        Ok(())
+    }
+
+    /// The next lot of samples, None once the file's finished.
+    fn next_samples(&mut self) -> Option<Vec<f64>> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => return None,
+                Err(err) => {
+                    warn!("Failed to read local file: {}", err);
+
+                    return None;
+                }
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A corrupt packet is worth skipping over rather than giving up on the file.
+                Err(SymphoniaError::DecodeError(err)) => {
+                    warn!("Skipping undecodable packet in local file: {}", err);
+
+                    continue;
+                },
+                Err(err) => {
+                    warn!("Failed to decode local file: {}", err);
+
+                    return None;
+                }
+            };
+
+            let channels = decoded.spec().channels.count().max(1);
+
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+
+            // Mono goes to both speakers, anything past stereo is dropped.
+            let frames = buffer
+                .samples()
+                .chunks_exact(channels)
+                .map(|frame| match frame {
+                    [mono] => [*mono as f64; 2],
+                    [left, right, ..] => [*left as f64, *right as f64],
+                    [] => [0.0; 2],
+                })
+                .collect::<Vec<[f64; 2]>>();
+
+            if frames.is_empty() {
+                continue;
+            }
+
+            self.frames += frames.len() as u64;
+
+            return Some(self.resample(&frames));
+        }
+    }
+
+    // Linear interpolation, nothing fancy, but most files are 44.1kHz already.
+    fn resample(&mut self, frames: &[[f64; 2]]) -> Vec<f64> {
+        if self.sample_rate == SAMPLE_RATE {
+            return frames.iter().flatten().copied().collect();
+        }
+
+        let step = self.sample_rate as f64 / SAMPLE_RATE as f64;
+        let frame_at = |index: usize| if index == 0 { self.resample_last } else { frames[index - 1] };
+
+        let mut samples = Vec::with_capacity(((frames.len() as f64 / step) as usize + 1) * 2);
+
+        while self.resample_at < frames.len() as f64 {
+            let index = self.resample_at as usize;
+            let fraction = self.resample_at - index as f64;
+
+            let (a, b) = (frame_at(index), frame_at(index + 1));
+
+            samples.push(a[0] + (b[0] - a[0]) * fraction);
+            samples.push(a[1] + (b[1] - a[1]) * fraction);
+
+            self.resample_at += step;
+        }
+
+        self.resample_at -= frames.len() as f64;
+        self.resample_last = frames[frames.len() - 1];
+
+        samples
+    }
+}
+
+/// Plays files from Your Files through the same librespot audio backend
+/// and volume curve as Spotify tracks. Decoding runs on its own thread,
+/// the sink's writes block while its buffer is full, which keeps us in time.
+#[derive(Clone)]
+pub struct LocalPlayer {
+    commands: Sender<LocalPlayerCommand>,
+    volume: Arc<AtomicU16>,
+}
+
+impl LocalPlayer {
+    pub fn new(
+        event_loop: Option<Arc<EventLoopProxy<AppEvent>>>,
+        status: Arc<Mutex<SpotifyPlaybackStatus>>,
+        backend: Option<String>,
+        device: Option<String>,
+    ) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let volume = Arc::new(AtomicU16::new(u16::MAX));
+
+        let thread_volume = Arc::clone(&volume);
+
+        thread::spawn(move || {
+            LocalPlayer::run(receiver, event_loop, status, thread_volume, backend, device);
+        });
+
+        LocalPlayer { commands, volume }
+    }
+
+    pub fn load(&self, path: PathBuf, start_playing: bool, position_ms: u32) {
+        self.commands.send(LocalPlayerCommand::Load { path, start_playing, position_ms }).ok();
+    }
+
+    pub fn play(&self) {
+        self.commands.send(LocalPlayerCommand::Play).ok();
+    }
+
+    pub fn pause(&self) {
+        self.commands.send(LocalPlayerCommand::Pause).ok();
+    }
+
+    pub fn seek(&self, position_ms: u32) {
+        self.commands.send(LocalPlayerCommand::Seek(position_ms)).ok();
+    }
+
+    pub fn stop(&self) {
+        self.commands.send(LocalPlayerCommand::Stop).ok();
+    }
+
+    pub fn volume(&self) -> u16 {
+        self.volume.load(Ordering::Relaxed)
+    }
+
+    pub fn set_volume(&self, volume: u16) {
+        self.volume.store(volume, Ordering::Relaxed);
+    }
+
+    fn run(
+        receiver: Receiver<LocalPlayerCommand>,
+        event_loop: Option<Arc<EventLoopProxy<AppEvent>>>,
+        status: Arc<Mutex<SpotifyPlaybackStatus>>,
+        volume: Arc<AtomicU16>,
+        backend: Option<String>,
+        device: Option<String>,
+    ) {
+        let volume_ctrl = MixerConfig::default().volume_ctrl;
+        let mut converter = Converter::new(None);
+
+        let mut sink: Option<Box<dyn Sink>> = None;
+        let mut decoder: Option<LocalDecoder> = None;
+        let mut playing = false;
+
+        // The sink is only open while we're playing, so librespot
+        // can have the device the rest of the time.
+        let set_playing = |sink: &mut Option<Box<dyn Sink>>, play: bool| -> bool {
+            if play {
+                if sink.is_none() {
+                    let Some(sink_builder) = audio_backend::find(backend.clone()) else {
+                        error!("No audio backend {:?} to play local files with.", backend);
+
+                        return false;
+                    };
+
+                    *sink = Some(sink_builder(device.clone(), AudioFormat::default()));
+                }
+
+                if let Some(Err(err)) = sink.as_mut().map(|s| s.start()) {
+                    error!("Failed to start audio sink for local files: {}", err);
+
+                    return false;
+                }
+
+                true
+            } else {
+                if let Some(Err(err)) = sink.as_mut().map(|s| s.stop()) {
+                    warn!("Failed to stop audio sink for local files: {}", err);
+                }
+
+                false
+            }
+        };
+
+        loop {
+            // Wait for something to do when nothing's playing,
+            // otherwise just check in between packets.
+            let command = if playing {
+                match receiver.try_recv() {
+                    Ok(command) => Some(command),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            } else {
+                match receiver.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                }
+            };
+
+            match command {
+                Some(LocalPlayerCommand::Load { path, start_playing, position_ms }) => {
+                    info!("Loading local file {}.", path.display());
+
+                    let opened = LocalDecoder::open(&path).and_then(|mut d| {
+                        if position_ms > 0 {
+                            d.seek(position_ms)?;
+                        }
+
+                        Ok(d)
+                    });
+
+                    let mut status = status.lock().unwrap();
+
+                    status.loading = false;
+
+                    match opened {
+                        Ok(opened) => {
+                            status.set_position(opened.position_ms());
+
+                            decoder = Some(opened);
+                            playing = set_playing(&mut sink, start_playing);
+                        },
+                        Err(err) => {
+                            warn!("Local file {} is unavailable for playback: {}", path.display(), err);
+
+                            decoder = None;
+                            playing = set_playing(&mut sink, false);
+                        }
+                    }
+
+                    status.playing = playing;
+                },
+                Some(LocalPlayerCommand::Play) if decoder.is_some() => {
+                    playing = set_playing(&mut sink, true);
+
+                    let mut status = status.lock().unwrap();
+
+                    status.playing = playing;
+
+                    if let Some(decoder) = &decoder {
+                        status.set_position(decoder.position_ms());
+                    }
+                },
+                Some(LocalPlayerCommand::Pause) => {
+                    playing = set_playing(&mut sink, false);
+
+                    let mut status = status.lock().unwrap();
+
+                    status.playing = false;
+
+                    if let Some(decoder) = &decoder {
+                        status.set_position(decoder.position_ms());
+                    }
+                },
+                Some(LocalPlayerCommand::Seek(position_ms)) => {
+                    if let Some(d) = &mut decoder {
+                        if let Err(err) = d.seek(position_ms) {
+                            warn!("Failed to seek local file: {}", err);
+                        }
+
+                        status.lock().unwrap().set_position(d.position_ms());
+                    }
+                },
+                Some(LocalPlayerCommand::Stop) => {
+                    decoder = None;
+                    playing = set_playing(&mut sink, false);
+                },
+                Some(LocalPlayerCommand::Play) | None => {}
+            }
+
+            if !playing {
+                continue;
+            }
+
+            let Some(d) = &mut decoder else {
+                playing = set_playing(&mut sink, false);
+
+                continue;
+            };
+
+            match d.next_samples() {
+                Some(mut samples) => {
+                    let attenuation = volume_ctrl.to_mapped(volume.load(Ordering::Relaxed));
+
+                    if attenuation < 1.0 {
+                        samples.iter_mut().for_each(|s| *s *= attenuation);
+                    }
+
+                    if let Some(Err(err)) = sink.as_mut().map(|s| s.write(AudioPacket::Samples(samples), &mut converter)) {
+                        error!("Failed to write local file to audio sink: {}", err);
+
+                        playing = set_playing(&mut sink, false);
+                        status.lock().unwrap().playing = false;
+                    }
+                },
+                None => {
+                    decoder = None;
+                    playing = set_playing(&mut sink, false);
+
+                    {
+                        let mut status = status.lock().unwrap();
+
+                        status.playing = false;
+                        status.set_position(0);
+                    }
+
+                    if let Some(event_loop) = &event_loop {
+                        event_loop.send_event(AppEvent::EndOfTrack).ok();
+                    }
+                },
+            }
+        }
+    }
+}
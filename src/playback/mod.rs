@@ -0,0 +1,843 @@
+pub mod connect;
+pub mod error;
+pub mod local;
+pub mod status;
+#[cfg(test)]
+pub mod testing;
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use connect::{SpotifyConnectEventSender, SpotifyConnectReceiver, SpotifyConnectTransport};
+use easy_imgui_window::winit::event_loop::EventLoopProxy;
+use error::SpotifyPlaybackError;
+use local::LocalPlayer;
+use futures::StreamExt;
+use librespot::{
+    connect::{config::ConnectConfig, spirc::Spirc},
+    core::{authentication::Credentials, config::DeviceType, Session, SessionConfig, SpotifyId},
+    discovery::Discovery,
+    playback::{
+        audio_backend,
+        config::{AudioFormat, PlayerConfig},
+        mixer::{softmixer::SoftMixer, Mixer, MixerConfig},
+        player::{Player, PlayerEvent},
+    },
+};
+use rspotify_model::{FullTrack, Id as _};
+use status::SpotifyPlaybackStatus;
+use tracing::{error, info, warn};
+
+use crate::{
+    api::constants::{SPOTIFY_CLIENT_ID, SPOTIFY_DEVICE_NAME},
+    event::AppEvent,
+    local::track_path,
+};
+
+#[derive(Debug)]
+pub enum SpotifyPlaybackState {
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
+struct SpotifyPlaybackSession {
+    session: Session,
+    player: Arc<Player>,
+    mixer: Arc<SoftMixer>,
+}
+
+#[derive(Clone)]
+pub struct SpotifyPlayback {
+    // Optional so the playback session can run headless,
+    // without a window to send events to.
+    pub event_loop: Option<Arc<EventLoopProxy<AppEvent>>>,
+
+    pub state: Arc<Mutex<Result<SpotifyPlaybackState, SpotifyPlaybackError>>>,
+    pub status: Arc<Mutex<SpotifyPlaybackStatus>>,
+
+    // Name of the librespot audio backend, "pipe" or "subprocess"
+    // let us run without a sound card. None picks the default backend.
+    pub backend: Option<String>,
+    pub device: Option<String>,
+
+    // What we announce ourselves as over Spotify Connect, see connect::load_device_id.
+    pub device_id: String,
+
+    // Lets phones and other clients cast to us over Spotify Connect.
+    pub connect: SpotifyConnectReceiver,
+
+    // Plays Your Files, which librespot has no way to load.
+    local: LocalPlayer,
+
+    session: Arc<Mutex<Option<SpotifyPlaybackSession>>>,
+    // Bumped by every disconnect, so a connect that was still
+    // creating its session knows to throw it away.
+    generation: Arc<AtomicU64>,
+}
+
+impl SpotifyPlayback {
+    pub fn new(event_loop: Option<Arc<EventLoopProxy<AppEvent>>>, device_id: String, backend: Option<String>, device: Option<String>) -> Self {
+        let connect_send = event_loop.clone().map(|event_loop| {
+            Arc::new(move |event| {
+                event_loop.send_event(event).ok();
+            }) as SpotifyConnectEventSender
+        });
+
+        let status = Arc::new(Mutex::new(SpotifyPlaybackStatus::default()));
+
+        let local = LocalPlayer::new(event_loop.clone(), Arc::clone(&status), backend.clone(), device.clone());
+
+        SpotifyPlayback {
+            event_loop,
+
+            state: Arc::new(Mutex::new(Ok(SpotifyPlaybackState::Disconnected))),
+            status,
+
+            backend,
+            device,
+
+            device_id,
+
+            connect: SpotifyConnectReceiver::new(connect_send),
+            local,
+
+            session: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), Ok(SpotifyPlaybackState::Connected))
+    }
+
+    pub fn is_connecting(&self) -> bool {
+        matches!(*self.state.lock().unwrap(), Ok(SpotifyPlaybackState::Connecting))
+    }
+
+    pub fn get_state_error(&self) -> Option<SpotifyPlaybackError> {
+        self.state.lock().unwrap().as_ref().err().cloned()
+    }
+
+    pub fn status(&self) -> SpotifyPlaybackStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    // Whether the current track is one of Your Files rather than Spotify's.
+    fn is_playing_file(&self) -> bool {
+        self.status.lock().unwrap()
+            .track
+            .as_ref()
+            .map(|t| t.is_local)
+            .unwrap_or(false)
+    }
+
+    fn player(&self) -> Result<Arc<Player>, SpotifyPlaybackError> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| Arc::clone(&s.player))
+            .ok_or(SpotifyPlaybackError::NotConnected)
+    }
+
+    // Attaching Spirc is left to the caller, once it knows the session is still wanted.
+    async fn create_session(&self, credentials: Credentials) -> Result<(SpotifyPlaybackSession, Box<dyn SpotifyConnectTransport>), SpotifyPlaybackError> {
+        let sink_builder = audio_backend::find(self.backend.clone())
+            .ok_or(SpotifyPlaybackError::NoAudioBackend(self.backend.clone()))?;
+
+        let session_config = SessionConfig {
+            client_id: SPOTIFY_CLIENT_ID.to_string(),
+            device_id: self.device_id.clone(),
+            ..Default::default()
+        };
+
+        let session = Session::new(session_config, None);
+
+        let mixer = Arc::new(SoftMixer::open(MixerConfig::default()));
+        let device = self.device.clone();
+
+        let player = Player::new(
+            PlayerConfig::default(),
+            session.clone(),
+            mixer.get_soft_volume(),
+            move || sink_builder(device, AudioFormat::default()),
+        );
+
+        let connect_config = ConnectConfig {
+            name: SPOTIFY_DEVICE_NAME.to_string(),
+            device_type: DeviceType::Computer,
+            ..Default::default()
+        };
+
+        // Spirc connects the session for us, and registers
+        // us as a Connect device on the account.
+        let (spirc, spirc_task) = Spirc::new(
+            connect_config,
+            session.clone(),
+            credentials,
+            Arc::clone(&player),
+            Arc::clone(&mixer) as Arc<dyn Mixer>,
+        )
+            .await
+            .map_err(|e| SpotifyPlaybackError::SessionError(Arc::new(e)))?;
+
+        tokio::task::spawn(spirc_task);
+
+        Ok((
+            SpotifyPlaybackSession {
+                session,
+                player,
+                mixer,
+            },
+            Box::new(spirc),
+        ))
+    }
+
+    pub async fn connect(&self, access_token: String) {
+        self.connect_with_credentials(Credentials::with_access_token(access_token)).await;
+    }
+
+    pub async fn connect_with_credentials(&self, credentials: Credentials) {
+        // Checked and set under one lock, so two callers can't both get past it.
+        let generation = {
+            let mut state = self.state.lock().unwrap();
+
+            if matches!(*state, Ok(SpotifyPlaybackState::Connected) | Ok(SpotifyPlaybackState::Connecting)) {
+                return;
+            }
+
+            *state = Ok(SpotifyPlaybackState::Connecting);
+
+            self.generation.load(Ordering::SeqCst)
+        };
+
+        let result = self.create_session(credentials).await;
+
+        self.finish_connecting(generation, result);
+    }
+
+    // Keeps the new session, unless we were disconnected while creating it.
+    fn finish_connecting(
+        &self,
+        generation: u64,
+        result: Result<(SpotifyPlaybackSession, Box<dyn SpotifyConnectTransport>), SpotifyPlaybackError>
+    ) {
+        // Held throughout, disconnect bumps the generation under the same lock.
+        let mut state = self.state.lock().unwrap();
+
+        if self.generation.load(Ordering::SeqCst) != generation {
+            info!("Disconnected while connecting, dropping the new playback session.");
+
+            if let Ok((playback_session, spirc)) = result {
+                spirc.shutdown().ok();
+                playback_session.player.stop();
+                playback_session.session.shutdown();
+            }
+
+            return;
+        }
+
+        match result {
+            Ok((playback_session, spirc)) => {
+                self.listen(&playback_session.player);
+
+                info!(
+                    "Connected playback session as {}.",
+                    playback_session.session.username()
+                );
+
+                self.connect.attach(spirc);
+
+                *self.session.lock().unwrap() = Some(playback_session);
+                *state = Ok(SpotifyPlaybackState::Connected);
+            },
+            Err(err) => {
+                error!("Failed to connect playback session: {:#?}", err);
+
+                *state = Err(err);
+            }
+        }
+    }
+
+    // Follows librespot's player events for as long as the player lives.
+    fn listen(&self, player: &Player) {
+        let mut channel = player.get_player_event_channel();
+        let status_arc = Arc::clone(&self.status);
+        let event_loop = self.event_loop.clone();
+        let connect = self.connect.clone();
+
+        tokio::task::spawn(async move {
+            while let Some(event) = channel.recv().await {
+                let (local_track_id, playing_file) = {
+                    let status = status_arc.lock().unwrap();
+
+                    (
+                        status.track.as_ref().and_then(|t| t.id.as_ref()).map(|id| id.id().to_string()),
+                        status.track.as_ref().map(|t| t.is_local).unwrap_or(false),
+                    )
+                };
+
+                // Stragglers from before a local file took over, like the
+                // Stopped from stopping librespot, would clobber its status.
+                if playing_file {
+                    continue;
+                }
+
+                connect.handle_player_event(local_track_id.as_deref(), &event);
+
+                // Whoever is controlling us over Connect decides
+                // what plays next, not our queue.
+                if let (PlayerEvent::EndOfTrack { .. }, Some(event_loop)) = (&event, &event_loop) {
+                    if !connect.is_active() {
+                        event_loop.send_event(AppEvent::EndOfTrack).ok();
+                    }
+                }
+
+                SpotifyPlayback::handle_player_event(&status_arc, event);
+            }
+        });
+    }
+
+    /// Announces us on the local network so clients that aren't signed in
+    /// to the same account can cast to us. Each time a client connects we
+    /// restart the session with the credentials it hands us.
+    pub async fn run_discovery(&self) {
+        let discovery = Discovery::builder(self.device_id.clone(), SPOTIFY_CLIENT_ID.to_string())
+            .name(SPOTIFY_DEVICE_NAME)
+            .device_type(DeviceType::Computer)
+            .launch();
+
+        let mut discovery = match discovery {
+            Ok(discovery) => discovery,
+            Err(err) => {
+                error!("Failed to start Spotify Connect discovery: {:#?}", err);
+
+                return;
+            }
+        };
+
+        info!("Advertising {} as a Spotify Connect device.", SPOTIFY_DEVICE_NAME);
+
+        while let Some(credentials) = discovery.next().await {
+            info!("Received credentials from a Spotify Connect client, reconnecting...");
+
+            self.disconnect();
+            self.connect_with_credentials(credentials).await;
+        }
+    }
+
+    pub fn disconnect(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        self.generation.fetch_add(1, Ordering::SeqCst);
+
+        self.connect.detach();
+        self.local.stop();
+
+        if let Some(playback_session) = self.session.lock().unwrap().take() {
+            playback_session.player.stop();
+            playback_session.session.shutdown();
+        }
+
+        *self.status.lock().unwrap() = SpotifyPlaybackStatus::default();
+        *state = Ok(SpotifyPlaybackState::Disconnected);
+    }
+
+    fn handle_player_event(status_arc: &Arc<Mutex<SpotifyPlaybackStatus>>, event: PlayerEvent) {
+        let mut status = status_arc.lock().unwrap();
+
+        match event {
+            PlayerEvent::Loading { position_ms, .. } => {
+                status.loading = true;
+                status.set_position(position_ms);
+            },
+            PlayerEvent::Playing { position_ms, .. } => {
+                status.loading = false;
+                status.playing = true;
+                status.set_position(position_ms);
+            },
+            PlayerEvent::Paused { position_ms, .. } => {
+                status.loading = false;
+                status.playing = false;
+                status.set_position(position_ms);
+            },
+            PlayerEvent::Seeked { position_ms, .. }
+            | PlayerEvent::PositionCorrection { position_ms, .. } => {
+                status.set_position(position_ms);
+            },
+            PlayerEvent::Stopped { .. } | PlayerEvent::EndOfTrack { .. } => {
+                status.loading = false;
+                status.playing = false;
+                status.set_position(0);
+            },
+            PlayerEvent::Unavailable { track_id, .. } => {
+                warn!("Track {:?} is unavailable for playback.", track_id);
+
+                status.loading = false;
+                status.playing = false;
+            },
+            _ => {}
+        }
+    }
+
+    pub fn load(&self, track: FullTrack, start_playing: bool, position_ms: u32) -> Result<(), SpotifyPlaybackError> {
+        if let Some(path) = track_path(&track) {
+            return self.load_file(track, path, start_playing, position_ms);
+        }
+
+        let player = self.player()?;
+
+        // Playing something ourselves takes control back from any remote.
+        self.connect.deactivate();
+        self.local.stop();
+
+        let uri = track.id
+            .as_ref()
+            .map(|id| id.uri())
+            .ok_or(SpotifyPlaybackError::InvalidTrack(track.name.clone()))?;
+
+        let spotify_id = SpotifyId::from_uri(&uri)
+            .map_err(|_| SpotifyPlaybackError::InvalidTrack(uri.clone()))?;
+
+        info!("Loading track {} ({}).", track.name, uri);
+
+        {
+            let mut status = self.status.lock().unwrap();
+
+            status.track = Some(track);
+            status.loading = true;
+            status.playing = false;
+            status.set_position(position_ms);
+        }
+
+        player.load(spotify_id, start_playing, position_ms);
+
+        Ok(())
+    }
+
+    // Local files don't need a session, they play whether we're connected or not.
+    fn load_file(&self, track: FullTrack, path: PathBuf, start_playing: bool, position_ms: u32) -> Result<(), SpotifyPlaybackError> {
+        self.connect.deactivate();
+
+        if let Ok(player) = self.player() {
+            player.stop();
+        }
+
+        info!("Loading local file {} ({}).", track.name, path.display());
+
+        {
+            let mut status = self.status.lock().unwrap();
+
+            status.track = Some(track);
+            status.loading = true;
+            status.playing = false;
+            status.set_position(position_ms);
+        }
+
+        self.local.load(path, start_playing, position_ms);
+
+        Ok(())
+    }
+
+    /// Sets the track shown in the player bar without loading it,
+    /// for tracks a remote device started through Connect.
+    pub fn set_remote_track(&self, track: FullTrack) {
+        self.status.lock().unwrap().track = Some(track);
+    }
+
+    pub fn play(&self) -> Result<(), SpotifyPlaybackError> {
+        if self.is_playing_file() {
+            self.local.play();
+
+            return Ok(());
+        }
+
+        if let Some(result) = self.connect.command(|t| t.play()) {
+            return result;
+        }
+
+        self.player()?.play();
+
+        Ok(())
+    }
+
+    pub fn pause(&self) -> Result<(), SpotifyPlaybackError> {
+        if self.is_playing_file() {
+            self.local.pause();
+
+            return Ok(());
+        }
+
+        if let Some(result) = self.connect.command(|t| t.pause()) {
+            return result;
+        }
+
+        self.player()?.pause();
+
+        Ok(())
+    }
+
+    pub fn play_pause(&self) -> Result<(), SpotifyPlaybackError> {
+        if self.status.lock().unwrap().playing {
+            self.pause()
+        } else {
+            self.play()
+        }
+    }
+
+    pub fn seek(&self, position_ms: u32) -> Result<(), SpotifyPlaybackError> {
+        if self.is_playing_file() {
+            self.local.seek(position_ms);
+        } else {
+            match self.connect.command(|t| t.seek(position_ms)) {
+                Some(result) => result?,
+                None => self.player()?.seek(position_ms),
+            }
+        }
+
+        // Update our position straight away so the progress bar
+        // doesn't jump back while waiting for the Seeked event.
+        self.status.lock().unwrap().set_position(position_ms);
+
+        Ok(())
+    }
+
+    // Volume is a fraction between 0.0 and 1.0.
+    pub fn volume(&self) -> f64 {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.mixer.volume())
+            .unwrap_or(self.local.volume()) as f64 / u16::MAX as f64
+    }
+
+    pub fn set_volume(&self, volume: f64) -> Result<(), SpotifyPlaybackError> {
+        let volume = (volume.clamp(0.0, 1.0) * u16::MAX as f64) as u16;
+
+        // Kept in step with librespot's, so switching between
+        // a local file and Spotify doesn't change the volume.
+        self.local.set_volume(volume);
+
+        // Go through Connect so the remote's volume slider follows along.
+        if let Some(result) = self.connect.command(|t| t.set_volume(volume)) {
+            return result;
+        }
+
+        let session = self.session.lock().unwrap();
+
+        match session.as_ref() {
+            Some(playback_session) => playback_session.mixer.set_volume(volume),
+            None if self.is_playing_file() => {},
+            None => return Err(SpotifyPlaybackError::NotConnected),
+        }
+
+        Ok(())
+    }
+
+    pub fn stop(&self) -> Result<(), SpotifyPlaybackError> {
+        if self.is_playing_file() {
+            self.local.stop();
+
+            let mut status = self.status.lock().unwrap();
+
+            status.playing = false;
+            status.set_position(0);
+
+            return Ok(());
+        }
+
+        self.player()?.stop();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::Path,
+        thread,
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use super::*;
+    use crate::local::{scanner, testing::write_wav};
+    use testing::FakeTransport;
+
+    // Plays into a file through librespot's pipe backend, no sound card needed.
+    fn playback(dir: &Path) -> SpotifyPlayback {
+        SpotifyPlayback::new(
+            None,
+            "0".repeat(40),
+            Some("pipe".to_string()),
+            Some(dir.join("output.raw").to_string_lossy().to_string())
+        )
+    }
+
+    fn local_track(dir: &Path, seconds: u32) -> FullTrack {
+        let path = dir.join("track.wav");
+
+        write_wav(&path, seconds);
+
+        scanner::read_track(&path, SystemTime::now())
+            .unwrap()
+            .to_full_track()
+            .unwrap()
+    }
+
+    fn spotify_track() -> FullTrack {
+        crate::api::testing::track(Some("11dFghVXANMlKmJXsNCbNl"), "Track")
+    }
+
+    // librespot's own player on the pipe backend, with a session that's
+    // never connected. Spotify's tracks can't actually load on it, but
+    // everything we ask of the player still goes through librespot.
+    fn librespot_session(dir: &Path) -> SpotifyPlaybackSession {
+        let sink_builder = audio_backend::find(Some("pipe".to_string())).unwrap();
+        let device = Some(dir.join("librespot.raw").to_string_lossy().to_string());
+
+        let session = Session::new(SessionConfig::default(), None);
+        let mixer = Arc::new(SoftMixer::open(MixerConfig::default()));
+
+        let player = Player::new(
+            PlayerConfig::default(),
+            session.clone(),
+            mixer.get_soft_volume(),
+            move || sink_builder(device, AudioFormat::default()),
+        );
+
+        SpotifyPlaybackSession {
+            session,
+            player,
+            mixer,
+        }
+    }
+
+    // Left without anything listening to the player, so the status
+    // only changes when we change it.
+    fn connected(dir: &Path) -> SpotifyPlayback {
+        let playback = playback(dir);
+
+        *playback.session.lock().unwrap() = Some(librespot_session(dir));
+        *playback.state.lock().unwrap() = Ok(SpotifyPlaybackState::Connected);
+
+        playback
+    }
+
+    fn output_len(dir: &Path) -> u64 {
+        fs::metadata(dir.join("output.raw")).map(|m| m.len()).unwrap_or(0)
+    }
+
+    fn wait_for(what: &str, condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while !condition() {
+            assert!(Instant::now() < deadline, "Timed out waiting for {}", what);
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn loads_local_files_paused() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+        let track = local_track(dir.path(), 10);
+
+        playback.load(track.clone(), false, 0).unwrap();
+
+        wait_for("the file to load", || !playback.status().loading);
+
+        let status = playback.status();
+
+        assert_eq!(status.track.map(|t| t.name), Some(track.name));
+        assert!(!status.playing);
+        assert_eq!(status.position_ms, 0);
+        assert_eq!(status.duration_ms(), 10_000);
+
+        // The sink is only opened to play.
+        assert!(!dir.path().join("output.raw").exists());
+    }
+
+    #[test]
+    fn loads_and_seeks_to_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+
+        playback.load(local_track(dir.path(), 10), false, 2000).unwrap();
+
+        wait_for("the file to load", || !playback.status().loading);
+
+        assert!(playback.status().position_ms.abs_diff(2000) < 100);
+
+        playback.seek(5000).unwrap();
+
+        assert_eq!(playback.status().position_ms, 5000);
+
+        // Settles on wherever the decoder actually landed.
+        thread::sleep(Duration::from_millis(100));
+
+        assert!(playback.status().position_ms.abs_diff(5000) < 100);
+    }
+
+    #[test]
+    fn plays_and_pauses() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+
+        playback.load(local_track(dir.path(), 600), false, 0).unwrap();
+
+        wait_for("the file to load", || !playback.status().loading);
+
+        playback.play().unwrap();
+
+        wait_for("audio to be written", || output_len(dir.path()) > 0);
+
+        playback.pause().unwrap();
+
+        wait_for("playback to pause", || !playback.status().playing);
+
+        let written = output_len(dir.path());
+
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(output_len(dir.path()), written);
+        assert!(playback.status().position_ms > 0);
+    }
+
+    #[test]
+    fn plays_to_the_end() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+
+        playback.load(local_track(dir.path(), 1), true, 0).unwrap();
+
+        wait_for("audio to be written", || output_len(dir.path()) > 0);
+        wait_for("the file to finish", || !playback.status().playing);
+
+        assert_eq!(playback.status().position_ms, 0);
+
+        // 1s at 44.1kHz, 16-bit stereo.
+        assert!(output_len(dir.path()).abs_diff(44100 * 4) < 44100 * 4 / 10);
+    }
+
+    #[test]
+    fn spotify_tracks_need_a_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+
+        assert!(matches!(playback.load(spotify_track(), true, 0), Err(SpotifyPlaybackError::NotConnected)));
+        assert!(matches!(playback.play(), Err(SpotifyPlaybackError::NotConnected)));
+    }
+
+    #[tokio::test]
+    async fn ignores_connecting_twice() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+
+        *playback.state.lock().unwrap() = Ok(SpotifyPlaybackState::Connecting);
+
+        playback.connect("access-token".to_string()).await;
+
+        assert!(playback.is_connecting());
+        assert!(playback.session.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn loads_spotify_tracks_into_librespot() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = connected(dir.path());
+
+        playback.load(local_track(dir.path(), 600), true, 0).unwrap();
+
+        wait_for("audio to be written", || output_len(dir.path()) > 0);
+
+        // Takes over from the local file.
+        playback.load(spotify_track(), false, 1500).unwrap();
+
+        let written = output_len(dir.path());
+
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(output_len(dir.path()), written);
+
+        let status = playback.status();
+
+        assert_eq!(status.track.map(|t| t.name), Some("Track".to_string()));
+        assert!(status.loading);
+        assert!(!status.playing);
+        assert_eq!(status.position_ms, 1500);
+        assert!(!playback.is_playing_file());
+    }
+
+    #[tokio::test]
+    async fn controls_librespot_once_connected() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = connected(dir.path());
+
+        playback.load(spotify_track(), false, 0).unwrap();
+
+        assert!(playback.play().is_ok());
+        assert!(playback.pause().is_ok());
+
+        playback.seek(3000).unwrap();
+
+        assert_eq!(playback.status().position_ms, 3000);
+
+        playback.set_volume(0.25).unwrap();
+
+        assert!((playback.volume() - 0.25).abs() < 0.001);
+
+        playback.stop().unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_a_session_finished_while_connecting() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+        let transport = FakeTransport::default();
+
+        *playback.state.lock().unwrap() = Ok(SpotifyPlaybackState::Connecting);
+
+        playback.finish_connecting(0, Ok((librespot_session(dir.path()), Box::new(transport.clone()))));
+
+        assert!(playback.is_connected());
+        assert!(playback.session.lock().unwrap().is_some());
+        assert!(transport.calls().is_empty());
+
+        playback.disconnect();
+
+        assert_eq!(transport.calls(), vec!["shutdown"]);
+    }
+
+    #[tokio::test]
+    async fn drops_a_session_finished_after_disconnecting() {
+        let dir = tempfile::tempdir().unwrap();
+        let playback = playback(dir.path());
+        let transport = FakeTransport::default();
+
+        *playback.state.lock().unwrap() = Ok(SpotifyPlaybackState::Connecting);
+
+        // The user signed out while Spirc was still connecting.
+        playback.disconnect();
+
+        playback.finish_connecting(0, Ok((librespot_session(dir.path()), Box::new(transport.clone()))));
+
+        assert!(matches!(*playback.state.lock().unwrap(), Ok(SpotifyPlaybackState::Disconnected)));
+        assert!(playback.session.lock().unwrap().is_none());
+        assert_eq!(transport.calls(), vec!["shutdown"]);
+
+        // Nor does a failure from then overwrite it.
+        playback.finish_connecting(0, Err(SpotifyPlaybackError::NotConnected));
+
+        assert!(playback.get_state_error().is_none());
+    }
+}
@@ -0,0 +1,72 @@
+use std::time::Instant;
+
+use rspotify_model::{FullTrack, Image};
+
+#[derive(Debug, Clone, Default)]
+pub struct SpotifyPlaybackStatus {
+    pub track: Option<FullTrack>,
+
+    pub playing: bool,
+    pub loading: bool,
+
+    pub position_ms: u32,
+    pub position_updated_at: Option<Instant>,
+}
+
+impl SpotifyPlaybackStatus {
+    pub fn set_position(&mut self, position_ms: u32) {
+        self.position_ms = position_ms;
+        self.position_updated_at = Some(Instant::now());
+    }
+
+    pub fn duration_ms(&self) -> u32 {
+        self.track
+            .as_ref()
+            .map(|t| t.duration.num_milliseconds().max(0) as u32)
+            .unwrap_or(0)
+    }
+
+    // librespot only tells us the position when the player changes state,
+    // so interpolate between events while we're playing.
+    pub fn current_position_ms(&self) -> u32 {
+        let elapsed = match (self.playing, self.position_updated_at) {
+            (true, Some(updated_at)) => updated_at.elapsed().as_millis() as u32,
+            _ => 0,
+        };
+
+        let position = self.position_ms.saturating_add(elapsed);
+
+        match self.duration_ms() {
+            0 => position,
+            duration => position.min(duration),
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        match self.duration_ms() {
+            0 => 0.0,
+            duration => self.current_position_ms() as f32 / duration as f32,
+        }
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.track.as_ref().map(|t| t.name.clone())
+    }
+
+    pub fn artists(&self) -> Option<String> {
+        self.track.as_ref().map(|t| {
+            t.artists
+                .iter()
+                .map(|a| a.name.clone())
+                .collect::<Vec<String>>()
+                .join(", ")
+        })
+    }
+
+    pub fn album_images(&self) -> Vec<Image> {
+        self.track
+            .as_ref()
+            .map(|t| t.album.images.clone())
+            .unwrap_or_default()
+    }
+}
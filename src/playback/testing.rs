@@ -0,0 +1,36 @@
+use std::sync::{Arc, Mutex};
+
+use crate::state::queue::RepeatMode;
+
+use super::{connect::SpotifyConnectTransport, error::SpotifyPlaybackError};
+
+/// Stands in for Spirc, recording each command it's sent.
+#[derive(Default, Clone)]
+pub struct FakeTransport {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeTransport {
+    fn record(&self, call: String) -> Result<(), SpotifyPlaybackError> {
+        self.calls.lock().unwrap().push(call);
+
+        Ok(())
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl SpotifyConnectTransport for FakeTransport {
+    fn play(&self) -> Result<(), SpotifyPlaybackError> { self.record("play".to_string()) }
+    fn pause(&self) -> Result<(), SpotifyPlaybackError> { self.record("pause".to_string()) }
+    fn play_pause(&self) -> Result<(), SpotifyPlaybackError> { self.record("play_pause".to_string()) }
+    fn next(&self) -> Result<(), SpotifyPlaybackError> { self.record("next".to_string()) }
+    fn prev(&self) -> Result<(), SpotifyPlaybackError> { self.record("prev".to_string()) }
+    fn seek(&self, position_ms: u32) -> Result<(), SpotifyPlaybackError> { self.record(format!("seek {}", position_ms)) }
+    fn set_volume(&self, volume: u16) -> Result<(), SpotifyPlaybackError> { self.record(format!("volume {}", volume)) }
+    fn set_shuffle(&self, shuffle: bool) -> Result<(), SpotifyPlaybackError> { self.record(format!("shuffle {}", shuffle)) }
+    fn set_repeat(&self, repeat: RepeatMode) -> Result<(), SpotifyPlaybackError> { self.record(format!("repeat {:?}", repeat)) }
+    fn shutdown(&self) -> Result<(), SpotifyPlaybackError> { self.record("shutdown".to_string()) }
+}
@@ -0,0 +1,27 @@
+use crate::api::{error::SpotifyAPIError, models::album::AlbumDetails};
+
+// One of these per open album pane, there can be several side by side.
+#[derive(Debug, Clone)]
+pub struct WidgetStateAlbum {
+    pub id: String,
+    pub name: String,
+
+    // None while it's being fetched.
+    pub album: Option<Result<AlbumDetails, SpotifyAPIError>>,
+}
+
+impl WidgetStateAlbum {
+    pub fn new(id: String, name: String) -> Self {
+        WidgetStateAlbum {
+            id,
+            name,
+
+            album: None,
+        }
+    }
+
+    // The window title, the ID keeps albums that share a name apart.
+    pub fn title(&self) -> String {
+        format!("{}##album_{}", self.name, self.id)
+    }
+}
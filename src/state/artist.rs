@@ -0,0 +1,26 @@
+use crate::api::{error::SpotifyAPIError, models::artist::ArtistDetails};
+
+// One of these per open artist pane, like the album ones.
+#[derive(Debug, Clone)]
+pub struct WidgetStateArtist {
+    pub id: String,
+    pub name: String,
+
+    // None while it's being fetched.
+    pub artist: Option<Result<ArtistDetails, SpotifyAPIError>>,
+}
+
+impl WidgetStateArtist {
+    pub fn new(id: String, name: String) -> Self {
+        WidgetStateArtist {
+            id,
+            name,
+
+            artist: None,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        format!("{}##artist_{}", self.name, self.id)
+    }
+}
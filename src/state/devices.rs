@@ -0,0 +1,49 @@
+use std::time::Instant;
+
+use rspotify_model::{CurrentPlaybackContext, Device, PlayableItem};
+
+use crate::{api::error::SpotifyAPIError, playback::status::SpotifyPlaybackStatus};
+
+#[derive(Debug, Default)]
+pub struct WidgetStateDevices {
+    pub fetching: bool,
+    pub devices: Option<Result<Vec<Device>, SpotifyAPIError>>,
+
+    // The Connect device we're driving through the Web API,
+    // None while we're playing on this device.
+    pub remote_device: Option<Device>,
+    pub remote_playback: Option<CurrentPlaybackContext>,
+    // When remote_playback was fetched, its progress is as of then.
+    pub remote_playback_fetched_at: Option<Instant>,
+}
+
+impl WidgetStateDevices {
+    pub fn set_remote_playback(&mut self, playback: Option<CurrentPlaybackContext>) {
+        self.remote_playback = playback;
+        self.remote_playback_fetched_at = Some(Instant::now());
+    }
+
+    /// What the remote device we're controlling is playing, in the same shape
+    /// as the local player's status so the player bar can show either.
+    pub fn remote_status(&self) -> Option<SpotifyPlaybackStatus> {
+        self.remote_device.as_ref()?;
+
+        let Some(playback) = &self.remote_playback else {
+            return Some(SpotifyPlaybackStatus::default());
+        };
+
+        let track = match &playback.item {
+            Some(PlayableItem::Track(track)) => Some(track.clone()),
+            _ => None,
+        };
+
+        Some(SpotifyPlaybackStatus {
+            track,
+            playing: playback.is_playing,
+            loading: false,
+
+            position_ms: playback.progress.map(|p| p.num_milliseconds().max(0) as u32).unwrap_or(0),
+            position_updated_at: self.remote_playback_fetched_at,
+        })
+    }
+}
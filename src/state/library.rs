@@ -0,0 +1,518 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use rspotify_model::{FullArtist, FullTrack, Id as _, SavedAlbum};
+
+use crate::api::error::SpotifyAPIError;
+
+#[derive(Debug, Default)]
+pub struct WidgetStateLikedSongs {
+    pub visible: bool,
+
+    // None until the pane is first opened. Shared so the pane can take
+    // a copy each frame without cloning thousands of tracks.
+    pub tracks: Option<Result<Arc<Vec<FullTrack>>, SpotifyAPIError>>,
+    pub fetching: bool,
+
+    // Whether each track we've come across is in Liked Songs,
+    // None while we're still asking Spotify.
+    pub saved: HashMap<String, Option<bool>>,
+
+    // Bumped on every save or removal, so a failed request only rolls
+    // back if nothing's changed the track since.
+    versions: HashMap<String, u64>,
+}
+
+/// What `set_saved` changed, for putting it back if Spotify refuses.
+#[derive(Debug, Clone)]
+pub struct LikedSongsChange {
+    track: FullTrack,
+    track_id: String,
+    saved: bool,
+
+    // Where the track was in Liked Songs beforehand, if it was there.
+    index: Option<usize>,
+    version: u64,
+}
+
+impl WidgetStateLikedSongs {
+    /// Marks Liked Songs as being fetched, returns false if it's
+    /// already loaded or on its way.
+    pub fn start_fetch(&mut self) -> bool {
+        if self.fetching || matches!(self.tracks, Some(Ok(_))) {
+            return false;
+        }
+
+        self.tracks = None;
+        self.fetching = true;
+
+        true
+    }
+
+    pub fn extend(&mut self, tracks: Vec<FullTrack>) {
+        for track in &tracks {
+            if let Some(id) = &track.id {
+                self.saved.insert(id.id().to_string(), Some(true));
+            }
+        }
+
+        match &mut self.tracks {
+            Some(Ok(existing)) => Arc::make_mut(existing).extend(tracks),
+            _ => self.tracks = Some(Ok(Arc::new(tracks))),
+        }
+    }
+
+    pub fn is_saved(&self, track_id: &str) -> Option<bool> {
+        self.saved.get(track_id).copied().flatten()
+    }
+
+    /// Records `track` as saved or not, adding it to the top of the list or
+    /// taking it out so the pane matches without refetching.
+    pub fn set_saved(&mut self, track: &FullTrack, saved: bool) -> Option<LikedSongsChange> {
+        let track_id = track.id.as_ref().map(|id| id.id().to_string())?;
+
+        self.saved.insert(track_id.clone(), Some(saved));
+
+        let version = self.versions.entry(track_id.clone()).or_default();
+        *version += 1;
+
+        let mut change = LikedSongsChange {
+            track: track.clone(),
+            track_id,
+            saved,
+            index: None,
+            version: *version,
+        };
+
+        let Some(Ok(tracks)) = &mut self.tracks else {
+            return Some(change);
+        };
+
+        let tracks = Arc::make_mut(tracks);
+        change.index = tracks.iter().position(|t| t.id == track.id);
+
+        match (saved, change.index) {
+            (true, None) => tracks.insert(0, track.clone()),
+            (false, Some(index)) => {
+                tracks.remove(index);
+            },
+            _ => {}
+        }
+
+        Some(change)
+    }
+
+    /// Undoes `change`, putting the track back where it was in Liked Songs.
+    /// Does nothing if the track's been saved or removed again since.
+    pub fn revert(&mut self, change: LikedSongsChange) {
+        if self.versions.get(&change.track_id) != Some(&change.version) {
+            return;
+        }
+
+        self.saved.insert(change.track_id.clone(), Some(!change.saved));
+
+        let Some(Ok(tracks)) = &mut self.tracks else {
+            return;
+        };
+
+        let tracks = Arc::make_mut(tracks);
+
+        if let Some(position) = tracks.iter().position(|t| t.id == change.track.id) {
+            tracks.remove(position);
+        }
+
+        if let Some(index) = change.index {
+            tracks.insert(index.min(tracks.len()), change.track);
+        }
+    }
+}
+
+/// A follow or save shown in a library view before Spotify confirmed it,
+/// for putting it back if Spotify refuses.
+#[derive(Debug, Clone)]
+pub struct LibraryChange {
+    pub id: String,
+    pub saved: bool,
+
+    version: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LibraryLayout {
+    #[default]
+    Grid,
+    List,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LibrarySort {
+    RecentlyAdded,
+    Name,
+    Artist,
+}
+
+impl LibrarySort {
+    pub fn title(&self) -> &'static str {
+        match self {
+            LibrarySort::RecentlyAdded => "Recently added",
+            LibrarySort::Name => "Name",
+            LibrarySort::Artist => "Artist",
+        }
+    }
+}
+
+// Something that can sit in one of the library panes.
+pub trait LibraryItem: Clone {
+    // The ways this kind of item can be sorted, the first is the default.
+    const SORTS: &'static [LibrarySort];
+
+    fn id(&self) -> String;
+    fn name(&self) -> &str;
+    fn artist(&self) -> String;
+    fn added_at(&self) -> Option<DateTime<Utc>>;
+}
+
+impl LibraryItem for SavedAlbum {
+    const SORTS: &'static [LibrarySort] = &[LibrarySort::RecentlyAdded, LibrarySort::Name, LibrarySort::Artist];
+
+    fn id(&self) -> String {
+        self.album.id.id().to_string()
+    }
+
+    fn name(&self) -> &str {
+        &self.album.name
+    }
+
+    fn artist(&self) -> String {
+        self.album.artists
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn added_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.added_at)
+    }
+}
+
+// Spotify doesn't say when an artist was followed, so they only sort by name.
+impl LibraryItem for FullArtist {
+    const SORTS: &'static [LibrarySort] = &[LibrarySort::Name];
+
+    fn id(&self) -> String {
+        self.id.id().to_string()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn artist(&self) -> String {
+        self.name.clone()
+    }
+
+    fn added_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+// Your Albums and Your Artists. Like playlists, cloned each frame,
+// so the items and the filtered, sorted view of them are behind Arcs.
+#[derive(Debug, Clone)]
+pub struct WidgetStateLibraryView<T: LibraryItem> {
+    pub visible: bool,
+
+    // None until the pane is first opened.
+    pub items: Option<Result<Arc<Vec<T>>, SpotifyAPIError>>,
+    pub fetching: bool,
+
+    pub layout: LibraryLayout,
+    pub sort: LibrarySort,
+    pub filter: String,
+
+    // Removed or unfollowed, kept out of the view straight away
+    // rather than waiting on Spotify or a refetch.
+    hidden: HashSet<String>,
+    // Same as in WidgetStateLikedSongs, bumped by every `set_saved`.
+    versions: HashMap<String, u64>,
+
+    // Indices into `items` in the order they're shown.
+    pub view: Arc<Vec<usize>>,
+    view_dirty: bool,
+}
+
+impl<T: LibraryItem> Default for WidgetStateLibraryView<T> {
+    fn default() -> Self {
+        WidgetStateLibraryView {
+            visible: false,
+
+            items: None,
+            fetching: false,
+
+            layout: LibraryLayout::default(),
+            sort: T::SORTS[0],
+            filter: String::new(),
+
+            hidden: HashSet::new(),
+            versions: HashMap::new(),
+
+            view: Arc::new(Vec::new()),
+            view_dirty: true,
+        }
+    }
+}
+
+impl<T: LibraryItem> WidgetStateLibraryView<T> {
+    /// Same as `WidgetStateLikedSongs::start_fetch`.
+    pub fn start_fetch(&mut self) -> bool {
+        if self.fetching || matches!(self.items, Some(Ok(_))) {
+            return false;
+        }
+
+        self.items = None;
+        self.fetching = true;
+
+        true
+    }
+
+    pub fn extend(&mut self, items: Vec<T>) {
+        match &mut self.items {
+            Some(Ok(existing)) => Arc::make_mut(existing).extend(items),
+            _ => self.items = Some(Ok(Arc::new(items))),
+        }
+
+        self.view_dirty = true;
+    }
+
+    /// Puts `item` back in view, adding it to the top if we've not got it.
+    pub fn show(&mut self, item: Option<T>, id: &str) {
+        self.hidden.remove(id);
+
+        if let (Some(item), Some(Ok(items))) = (item, &mut self.items) {
+            if !items.iter().any(|i| i.id() == id) {
+                Arc::make_mut(items).insert(0, item);
+            }
+        }
+
+        self.view_dirty = true;
+    }
+
+    pub fn hide(&mut self, id: &str) {
+        self.hidden.insert(id.to_string());
+        self.view_dirty = true;
+    }
+
+    /// Shows or hides `id` straight away for a follow or save we're
+    /// sending Spotify.
+    pub fn set_saved(&mut self, item: Option<T>, id: &str, saved: bool) -> LibraryChange {
+        if saved {
+            self.show(item, id);
+        } else {
+            self.hide(id);
+        }
+
+        let version = self.versions.entry(id.to_string()).or_default();
+        *version += 1;
+
+        LibraryChange {
+            id: id.to_string(),
+            saved,
+            version: *version,
+        }
+    }
+
+    /// Undoes `change`, unless `id` has been followed, saved or removed again
+    /// since. Returns whether it did.
+    pub fn revert(&mut self, change: &LibraryChange) -> bool {
+        if self.versions.get(&change.id) != Some(&change.version) {
+            return false;
+        }
+
+        if change.saved {
+            self.hide(&change.id);
+        } else {
+            self.show(None, &change.id);
+        }
+
+        true
+    }
+
+    pub fn set_filter(&mut self, filter: String) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.view_dirty = true;
+        }
+    }
+
+    pub fn set_sort(&mut self, sort: LibrarySort) {
+        if sort != self.sort {
+            self.sort = sort;
+            self.view_dirty = true;
+        }
+    }
+
+    /// Rebuilds `view` if anything changed since last time.
+    pub fn update_view(&mut self) {
+        if !self.view_dirty {
+            return;
+        }
+
+        self.view_dirty = false;
+
+        let Some(Ok(items)) = &self.items else {
+            self.view = Arc::new(Vec::new());
+
+            return;
+        };
+
+        let filter = self.filter.trim().to_lowercase();
+
+        let mut view = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !self.hidden.contains(&item.id()))
+            .filter(|(_, item)| {
+                filter.is_empty()
+                    || item.name().to_lowercase().contains(&filter)
+                    || item.artist().to_lowercase().contains(&filter)
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+
+        match self.sort {
+            LibrarySort::RecentlyAdded => view.sort_by(|&a, &b| items[b].added_at().cmp(&items[a].added_at())),
+            LibrarySort::Name => view.sort_by_cached_key(|&i| items[i].name().to_lowercase()),
+            LibrarySort::Artist => view.sort_by_cached_key(|&i| (items[i].artist().to_lowercase(), items[i].name().to_lowercase())),
+        }
+
+        self.view = Arc::new(view);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::testing;
+
+    fn track(id: &str) -> FullTrack {
+        testing::track(Some(id), id)
+    }
+
+    const FIRST: &str = "11dFghVXANMlKmJXsNCbNl";
+    const SECOND: &str = "4iV5W9uYEdYUVa79Axb7Rh";
+    const THIRD: &str = "3n3Ppam7vgaVa1iaRUc9Lp";
+
+    fn liked_songs() -> WidgetStateLikedSongs {
+        let mut liked_songs = WidgetStateLikedSongs::default();
+
+        liked_songs.extend(vec![track(FIRST), track(SECOND), track(THIRD)]);
+
+        liked_songs
+    }
+
+    fn names(liked_songs: &WidgetStateLikedSongs) -> Vec<String> {
+        match &liked_songs.tracks {
+            Some(Ok(tracks)) => tracks.iter().map(|t| t.name.clone()).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn puts_removed_tracks_back_where_they_were() {
+        let mut liked_songs = liked_songs();
+
+        let change = liked_songs.set_saved(&track(SECOND), false).unwrap();
+
+        assert_eq!(names(&liked_songs), [FIRST, THIRD]);
+        assert_eq!(liked_songs.is_saved(SECOND), Some(false));
+
+        liked_songs.revert(change);
+
+        assert_eq!(names(&liked_songs), [FIRST, SECOND, THIRD]);
+        assert_eq!(liked_songs.is_saved(SECOND), Some(true));
+    }
+
+    #[test]
+    fn takes_saved_tracks_back_out() {
+        let mut liked_songs = WidgetStateLikedSongs::default();
+        liked_songs.extend(vec![track(FIRST)]);
+
+        let change = liked_songs.set_saved(&track(SECOND), true).unwrap();
+
+        assert_eq!(names(&liked_songs), [SECOND, FIRST]);
+
+        liked_songs.revert(change);
+
+        assert_eq!(names(&liked_songs), [FIRST]);
+        assert_eq!(liked_songs.is_saved(SECOND), Some(false));
+    }
+
+    #[test]
+    fn ignores_rollbacks_overtaken_by_later_changes() {
+        let mut liked_songs = liked_songs();
+
+        let removed = liked_songs.set_saved(&track(SECOND), false).unwrap();
+        let saved = liked_songs.set_saved(&track(SECOND), true).unwrap();
+
+        // The removal failing after the track's been saved again mustn't undo the save.
+        liked_songs.revert(removed);
+
+        assert_eq!(names(&liked_songs), [SECOND, FIRST, THIRD]);
+        assert_eq!(liked_songs.is_saved(SECOND), Some(true));
+
+        liked_songs.revert(saved);
+
+        assert_eq!(names(&liked_songs), [FIRST, THIRD]);
+        assert_eq!(liked_songs.is_saved(SECOND), Some(false));
+    }
+
+    fn artist(id: &str) -> FullArtist {
+        serde_json::from_value(json!({
+            "external_urls": {},
+            "followers": { "href": null, "total": 0 },
+            "genres": [],
+            "href": "",
+            "id": id,
+            "images": [],
+            "name": id,
+            "popularity": 0,
+            "type": "artist"
+        }))
+        .unwrap()
+    }
+
+    fn view_names(view: &mut WidgetStateLibraryView<FullArtist>) -> Vec<String> {
+        view.update_view();
+
+        match &view.items {
+            Some(Ok(items)) => view.view.iter().map(|&i| items[i].name.clone()).collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn rolls_back_only_the_latest_library_change() {
+        let mut view = WidgetStateLibraryView::<FullArtist>::default();
+
+        view.extend(vec![artist(FIRST)]);
+
+        let followed = view.set_saved(Some(artist(SECOND)), SECOND, true);
+
+        assert_eq!(view_names(&mut view), [FIRST, SECOND]);
+
+        // Unfollowed again before the follow failed, that's what should stick.
+        let unfollowed = view.set_saved(None, SECOND, false);
+
+        assert!(!view.revert(&followed));
+        assert_eq!(view_names(&mut view), [FIRST]);
+
+        assert!(view.revert(&unfollowed));
+        assert_eq!(view_names(&mut view), [FIRST, SECOND]);
+    }
+}
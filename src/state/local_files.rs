@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use rspotify_model::FullTrack;
+
+use crate::local::LocalTrack;
+
+#[derive(Debug, Default)]
+pub struct WidgetStateLocalFiles {
+    pub visible: bool,
+
+    // What the pane shows, None until the index or first scan is in.
+    pub tracks: Option<Arc<Vec<FullTrack>>>,
+    pub scanning: bool,
+    // Folders changed mid-scan, so go again once it's done.
+    pub rescan: bool,
+
+    // Every file we know of, as kept in the index on disk.
+    pub files: Vec<LocalTrack>,
+    // Whether `files` has had the index merged into it yet.
+    pub index_loaded: bool,
+}
+
+impl WidgetStateLocalFiles {
+    pub fn set_files(&mut self, files: Vec<LocalTrack>) {
+        let tracks = files
+            .iter()
+            .filter_map(|f| f.to_full_track())
+            .collect::<Vec<FullTrack>>();
+
+        self.files = files;
+        self.tracks = Some(Arc::new(tracks));
+    }
+}
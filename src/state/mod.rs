@@ -1,25 +1,61 @@
+use album::WidgetStateAlbum;
+use artist::WidgetStateArtist;
+use library::{WidgetStateLibraryView, WidgetStateLikedSongs};
+use local_files::WidgetStateLocalFiles;
+use playlist::WidgetStatePlaylist;
 use developer::WidgetStateDeveloper;
+use devices::WidgetStateDevices;
 use preferences::WidgetStatePreferences;
+use queue::PlaybackQueue;
+use recently_played::WidgetStateRecentlyPlayed;
 use search::WidgetStateSearch;
 
+use std::sync::Arc;
+
+use rspotify_model::{FullArtist, SavedAlbum};
+
 use crate::api::{data::SpotifyAPIData, error::SpotifyAPIError, models::recommendations::{BrowseRecommendationSections, BrowseRecommendations}};
 
 use super::theme::UITheme;
 
+pub mod album;
+pub mod artist;
+pub mod library;
+pub mod local_files;
+pub mod playlist;
 pub mod developer;
+pub mod devices;
 pub mod search;
 pub mod preferences;
+pub mod queue;
+pub mod recently_played;
 
 #[derive(Debug, Default)]
 pub struct State {
     pub current_theme: UITheme,
 
     pub home_visible: bool,
+    pub queue_visible: bool,
 
     pub preferences: WidgetStatePreferences,
     pub search: WidgetStateSearch,
-
-    pub recommendations: Option<BrowseRecommendations>,
+    pub liked_songs: WidgetStateLikedSongs,
+    pub recently_played: WidgetStateRecentlyPlayed,
+    pub your_artists: WidgetStateLibraryView<FullArtist>,
+    pub your_albums: WidgetStateLibraryView<SavedAlbum>,
+    pub local_files: WidgetStateLocalFiles,
+
+    // Shared so the home pane doesn't copy every section each frame.
+    pub recommendations: Option<Arc<BrowseRecommendations>>,
+
+    pub albums: Vec<WidgetStateAlbum>,
+    pub artists: Vec<WidgetStateArtist>,
+    pub playlists: Vec<WidgetStatePlaylist>,
+    // The playlist pane Edit > Filter applies to.
+    pub focused_playlist: Option<String>,
+
+    pub queue: PlaybackQueue,
+    pub devices: WidgetStateDevices,
 
     #[cfg(debug_assertions)]
     pub developer: WidgetStateDeveloper,
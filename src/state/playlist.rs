@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use rspotify_model::{FullPlaylist, PlaylistItem};
+
+use crate::api::{
+    error::SpotifyAPIError,
+    models::playlists::{playlist_item_added_by, playlist_item_track},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaylistSortColumn {
+    Index,
+    Title,
+    Artist,
+    Album,
+    DateAdded,
+    AddedBy,
+    Duration,
+}
+
+impl PlaylistSortColumn {
+    pub const ALL: [PlaylistSortColumn; 7] = [
+        PlaylistSortColumn::Index,
+        PlaylistSortColumn::Title,
+        PlaylistSortColumn::Artist,
+        PlaylistSortColumn::Album,
+        PlaylistSortColumn::DateAdded,
+        PlaylistSortColumn::AddedBy,
+        PlaylistSortColumn::Duration,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            PlaylistSortColumn::Index => "#",
+            PlaylistSortColumn::Title => "Title",
+            PlaylistSortColumn::Artist => "Artist",
+            PlaylistSortColumn::Album => "Album",
+            PlaylistSortColumn::DateAdded => "Date added",
+            PlaylistSortColumn::AddedBy => "Added by",
+            PlaylistSortColumn::Duration => "Duration",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaylistSort {
+    pub column: PlaylistSortColumn,
+    pub descending: bool,
+}
+
+// One of these per open playlist pane. Playlists can run to thousands
+// of items, so they're behind Arcs to keep cloning each frame cheap.
+#[derive(Debug, Clone)]
+pub struct WidgetStatePlaylist {
+    pub id: String,
+    pub name: String,
+
+    // None while it's being fetched.
+    pub playlist: Option<Result<Arc<FullPlaylist>, SpotifyAPIError>>,
+    pub items: Arc<Vec<PlaylistItem>>,
+    // Whether there are still pages of items on their way.
+    pub fetching: bool,
+
+    pub filter: String,
+    pub focus_filter: bool,
+    pub sort: Option<PlaylistSort>,
+
+    // Indices into `items` in the order they're shown, after filtering and sorting.
+    pub view: Arc<Vec<usize>>,
+    view_dirty: bool,
+}
+
+impl WidgetStatePlaylist {
+    pub fn new(id: String, name: String) -> Self {
+        WidgetStatePlaylist {
+            id,
+            name,
+
+            playlist: None,
+            items: Arc::new(Vec::new()),
+            fetching: false,
+
+            filter: String::new(),
+            focus_filter: false,
+            sort: None,
+
+            view: Arc::new(Vec::new()),
+            view_dirty: true,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        format!("{}##playlist_{}", self.name, self.id)
+    }
+
+    pub fn extend(&mut self, items: Vec<PlaylistItem>) {
+        Arc::make_mut(&mut self.items).extend(items);
+
+        self.view_dirty = true;
+    }
+
+    pub fn set_filter(&mut self, filter: String) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.view_dirty = true;
+        }
+    }
+
+    pub fn set_sort(&mut self, sort: Option<PlaylistSort>) {
+        if sort != self.sort {
+            self.sort = sort;
+            self.view_dirty = true;
+        }
+    }
+
+    /// Rebuilds `view` if the items, filter or sort changed since last time.
+    pub fn update_view(&mut self) {
+        if !self.view_dirty {
+            return;
+        }
+
+        self.view_dirty = false;
+
+        let filter = self.filter.trim().to_lowercase();
+
+        let mut view = self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| filter.is_empty() || item_matches(item, &filter))
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+
+        if let Some(sort) = self.sort {
+            let items = &self.items;
+
+            view.sort_by(|&a, &b| {
+                let ordering = match sort.column {
+                    PlaylistSortColumn::Index => a.cmp(&b),
+                    PlaylistSortColumn::DateAdded => items[a].added_at.cmp(&items[b].added_at),
+                    PlaylistSortColumn::Duration => item_duration_ms(&items[a]).cmp(&item_duration_ms(&items[b])),
+                    column => item_sort_text(&items[a], column).cmp(&item_sort_text(&items[b], column)),
+                };
+
+                if sort.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+
+        self.view = Arc::new(view);
+    }
+}
+
+fn item_sort_text(item: &PlaylistItem, column: PlaylistSortColumn) -> String {
+    let track = playlist_item_track(item);
+
+    match column {
+        PlaylistSortColumn::Title => track.map(|t| t.name.to_lowercase()),
+        PlaylistSortColumn::Artist => track
+            .and_then(|t| t.artists.first())
+            .map(|a| a.name.to_lowercase()),
+        PlaylistSortColumn::Album => track.map(|t| t.album.name.to_lowercase()),
+        PlaylistSortColumn::AddedBy => Some(playlist_item_added_by(item).to_lowercase()),
+        _ => None,
+    }
+    .unwrap_or_default()
+}
+
+fn item_duration_ms(item: &PlaylistItem) -> i64 {
+    playlist_item_track(item)
+        .map(|t| t.duration.num_milliseconds())
+        .unwrap_or_default()
+}
+
+fn item_matches(item: &PlaylistItem, filter: &str) -> bool {
+    let Some(track) = playlist_item_track(item) else {
+        return false;
+    };
+
+    track.name.to_lowercase().contains(filter)
+        || track.album.name.to_lowercase().contains(filter)
+        || track.artists.iter().any(|a| a.name.to_lowercase().contains(filter))
+}
@@ -1,5 +1,7 @@
 use url::Url;
 
+use crate::api::providers::oauth2::{SpotifyAPIOAuthConfig, SpotifyAPIOAuthPortFallback};
+
 #[derive(Debug, Default)]
 pub struct WidgetStatePreferences {
     pub visible: bool,
@@ -8,5 +10,74 @@ pub struct WidgetStatePreferences {
 
     pub credentials_email: Option<String>,
     pub credentials_password: Option<String>,
-    pub credentials_auth_url: Option<Url>
+    pub credentials_auth_url: Option<Url>,
+
+    // What's typed into the Spotify app section, until it's saved.
+    pub oauth_draft: Option<WidgetStatePreferencesOAuth>,
+
+    // The folder being typed into the Your Files section.
+    pub local_folder_draft: String,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct WidgetStatePreferencesOAuth {
+    pub client_id: String,
+    pub redirect_host: String,
+    pub redirect_port: String,
+    pub fallback: bool,
+    pub fallback_start: String,
+    pub fallback_end: String,
+}
+
+impl WidgetStatePreferencesOAuth {
+    pub fn from_config(config: &SpotifyAPIOAuthConfig) -> Self {
+        let (fallback, fallback_start, fallback_end) = match config.port_fallback {
+            SpotifyAPIOAuthPortFallback::Range { start, end } => (true, start.to_string(), end.to_string()),
+            SpotifyAPIOAuthPortFallback::None => (false, String::new(), String::new()),
+        };
+
+        WidgetStatePreferencesOAuth {
+            client_id: config.client_id.clone(),
+            redirect_host: config.redirect_host.clone(),
+            redirect_port: config.redirect_port.to_string(),
+            fallback,
+            fallback_start,
+            fallback_end,
+        }
+    }
+
+    pub fn to_config(&self) -> Result<SpotifyAPIOAuthConfig, &'static str> {
+        // Ports below 1024 need root on most systems.
+        let parse_port = |port: &str| port.trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|p| *p >= 1024);
+
+        if self.client_id.trim().is_empty() {
+            return Err("Client ID can't be empty.");
+        }
+
+        if self.redirect_host.trim().is_empty() {
+            return Err("Redirect host can't be empty.");
+        }
+
+        let redirect_port = parse_port(&self.redirect_port)
+            .ok_or("Redirect port must be between 1024 and 65535.")?;
+
+        let port_fallback = if self.fallback {
+            match (parse_port(&self.fallback_start), parse_port(&self.fallback_end)) {
+                (Some(start), Some(end)) if start <= end => SpotifyAPIOAuthPortFallback::Range { start, end },
+                _ => return Err("Fallback ports must be a range between 1024 and 65535."),
+            }
+        } else {
+            SpotifyAPIOAuthPortFallback::None
+        };
+
+        Ok(SpotifyAPIOAuthConfig {
+            client_id: self.client_id.trim().to_string(),
+            redirect_host: self.redirect_host.trim().to_string(),
+            redirect_port,
+            port_fallback,
+        })
+    }
 }
@@ -0,0 +1,555 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rspotify_model::FullTrack;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackContext {
+    Album { id: String, name: String },
+    Playlist { id: String, name: String },
+    LikedSongs,
+    LocalFiles,
+    SearchResults { query: String },
+}
+
+impl PlaybackContext {
+    pub fn name(&self) -> String {
+        match self {
+            PlaybackContext::Album { name, .. } => name.clone(),
+            PlaybackContext::Playlist { name, .. } => name.clone(),
+            PlaybackContext::LikedSongs => "Liked Songs".to_string(),
+            PlaybackContext::LocalFiles => "Your Files".to_string(),
+            PlaybackContext::SearchResults { query } => format!("Search results for \"{}\"", query),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    Context,
+    Track,
+}
+
+impl RepeatMode {
+    pub fn cycle(&self) -> RepeatMode {
+        match self {
+            RepeatMode::Off => RepeatMode::Context,
+            RepeatMode::Context => RepeatMode::Track,
+            RepeatMode::Track => RepeatMode::Off,
+        }
+    }
+}
+
+// Where we are in the context's play order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum QueuePosition {
+    // Nothing from the context has played yet.
+    #[default]
+    NotStarted,
+    At(usize),
+    // Played through to the end with repeat off.
+    Ended,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackQueue {
+    pub context: Option<PlaybackContext>,
+    // Shared so the queue pane can take a copy of the queue every frame.
+    context_tracks: Arc<Vec<FullTrack>>,
+
+    // Indices into context_tracks in the order they will be played.
+    order: Arc<Vec<usize>>,
+    // Position of the current context track within order.
+    position: QueuePosition,
+
+    // Tracks explicitly queued by the user, these always play
+    // before we continue with the context.
+    pub user_queue: VecDeque<FullTrack>,
+
+    now_playing: Option<FullTrack>,
+    // Whether now_playing came from user_queue rather than
+    // the context track at position.
+    playing_queued: bool,
+
+    shuffle: bool,
+    shuffle_seed: u64,
+
+    pub repeat: RepeatMode,
+}
+
+impl PlaybackQueue {
+    pub fn new(shuffle_seed: u64) -> Self {
+        PlaybackQueue {
+            shuffle_seed,
+            ..Default::default()
+        }
+    }
+
+    pub fn now_playing(&self) -> Option<&FullTrack> {
+        self.now_playing.as_ref()
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffle
+    }
+
+    pub fn context_tracks(&self) -> &[FullTrack] {
+        &self.context_tracks
+    }
+
+    /// Replaces the current context, starting playback from `start_index`
+    /// (an index into `tracks`, not the shuffled order).
+    pub fn set_context(&mut self, context: Option<PlaybackContext>, tracks: Vec<FullTrack>, start_index: usize) -> Option<FullTrack> {
+        self.context = context;
+        self.context_tracks = Arc::new(tracks);
+        self.playing_queued = false;
+
+        if self.context_tracks.is_empty() {
+            self.order = Arc::default();
+            self.position = QueuePosition::NotStarted;
+            self.now_playing = None;
+
+            return None;
+        }
+
+        let start_index = start_index.min(self.context_tracks.len() - 1);
+
+        self.rebuild_order(start_index);
+        self.position = self.position_of(start_index);
+        self.now_playing = self.context_tracks.get(start_index).cloned();
+
+        self.now_playing.clone()
+    }
+
+    /// Shows a track started elsewhere, e.g. by a Spotify Connect remote,
+    /// as playing. We don't know the rest of its context so that's cleared.
+    pub fn set_remote_track(&mut self, track: FullTrack) {
+        self.clear_context();
+        self.now_playing = Some(track);
+    }
+
+    pub fn clear_context(&mut self) {
+        self.context = None;
+        self.context_tracks = Arc::default();
+        self.order = Arc::default();
+        self.position = QueuePosition::NotStarted;
+        self.playing_queued = false;
+    }
+
+    // Builds the play order, keeping `current` as the first track when shuffled
+    // so turning shuffle on doesn't interrupt what's playing.
+    fn rebuild_order(&mut self, current: usize) {
+        if self.context_tracks.is_empty() {
+            self.order = Arc::default();
+
+            return;
+        }
+
+        let mut order = (0..self.context_tracks.len()).collect::<Vec<usize>>();
+
+        if self.shuffle {
+            order.retain(|&i| i != current);
+
+            let mut rng = StdRng::seed_from_u64(self.shuffle_seed);
+            order.shuffle(&mut rng);
+
+            order.insert(0, current);
+        }
+
+        self.order = Arc::new(order);
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+
+        // Having not started or already ended stays that way.
+        if let Some(current) = self.current_context_index() {
+            self.rebuild_order(current);
+            self.position = self.position_of(current);
+        } else {
+            self.rebuild_order(0);
+        }
+    }
+
+    pub fn set_shuffle_seed(&mut self, shuffle_seed: u64) {
+        self.shuffle_seed = shuffle_seed;
+
+        if self.shuffle {
+            self.set_shuffle(true);
+        }
+    }
+
+    fn current_context_index(&self) -> Option<usize> {
+        match self.position {
+            QueuePosition::At(position) => self.order.get(position).copied(),
+            _ => None,
+        }
+    }
+
+    fn position_of(&self, index: usize) -> QueuePosition {
+        self.order
+            .iter()
+            .position(|&i| i == index)
+            .map(QueuePosition::At)
+            .unwrap_or_default()
+    }
+
+    fn set_position(&mut self, position: QueuePosition) -> Option<FullTrack> {
+        self.position = position;
+        self.playing_queued = false;
+
+        self.now_playing = self.current_context_index()
+            .and_then(|i| self.context_tracks.get(i))
+            .cloned();
+
+        self.now_playing.clone()
+    }
+
+    /// Adds a track to the end of the user queue.
+    pub fn add_to_queue(&mut self, track: FullTrack) {
+        self.user_queue.push_back(track);
+    }
+
+    /// Adds a track to the front of the user queue.
+    pub fn play_next(&mut self, track: FullTrack) {
+        self.user_queue.push_front(track);
+    }
+
+    pub fn remove_from_queue(&mut self, index: usize) -> Option<FullTrack> {
+        self.user_queue.remove(index)
+    }
+
+    pub fn move_in_queue(&mut self, from: usize, to: usize) {
+        if from >= self.user_queue.len() || to >= self.user_queue.len() || from == to {
+            return;
+        }
+
+        if let Some(track) = self.user_queue.remove(from) {
+            self.user_queue.insert(to, track);
+        }
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.user_queue.clear();
+    }
+
+    /// Indices into `context_tracks` of the tracks that will play after
+    /// the current one, in play order. Doesn't include wrap-around from repeat.
+    pub fn upcoming_indices(&self) -> &[usize] {
+        let start = match self.position {
+            QueuePosition::NotStarted => 0,
+            QueuePosition::At(position) => position + 1,
+            QueuePosition::Ended => self.order.len(),
+        };
+
+        self.order.get(start..).unwrap_or_default()
+    }
+
+    /// Tracks from the context that will play after the current one,
+    /// in play order. Doesn't include wrap-around from repeat.
+    pub fn upcoming_context(&self) -> Vec<&FullTrack> {
+        self.upcoming_indices()
+            .iter()
+            .filter_map(|&i| self.context_tracks.get(i))
+            .collect()
+    }
+
+    /// Advances to the next track. `skip` is true when the user asked
+    /// for the next track, which ignores repeating the current track.
+    pub fn next_track(&mut self, skip: bool) -> Option<FullTrack> {
+        if !skip && self.repeat == RepeatMode::Track && self.now_playing.is_some() {
+            return self.now_playing.clone();
+        }
+
+        if let Some(track) = self.user_queue.pop_front() {
+            self.now_playing = Some(track);
+            self.playing_queued = true;
+
+            return self.now_playing.clone();
+        }
+
+        if self.order.is_empty() {
+            self.now_playing = None;
+            self.playing_queued = false;
+
+            return None;
+        }
+
+        let next_position = match self.position {
+            QueuePosition::NotStarted => 0,
+            QueuePosition::At(position) => position + 1,
+            // Nothing more to play unless repeat was turned on since.
+            QueuePosition::Ended => self.order.len(),
+        };
+
+        let position = if next_position < self.order.len() {
+            QueuePosition::At(next_position)
+        } else if self.repeat != RepeatMode::Off {
+            QueuePosition::At(0)
+        } else {
+            QueuePosition::Ended
+        };
+
+        self.set_position(position)
+    }
+
+    /// Goes back to the previous context track. Tracks from the user queue
+    /// aren't kept once played, so this only ever walks the context.
+    pub fn previous_track(&mut self) -> Option<FullTrack> {
+        if self.order.is_empty() {
+            return self.now_playing.clone();
+        }
+
+        let position = match self.position {
+            // The context track at position played before the queued one
+            // that's playing now, so that's the one to go back to.
+            QueuePosition::At(position) if self.playing_queued => position,
+            QueuePosition::At(0) if self.repeat != RepeatMode::Off => self.order.len() - 1,
+            QueuePosition::At(0) => 0,
+            QueuePosition::At(position) => position - 1,
+            QueuePosition::NotStarted => 0,
+            QueuePosition::Ended => self.order.len() - 1,
+        };
+
+        self.set_position(QueuePosition::At(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::testing;
+
+    // Named after `index`, with an ID that looks like Spotify's.
+    fn track(index: usize) -> FullTrack {
+        testing::track(Some(&format!("{:0>22}", index)), &index.to_string())
+    }
+
+    fn tracks(count: usize) -> Vec<FullTrack> {
+        (0..count).map(track).collect()
+    }
+
+    fn index(track: Option<FullTrack>) -> Option<usize> {
+        track.map(|t| t.name.parse().unwrap())
+    }
+
+    fn context_queue(count: usize, start_index: usize) -> (PlaybackQueue, Option<FullTrack>) {
+        let mut queue = PlaybackQueue::new(42);
+        let first = queue.set_context(None, tracks(count), start_index);
+
+        (queue, first)
+    }
+
+    #[test]
+    fn set_context_starts_from_index() {
+        let (queue, first) = context_queue(3, 1);
+
+        assert_eq!(index(first), Some(1));
+        assert_eq!(queue.upcoming_context().len(), 1);
+    }
+
+    #[test]
+    fn upcoming_indices_follow_the_shuffled_order() {
+        let (mut queue, _) = context_queue(5, 2);
+
+        queue.set_shuffle(true);
+
+        let upcoming = queue.upcoming_indices().to_vec();
+
+        // Every other track, each pointing at its own place in the context.
+        assert_eq!(upcoming.len(), 4);
+        assert!(!upcoming.contains(&2));
+
+        for (row, track) in queue.upcoming_context().into_iter().enumerate() {
+            assert_eq!(track.name, upcoming[row].to_string());
+        }
+    }
+
+    #[test]
+    fn set_context_clamps_start_index() {
+        let (_, first) = context_queue(3, 10);
+
+        assert_eq!(index(first), Some(2));
+    }
+
+    #[test]
+    fn set_context_empty() {
+        let (mut queue, first) = context_queue(0, 0);
+
+        assert!(first.is_none());
+        assert!(queue.next_track(false).is_none());
+    }
+
+    #[test]
+    fn next_track_walks_context_then_stops() {
+        let (mut queue, _) = context_queue(3, 0);
+
+        assert_eq!(index(queue.next_track(false)), Some(1));
+        assert_eq!(index(queue.next_track(false)), Some(2));
+        assert_eq!(index(queue.next_track(false)), None);
+        assert!(queue.now_playing().is_none());
+    }
+
+    #[test]
+    fn next_track_stays_ended() {
+        let (mut queue, _) = context_queue(2, 1);
+
+        assert_eq!(index(queue.next_track(false)), None);
+        assert_eq!(index(queue.next_track(false)), None);
+        assert!(queue.upcoming_context().is_empty());
+
+        // Still plays anything queued after the end, then ends again.
+        queue.add_to_queue(track(100));
+
+        assert_eq!(index(queue.next_track(false)), Some(100));
+        assert_eq!(index(queue.next_track(false)), None);
+
+        // Going back from the end picks up the last track.
+        assert_eq!(index(queue.previous_track()), Some(1));
+    }
+
+    #[test]
+    fn previous_track_returns_to_context_after_queued_track() {
+        let (mut queue, _) = context_queue(3, 0);
+
+        assert_eq!(index(queue.next_track(false)), Some(1));
+
+        queue.add_to_queue(track(100));
+
+        assert_eq!(index(queue.next_track(false)), Some(100));
+        assert_eq!(index(queue.previous_track()), Some(1));
+        assert_eq!(index(queue.previous_track()), Some(0));
+    }
+
+    #[test]
+    fn previous_track_walks_back_and_stays_at_start() {
+        let (mut queue, _) = context_queue(3, 2);
+
+        assert_eq!(index(queue.previous_track()), Some(1));
+        assert_eq!(index(queue.previous_track()), Some(0));
+        assert_eq!(index(queue.previous_track()), Some(0));
+    }
+
+    #[test]
+    fn user_queue_plays_before_context() {
+        let (mut queue, _) = context_queue(3, 0);
+
+        queue.add_to_queue(track(100));
+        queue.play_next(track(101));
+
+        assert_eq!(index(queue.next_track(false)), Some(101));
+        assert_eq!(index(queue.next_track(false)), Some(100));
+        assert_eq!(index(queue.next_track(false)), Some(1));
+    }
+
+    #[test]
+    fn user_queue_plays_over_repeated_track_when_skipping() {
+        let (mut queue, _) = context_queue(3, 0);
+
+        queue.repeat = RepeatMode::Track;
+        queue.add_to_queue(track(100));
+
+        assert_eq!(index(queue.next_track(false)), Some(0));
+        assert_eq!(index(queue.next_track(true)), Some(100));
+    }
+
+    #[test]
+    fn move_in_queue_reorders() {
+        let mut queue = PlaybackQueue::new(0);
+
+        queue.add_to_queue(track(100));
+        queue.add_to_queue(track(101));
+        queue.move_in_queue(1, 0);
+
+        assert_eq!(index(queue.user_queue.front().cloned()), Some(101));
+    }
+
+    #[test]
+    fn repeat_off_ends() {
+        let (mut queue, _) = context_queue(2, 1);
+
+        assert_eq!(index(queue.next_track(false)), None);
+    }
+
+    #[test]
+    fn repeat_context_wraps_both_ways() {
+        let (mut queue, _) = context_queue(2, 1);
+
+        queue.repeat = RepeatMode::Context;
+
+        assert_eq!(index(queue.next_track(false)), Some(0));
+        assert_eq!(index(queue.previous_track()), Some(1));
+    }
+
+    #[test]
+    fn repeat_track_repeats_unless_skipped() {
+        let (mut queue, _) = context_queue(2, 0);
+
+        queue.repeat = RepeatMode::Track;
+
+        assert_eq!(index(queue.next_track(false)), Some(0));
+        assert_eq!(index(queue.next_track(true)), Some(1));
+    }
+
+    #[test]
+    fn repeat_cycles() {
+        assert_eq!(RepeatMode::Off.cycle(), RepeatMode::Context);
+        assert_eq!(RepeatMode::Context.cycle(), RepeatMode::Track);
+        assert_eq!(RepeatMode::Track.cycle(), RepeatMode::Off);
+    }
+
+    fn shuffled(seed: u64) -> (Option<usize>, Vec<usize>) {
+        let mut queue = PlaybackQueue::new(seed);
+
+        queue.set_context(None, tracks(20), 5);
+        queue.set_shuffle(true);
+
+        let upcoming = queue.upcoming_context()
+            .into_iter()
+            .map(|t| t.name.parse().unwrap())
+            .collect();
+
+        (index(queue.now_playing().cloned()), upcoming)
+    }
+
+    #[test]
+    fn shuffle_is_reproducible_with_seed() {
+        assert_eq!(shuffled(7), shuffled(7));
+        assert_ne!(shuffled(7).1, shuffled(8).1);
+    }
+
+    #[test]
+    fn shuffle_keeps_current_track_and_every_other() {
+        let (now_playing, mut upcoming) = shuffled(7);
+
+        assert_eq!(now_playing, Some(5));
+
+        upcoming.sort();
+
+        assert_eq!(upcoming, (0..20).filter(|&i| i != 5).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn unshuffle_resumes_in_order() {
+        let mut queue = PlaybackQueue::new(7);
+
+        queue.set_context(None, tracks(5), 2);
+        queue.set_shuffle(true);
+        queue.set_shuffle(false);
+
+        assert_eq!(index(queue.next_track(false)), Some(3));
+    }
+
+    #[test]
+    fn shuffle_with_empty_context_has_no_order() {
+        let mut queue = PlaybackQueue::new(7);
+
+        queue.set_shuffle(true);
+
+        assert!(queue.upcoming_context().is_empty());
+        assert!(queue.next_track(false).is_none());
+        assert!(queue.previous_track().is_none());
+    }
+}
@@ -0,0 +1,70 @@
+use std::{ops::Range, sync::Arc};
+
+use chrono::{Local, NaiveDate};
+use rspotify_model::PlayHistory;
+
+use crate::api::error::SpotifyAPIError;
+
+// A day's plays in local time, newest first.
+#[derive(Debug, Clone)]
+pub struct RecentlyPlayedDay {
+    pub date: NaiveDate,
+
+    // Every day shares the one list rather than copying its own plays.
+    all: Arc<Vec<PlayHistory>>,
+    range: Range<usize>,
+}
+
+impl RecentlyPlayedDay {
+    pub fn plays(&self) -> &[PlayHistory] {
+        &self.all[self.range.clone()]
+    }
+
+    pub fn title(&self) -> String {
+        let today = Local::now().date_naive();
+
+        if self.date == today {
+            "Today".to_string()
+        } else if today.pred_opt() == Some(self.date) {
+            "Yesterday".to_string()
+        } else {
+            self.date.format("%A %-d %B %Y").to_string()
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WidgetStateRecentlyPlayed {
+    pub visible: bool,
+
+    // What the pane shows, None until the first fetch finishes.
+    pub days: Option<Result<Arc<Vec<RecentlyPlayedDay>>, SpotifyAPIError>>,
+    pub fetching: bool,
+
+    // Every play we know of, newest first, as kept on disk.
+    pub plays: Arc<Vec<PlayHistory>>,
+    // Whether `plays` has had the history file merged into it yet.
+    pub history_loaded: bool,
+}
+
+impl WidgetStateRecentlyPlayed {
+    pub fn set_plays(&mut self, plays: Arc<Vec<PlayHistory>>) {
+        let mut days: Vec<RecentlyPlayedDay> = Vec::new();
+
+        for (index, play) in plays.iter().enumerate() {
+            let date = play.played_at.with_timezone(&Local).date_naive();
+
+            match days.last_mut() {
+                Some(day) if day.date == date => day.range.end = index + 1,
+                _ => days.push(RecentlyPlayedDay {
+                    date,
+                    all: Arc::clone(&plays),
+                    range: index..index + 1,
+                }),
+            }
+        }
+
+        self.plays = plays;
+        self.days = Some(Ok(Arc::new(days)));
+    }
+}
@@ -3,7 +3,7 @@ use std::{default, sync::Arc};
 use rspotify_model::SearchResult;
 use tokio::task::{AbortHandle, JoinHandle};
 
-use crate::api::{error::SpotifyAPIError, models::search::SearchResults};
+use crate::api::{error::SpotifyAPIError, models::search::{SearchResults, SearchResultsKind}};
 
 #[derive(Debug, Default)]
 pub enum WidgetStateSearchResults {
@@ -22,4 +22,8 @@ pub struct WidgetStateSearch {
 
     pub search_task: Option<AbortHandle>,
     pub search_results: WidgetStateSearchResults,
+
+    // Which kind of result we're fetching another page of, if any.
+    pub fetching_more: Option<SearchResultsKind>,
+    pub more_task: Option<AbortHandle>,
 }
\ No newline at end of file
@@ -43,3 +43,32 @@ pub fn color_light_dark(theme: UITheme, light_color: Color, dark_color: Color) -
     }
 }
 
+
+pub fn format_duration_ms(duration_ms: u32) -> String {
+    let total_seconds = duration_ms / 1000;
+
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+// Longer form for totals, e.g. "1 hr 12 min" for a whole album.
+pub fn format_duration_long_ms(duration_ms: u32) -> String {
+    let total_seconds = duration_ms / 1000;
+
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+
+    if hours > 0 {
+        format!("{} hr {} min", hours, minutes)
+    } else {
+        format!("{} min {} sec", minutes, seconds)
+    }
+}
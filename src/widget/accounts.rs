@@ -0,0 +1,204 @@
+use std::{fs, path::PathBuf};
+
+use merge_struct::merge;
+use rspotify_model::{Id as _, PrivateUser};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::api::models::user::UserImpl as _;
+
+use super::preferences::Preferences;
+
+pub const ACCOUNTS_FILE_NAME: &str = "accounts.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Account {
+    // Spotify user ID, also the key for the account's credentials.
+    pub id: String,
+
+    // Kept so the switcher can show accounts we aren't logged into right now.
+    pub profile: Option<PrivateUser>,
+
+    // Applied on top of preferences.toml while this account is in use.
+    pub overrides: Option<Preferences>,
+}
+
+impl Account {
+    pub fn name(&self) -> String {
+        self.profile
+            .as_ref()
+            .map(|p| p.name())
+            .unwrap_or(self.id.clone())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Accounts {
+    pub current: Option<String>,
+    pub accounts: Vec<Account>,
+}
+
+/// Everyone who's logged in on this machine, kept in accounts.json beside
+/// preferences.toml. Read and written whole, unlike preferences there's
+/// nothing to merge with.
+#[derive(Clone, Default)]
+pub struct AccountsManager {
+    path: Option<PathBuf>,
+
+    data: Accounts,
+}
+
+impl AccountsManager {
+    pub fn new(config_dir: Option<PathBuf>) -> AccountsManager {
+        let path = config_dir.map(|d| d.join(ACCOUNTS_FILE_NAME));
+
+        let data = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| match serde_json::from_str::<Accounts>(&contents) {
+                Ok(accounts) => Some(accounts),
+                Err(err) => {
+                    error!("Failed to parse accounts file: {:#?}", err);
+
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        AccountsManager { path, data }
+    }
+
+    fn write(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let result = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(path, contents).map_err(|e| e.to_string()));
+
+        if let Err(err) = result {
+            error!("Failed to write accounts file at {}: {}", path.display(), err);
+        }
+    }
+
+    pub fn list(&self) -> &[Account] {
+        &self.data.accounts
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Account> {
+        self.data.accounts.iter().find(|a| a.id == id)
+    }
+
+    pub fn current_id(&self) -> Option<String> {
+        self.data.current.clone()
+    }
+
+    pub fn current(&self) -> Option<&Account> {
+        self.data.current.as_deref().and_then(|id| self.get(id))
+    }
+
+    pub fn set_current(&mut self, id: Option<String>) {
+        self.data.current = id;
+        self.write();
+    }
+
+    /// Remembers whoever just logged in, and makes them the current account.
+    pub fn upsert(&mut self, profile: PrivateUser) {
+        let id = profile.id.id().to_string();
+
+        match self.data.accounts.iter_mut().find(|a| a.id == id) {
+            Some(account) => account.profile = Some(profile),
+            None => {
+                info!("Adding {} to accounts.", profile.name());
+
+                self.data.accounts.push(Account {
+                    id: id.clone(),
+                    profile: Some(profile),
+                    overrides: None,
+                });
+            }
+        }
+
+        self.data.current = Some(id);
+        self.write();
+    }
+
+    /// Layers `prefs` over the current account's overrides. Returns the new
+    /// overrides, or None when nobody's logged in and there's nowhere to keep them.
+    pub fn set_overrides(&mut self, prefs: Preferences) -> Option<Preferences> {
+        let current = self.data.current.clone()?;
+        let account = self.data.accounts.iter_mut().find(|a| a.id == current)?;
+
+        let overrides = match merge(&account.overrides.clone().unwrap_or_default(), &prefs) {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                error!("Failed to merge preferences into overrides for {}: {:#?}", account.id, err);
+
+                return None;
+            }
+        };
+
+        account.overrides = Some(overrides.clone());
+        self.write();
+
+        Some(overrides)
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.data.accounts.retain(|a| a.id != id);
+
+        if self.data.current.as_deref() == Some(id) {
+            self.data.current = None;
+        }
+
+        self.write();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::widget::theme::UITheme;
+
+    use super::*;
+
+    fn profile(id: &str) -> PrivateUser {
+        serde_json::from_value(json!({
+            "display_name": id,
+            "external_urls": { "spotify": format!("https://open.spotify.com/user/{}", id) },
+            "href": format!("https://api.spotify.com/v1/users/{}", id),
+            "id": id,
+            "type": "user",
+            "uri": format!("spotify:user:{}", id)
+        })).unwrap()
+    }
+
+    #[test]
+    fn overrides_belong_to_the_current_account() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut accounts = AccountsManager::new(Some(dir.path().to_path_buf()));
+
+        assert!(accounts.set_overrides(Preferences { theme: Some(UITheme::Dark), ..Default::default() }).is_none());
+
+        accounts.upsert(profile("alice"));
+        accounts.set_overrides(Preferences { theme: Some(UITheme::Dark), ..Default::default() });
+        accounts.set_overrides(Preferences { zoom_level: Some(1.5), ..Default::default() });
+
+        accounts.upsert(profile("bob"));
+        accounts.set_overrides(Preferences { theme: Some(UITheme::Light), ..Default::default() });
+
+        // Read back from accounts.json, not what's in memory.
+        let accounts = AccountsManager::new(Some(dir.path().to_path_buf()));
+
+        let alice = accounts.get("alice").and_then(|a| a.overrides.clone()).unwrap();
+        let bob = accounts.get("bob").and_then(|a| a.overrides.clone()).unwrap();
+
+        assert_eq!(alice.theme, Some(UITheme::Dark));
+        assert_eq!(alice.zoom_level, Some(1.5));
+        assert_eq!(bob.theme, Some(UITheme::Light));
+        assert_eq!(bob.zoom_level, None);
+    }
+}
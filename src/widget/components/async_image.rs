@@ -1,5 +1,72 @@
+use easy_imgui::{vec2, Color, ColorId, DrawFlags};
+use rspotify_model::Image;
+
+use crate::constants::UI_ALBUM_ART_SIZE;
+
 use super::ComponentContext;
 
-pub fn build(context: &mut ComponentContext) {
+/// Picks the smallest of Spotify's image sizes that still covers `size`,
+/// they're sorted largest first but don't always have dimensions.
+pub fn pick_image(images: &[Image], size: f32) -> Option<&str> {
+    images
+        .iter()
+        .filter(|i| i.width.map_or(true, |w| w as f32 >= size))
+        .last()
+        .or(images.first())
+        .map(|i| i.url.as_str())
+}
+
+/// Draws the image at `url` as a `size` square, with the album art
+/// placeholder in its place until it's loaded (or if there's none).
+pub fn build(context: &mut ComponentContext, url: Option<&str>, size: f32) {
+    let texture = url.and_then(|url| context.widget.images.get(url));
+
+    match texture {
+        Some((texture_id, _)) => {
+            context.ui.image_config(texture_id, vec2(size, size)).build();
+        },
+        None => {
+            context
+                .ui
+                .image_with_custom_rect_config(context.widget.glyph_album_art, size / UI_ALBUM_ART_SIZE)
+                .build();
+        }
+    }
+}
+
+/// Like `build`, but with rounded corners, e.g. a `rounding` of
+/// half the size for the circular artist images.
+pub fn build_rounded(context: &mut ComponentContext, url: Option<&str>, size: f32, rounding: f32) {
+    let texture = url.and_then(|url| context.widget.images.get(url));
+
+    let p_min = context.ui.get_cursor_screen_pos();
+    let p_max = vec2(p_min.x + size, p_min.y + size);
+
+    let draw_list = context.ui.window_draw_list();
+
+    match texture {
+        Some((texture_id, _)) => {
+            draw_list.add_image_rounded(
+                texture_id,
+                p_min,
+                p_max,
+                vec2(0.0, 0.0),
+                vec2(1.0, 1.0),
+                Color::WHITE,
+                rounding,
+                DrawFlags::None,
+            );
+        },
+        None => {
+            draw_list.add_rect_filled(
+                p_min,
+                p_max,
+                context.ui.style().color(ColorId::FrameBg),
+                rounding,
+                DrawFlags::None,
+            );
+        }
+    }
 
-}
\ No newline at end of file
+    context.ui.dummy(vec2(size, size));
+}
@@ -1,94 +1,283 @@
 use easy_imgui::{
-    vec2, ColorId, CustomRectIndex, ImGuiID, StyleValue, StyleVar, TableColumnFlags, TableFlags,
-    TableRowFlags,
+    vec2, Color, ColorId, ItemId, MouseButton, StyleValue, StyleVar, TableFlags,
 };
-use rspotify_model::{FullTrack, SimplifiedTrack};
+use rspotify_model::{FullAlbum, Id as _, Image, SimplifiedArtist};
 
-use crate::constants::UI_ALBUM_ART_SIZE;
-
-use super::ComponentContext;
+use crate::{
+    api::models::{entity::SpotifyEntity, recommendations::BrowseRecommendationItem},
+    commands::AppCommand,
+    constants::{UI_ACCENT_COLOR, UI_CARD_WIDTH},
+    dummy,
+    widget::icons::set::{UI_ICON_MEDIA_EXPLICIT, UI_ICON_MEDIA_PLAY},
+};
 
-pub enum CardType {
-    FullTrack(FullTrack),
-    SimplifiedTrack(SimplifiedTrack)
-}
+use super::{async_image, ComponentContext};
 
 #[derive(Debug, Default)]
 pub struct CardDetails {
     title: String,
     subtitle: Option<String>,
-    image: Option<String>,
-    href: Option<String>
+    images: Vec<Image>,
+    explicit: bool,
+    // Artists get a round picture, like everywhere else in Spotify.
+    round: bool,
+    entity: Option<SpotifyEntity>,
 }
 
-pub fn build(context: &mut ComponentContext, data: CardType) {
-    let details = match data {
-        CardType::FullTrack(track) => CardDetails {
-            image: track.album.images
-                .first().map(|i| i.url.clone()),
-            ..Default::default()
-        },
-        CardType::SimplifiedTrack(track) => CardDetails {
-            image: track.album
-                .and_then(|a|
-                    a.images
-                        .first()
-                        .map(|i| i.url.clone())
+impl CardDetails {
+    pub fn from_recommendation(item: &BrowseRecommendationItem) -> Self {
+        let entity = SpotifyEntity::from_recommendation(item);
+
+        match item {
+            BrowseRecommendationItem::Album(album) => CardDetails {
+                title: album.name.clone(),
+                subtitle: Some(
+                    album.release_date
+                        .as_ref()
+                        .and_then(|d| d.split('-').next())
+                        .map(|year| format!("{} - {}", year, join_artists(&album.artists)))
+                        .unwrap_or(join_artists(&album.artists))
                 ),
+                images: album.images.clone(),
+                entity,
+                ..Default::default()
+            },
+            BrowseRecommendationItem::Artist(artist) => CardDetails {
+                title: artist.name.clone(),
+                subtitle: Some("Artist".to_string()),
+                images: artist.images.clone(),
+                round: true,
+                entity,
+                ..Default::default()
+            },
+            BrowseRecommendationItem::Playlist(playlist) => CardDetails {
+                title: playlist.name.clone(),
+                subtitle: playlist.description
+                    .clone()
+                    .filter(|d| !d.trim().is_empty())
+                    .or(
+                        playlist.owner.display_name
+                            .clone()
+                            .or(Some(playlist.owner.id.id().to_string()))
+                            .map(|owner| format!("By {}", owner))
+                    ),
+                images: playlist.images.clone(),
+                entity,
+                ..Default::default()
+            },
+            BrowseRecommendationItem::Track(track) => CardDetails {
+                title: track.name.clone(),
+                subtitle: Some(join_artists(&track.artists)),
+                images: track.album.images.clone(),
+                explicit: track.explicit,
+                entity,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn from_album(album: &FullAlbum) -> Self {
+        CardDetails {
+            title: album.name.clone(),
+            subtitle: Some(
+                album.release_date
+                    .split('-')
+                    .next()
+                    .map(|year| format!("{} - {}", year, join_artists(&album.artists)))
+                    .unwrap_or(join_artists(&album.artists))
+            ),
+            images: album.images.clone(),
+            entity: Some(SpotifyEntity::Album {
+                id: album.id.id().to_string(),
+                name: album.name.clone(),
+            }),
             ..Default::default()
         }
-    };
+    }
+}
 
-    context
-        .ui
-        .table_config("Card", 1)
-        .flags(TableFlags::Borders)
-        .with(|| {
-            context.ui.table_setup_column(
-                "Card Details",
-                TableColumnFlags::WidthStretch,
-                -1.0,
-                ImGuiID::default(),
-            );
+fn join_artists(artists: &[SimplifiedArtist]) -> String {
+    artists
+        .iter()
+        .map(|a| a.name.clone())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
 
-            // context.ui.table_next_row(TableRowFlags::None, 180.0);
-            context.ui.table_next_column();
+/// Cuts `text` down to fit in `width`, ending it with "..." if it didn't.
+fn fit_text(context: &ComponentContext, text: &str, width: f32) -> String {
+    if context.ui.calc_text_size(text).x <= width {
+        return text.to_string();
+    }
 
-            // let image = image::load_from_memory(reqwest::)
+    // Where each character starts, so we only ever cut between them.
+    let cuts = text.char_indices().map(|(i, _)| i).collect::<Vec<usize>>();
 
-            // context
-            //     .widget
-            //     .create_image(context.ui, data.image, 180.0 / UI_ALBUM_ART_SIZE)
-            //     .build();
+    let fits = |cut: usize| context.ui.calc_text_size(&format!("{}...", &text[..cut])).x <= width;
 
-            context.ui.table_next_row(TableRowFlags::None, 200.0);
+    // Text only gets wider as characters are added, so binary search for
+    // the longest start that fits. Nothing at all always "fits", the
+    // whole text never does.
+    let (mut low, mut high) = (0, cuts.len());
 
-            context.ui.with_push(
-                (StyleVar::ItemSpacing, StyleValue::Vec2(vec2(0.0, 2.0))),
-                || {
-                    context.ui.with_push(
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+
+        if fits(cuts[mid]) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    format!("{}...", text[..cuts[low]].trim_end())
+}
+
+/// Returns whether the card was right clicked, for callers with a menu to show.
+pub fn build(context: &mut ComponentContext, details: CardDetails) -> bool {
+    let width = UI_CARD_WIDTH * context.widget.ui_scale;
+    let cover_pos = context.ui.get_cursor_screen_pos();
+
+    context.ui.with_group(|| {
+        let url = async_image::pick_image(&details.images, width);
+
+        let rounding = if details.round { width / 2.0 } else { 6.0 * context.widget.ui_scale };
+
+        async_image::build_rounded(context, url, width, rounding);
+
+        dummy!(context);
+
+        context.ui.with_push(
+            (StyleVar::ItemSpacing, StyleValue::Vec2(vec2(4.0 * context.widget.ui_scale, 2.0))),
+            || {
+                context.ui.with_push(
+                    (
+                        context.widget.font_bold,
+                        (ColorId::Text, context.ui.style().color(ColorId::Text)),
+                    ),
+                    || {
+                        context.ui.text(&fit_text(context, &details.title, width));
+                    },
+                );
+
+                context.ui.with_push(
+                    (
+                        context.widget.font_small,
                         (
-                            context.widget.font_bold,
-                            (ColorId::Text, context.ui.style().color(ColorId::Text)),
+                            ColorId::Text,
+                            context.ui.style().color_alpha(ColorId::Text, 0.7),
                         ),
-                        || {
-                            context.ui.text("All Out 80s");
-                        },
-                    );
+                    ),
+                    || {
+                        let mut subtitle_width = width;
 
-                    context.ui.with_push(
-                        (
-                            context.widget.font_small,
-                            (
-                                ColorId::Text,
+                        if details.explicit {
+                            context.widget.create_icon(
+                                context.ui,
+                                UI_ICON_MEDIA_EXPLICIT,
+                                14.0,
                                 context.ui.style().color_alpha(ColorId::Text, 0.7),
-                            ),
-                        ),
-                        || {
-                            context.ui.text("By Spotify");
-                        },
-                    );
-                },
-            );
+                            );
+                            context.ui.same_line();
+
+                            subtitle_width -= 18.0 * context.widget.ui_scale;
+                        }
+
+                        context.ui.text(&fit_text(
+                            context,
+                            details.subtitle.as_deref().unwrap_or(""),
+                            subtitle_width
+                        ));
+                    },
+                );
+            },
+        );
+    });
+
+    let hovered = context.ui.is_item_hovered();
+    let clicked = context.ui.is_item_clicked(MouseButton::Left);
+    let right_clicked = context.ui.is_item_clicked(MouseButton::Right);
+
+    let Some(entity) = details.entity else {
+        return right_clicked;
+    };
+
+    let mut play_clicked = false;
+
+    if hovered {
+        let button_size = 40.0 * context.widget.ui_scale;
+        let after_card = context.ui.get_cursor_screen_pos();
+
+        context.ui.set_cursor_screen_pos(vec2(
+            cover_pos.x + width - button_size - 8.0 * context.widget.ui_scale,
+            cover_pos.y + width - button_size - 8.0 * context.widget.ui_scale,
+        ));
+
+        play_clicked = context.widget.create_icon_button(
+            context.ui,
+            UI_ICON_MEDIA_PLAY,
+            24.0,
+            Color::BLACK,
+            UI_ACCENT_COLOR,
+            UI_ACCENT_COLOR,
+            UI_ACCENT_COLOR,
+            100.0,
+        );
+
+        context.ui.set_cursor_screen_pos(after_card);
+    }
+
+    if play_clicked {
+        context.widget.send_command(context.event_loop, AppCommand::PlayEntity(entity));
+    } else if clicked {
+        context.widget.send_command(context.event_loop, AppCommand::Open(entity));
+    }
+
+    right_clicked
+}
+
+/// Lays `items` out in as many columns of cards as fit the window.
+pub fn build_grid(context: &mut ComponentContext, id: &str, items: &[BrowseRecommendationItem]) {
+    let cards = items
+        .iter()
+        .map(CardDetails::from_recommendation)
+        .collect::<Vec<CardDetails>>();
+
+    build_grid_with_menu(context, id, cards, |_, _| {});
+}
+
+/// Same as `build_grid`, calling `menu` with the card's index to fill
+/// in a right click menu for it.
+pub fn build_grid_with_menu(
+    context: &mut ComponentContext,
+    id: &str,
+    cards: Vec<CardDetails>,
+    mut menu: impl FnMut(&mut ComponentContext, usize),
+) {
+    let card_width = UI_CARD_WIDTH * context.widget.ui_scale;
+    let spacing = context.ui.style().ItemSpacing.x * 2.0;
+
+    let columns = ((context.ui.get_content_region_avail().x + spacing) / (card_width + spacing))
+        .floor()
+        .max(1.0) as i32;
+
+    context
+        .ui
+        .table_config(id, columns)
+        .flags(TableFlags::SizingFixedSame)
+        .with(|| {
+            for (index, card) in cards.into_iter().enumerate() {
+                context.ui.table_next_column();
+
+                context.ui.with_push(ItemId(index), || {
+                    if build(context, card) {
+                        context.ui.open_popup("card_menu");
+                    }
+
+                    context.ui.popup_config("card_menu").with(|| {
+                        menu(context, index);
+                    });
+                });
+            }
         });
 }
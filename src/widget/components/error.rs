@@ -3,11 +3,11 @@ use std::fmt::{Display, Debug};
 use easy_imgui::ColorId;
 use tracing::info;
 
-use crate::{dummy, widget::icons::set::UI_ICON_ERROR};
+use crate::{api::error::SpotifyAPIError, dummy, widget::icons::set::UI_ICON_ERROR};
 
 use super::ComponentContext;
 
-pub fn build<E: Debug>(context: &mut ComponentContext, error: E) {
+fn build_message(context: &mut ComponentContext, title: &str, message: &str) {
     let icon_size = context.ui.get_font_size() * 2.0;
 
     dummy!(context, 10.0);
@@ -21,14 +21,28 @@ pub fn build<E: Debug>(context: &mut ComponentContext, error: E) {
     );
 
     context.ui.with_push(context.widget.font_h2, || {
-        context.ui.set_cursor_pos_x((context.ui.get_window_width() / 2.0) - (context.ui.calc_text_size("Something went wrong.").x / 2.0));
-        context.ui.text("Something went wrong.");
+        context.ui.set_cursor_pos_x((context.ui.get_window_width() / 2.0) - (context.ui.calc_text_size(title).x / 2.0));
+        context.ui.text(title);
     });
 
     dummy!(context);
 
-    let error_text = &format!("{:#?}", error);
+    context.ui.set_cursor_pos_x((context.ui.get_window_width() / 2.0) - (context.ui.calc_text_size(message).x / 2.0));
+    context.ui.text(message);
+}
 
-    context.ui.set_cursor_pos_x((context.ui.get_window_width() / 2.0) - (context.ui.calc_text_size(error_text).x / 2.0));
-    context.ui.text(error_text);
+pub fn build<E: Debug>(context: &mut ComponentContext, error: E) {
+    build_message(context, "Something went wrong.", &format!("{:#?}", error));
+}
+
+// Being rate limited isn't really a failure, so don't present it like one.
+pub fn build_api(context: &mut ComponentContext, error: &SpotifyAPIError) {
+    match error {
+        SpotifyAPIError::RateLimited(retry_after) => build_message(
+            context,
+            "Spotify is busy right now.",
+            &format!("Rate limited, try again in {}s.", retry_after.as_secs().max(1))
+        ),
+        err => build(context, err),
+    }
 }
\ No newline at end of file
@@ -5,7 +5,7 @@ use easy_imgui::{
 use tokio::runtime::Handle;
 
 use crate::{
-    api::models::user::{UserImpl as _}, commands::AppCommand, constants::UI_ROUTE_PREFERENCES, widget::icons::set::UI_ICON_USER
+    api::models::user::{UserImpl as _}, commands::AppCommand, constants::{UI_ROUTE_PREFERENCES, UI_SEEK_STEP_MS, UI_VOLUME_STEP}, widget::icons::set::UI_ICON_USER
 };
 
 use super::ComponentContext;
@@ -91,11 +91,17 @@ pub fn build(context: &mut ComponentContext) {
                             .shortcut("Ctrl+A")
                             .build();
                         context.ui.separator();
-                        context
+                        if context
                             .ui
                             .menu_item_config("Filter")
                             .shortcut("Ctrl+F")
-                            .build();
+                            .build()
+                        {
+                            context.widget.send_command(
+                                context.event_loop,
+                                AppCommand::Filter
+                            );
+                        }
                         context.ui.separator();
                         if context
                             .ui
@@ -142,54 +148,103 @@ pub fn build(context: &mut ComponentContext) {
                         }
                     });
                     context.ui.menu_config("Playback").with(|| {
-                        context
+                        let shuffle = context.widget.state.lock().unwrap().queue.is_shuffled();
+
+                        if context
                             .ui
                             .menu_item_config("Play / Pause")
                             .shortcut("Space")
-                            .build();
+                            .enabled(context.playback.is_connected())
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::PlayPause);
+                        }
                         context.ui.separator();
-                        context
+                        if context
                             .ui
                             .menu_item_config("Next Track")
                             .shortcut("Ctrl+Right Arrow")
-                            .build();
-                        context
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::NextTrack);
+                        }
+                        if context
                             .ui
                             .menu_item_config("Previous Track")
                             .shortcut("Ctrl+Left Arrow")
-                            .build();
-                        context
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::PreviousTrack);
+                        }
+                        if context
                             .ui
                             .menu_item_config("Seek Forward")
                             .shortcut("Shift+Right Arrow")
-                            .build();
-                        context
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::SeekBy(UI_SEEK_STEP_MS));
+                        }
+                        if context
                             .ui
                             .menu_item_config("Seek Backward")
                             .shortcut("Shift+Left Arrow")
-                            .build();
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::SeekBy(-UI_SEEK_STEP_MS));
+                        }
                         context.ui.separator();
-                        context
+                        if context
                             .ui
                             .menu_item_config("Shuffle")
                             .shortcut("Ctrl+S")
-                            .build();
-                        context
+                            .selected(shuffle)
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::ToggleShuffle);
+                        }
+                        if context
                             .ui
                             .menu_item_config("Repeat")
                             .shortcut("Ctrl+R")
-                            .build();
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::CycleRepeat);
+                        }
                         context.ui.separator();
-                        context
+                        if context
                             .ui
                             .menu_item_config("Volume Up")
                             .shortcut("Ctrl+Up Arrow")
-                            .build();
-                        context
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::VolumeBy(UI_VOLUME_STEP));
+                        }
+                        if context
                             .ui
                             .menu_item_config("Volume Down")
                             .shortcut("Ctrl+Down Arrow")
-                            .build();
+                            .build()
+                        {
+                            context
+                                .widget
+                                .send_command(context.event_loop, AppCommand::VolumeBy(-UI_VOLUME_STEP));
+                        }
                     });
                     context.ui.menu_config("Help").with(|| {
                         context.ui.menu_item_config("Third-party licences").build();
@@ -201,6 +256,18 @@ pub fn build(context: &mut ComponentContext) {
                         }
                     });
 
+                    if let Some(retry_in) = context.api.scheduler.rate_limited_for() {
+                        context.ui.with_push(
+                            (ColorId::Text, context.ui.style().color(ColorId::TextDisabled)),
+                            || {
+                                context.ui.text(&format!(
+                                    "Rate limited, retrying in {}s",
+                                    retry_in.as_secs() + 1
+                                ));
+                            }
+                        );
+                    }
+
                     context.ui.table_next_column();
 
                     let menuitem_start_x = context.ui.get_cursor_pos_x();
@@ -238,11 +305,50 @@ pub fn build(context: &mut ComponentContext) {
                                 );
                             }
 
-                            context
+                            context.ui.separator();
+
+                            let current_account = context.widget.accounts.current_id();
+
+                            let other_accounts = context.widget.accounts
+                                .list()
+                                .iter()
+                                .filter(|a| Some(&a.id) != current_account.as_ref())
+                                .map(|a| (a.id.clone(), a.name()))
+                                .collect::<Vec<(String, String)>>();
+
+                            context.ui.menu_config("Switch Account").with(|| {
+                                for (account_id, name) in other_accounts {
+                                    if context.ui.menu_item_config(format!("{}##{}", name, account_id)).build() {
+                                        context.widget.send_command(
+                                            context.event_loop,
+                                            AppCommand::SwitchAccount(account_id),
+                                        );
+                                    }
+                                }
+
+                                if context.widget.accounts.list().len() > 1 {
+                                    context.ui.separator();
+                                }
+
+                                if context.ui.menu_item_config("Add Account...").build() {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::AddAccount,
+                                    );
+                                }
+                            });
+
+                            if context
                                 .ui
                                 .menu_item_config("Log Out")
                                 .shortcut("Ctrl+Shift+W")
-                                .build();
+                                .build()
+                            {
+                                context.widget.send_command(
+                                    context.event_loop,
+                                    AppCommand::LogOut,
+                                );
+                            }
                         });
 
                         context.ui.set_cursor_pos(vec2(
@@ -4,15 +4,17 @@ pub mod modals;
 pub mod panes;
 pub mod player;
 pub mod sidebar;
+pub mod track_menu;
 pub mod error;
 pub mod async_image;
+pub mod track_table;
 
 use std::sync::{Arc, Mutex};
 
 use easy_imgui::Ui;
 use easy_imgui_window::winit::event_loop::EventLoopProxy;
 
-use crate::{api::SpotifyAPI, event::AppEvent, state::State, App};
+use crate::{api::SpotifyAPI, event::AppEvent, playback::SpotifyPlayback, state::State, App};
 
 use super::Widget;
 
@@ -38,5 +40,6 @@ pub struct ComponentContext<'a> {
     pub widget: &'a mut Widget,
     pub event_loop: &'a EventLoopProxy<AppEvent>,
     pub ui: &'a Ui<App>,
-    pub api: Arc<SpotifyAPI>
+    pub api: Arc<SpotifyAPI>,
+    pub playback: Arc<SpotifyPlayback>
 }
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use easy_imgui::{vec2, ColorId};
+
+use crate::{
+    api::models::album::AlbumDetails,
+    commands::AppCommand,
+    create_pane, dummy,
+    state::album::WidgetStateAlbum,
+    utils::format_duration_long_ms,
+    widget::components::{
+        self, async_image,
+        track_table::{self, TrackTableColumns},
+        ComponentContext,
+    },
+};
+
+fn build_header(context: &mut ComponentContext, details: &AlbumDetails) {
+    let album = &details.album;
+
+    let art_size = 200.0 * context.widget.ui_scale;
+
+    async_image::build(context, async_image::pick_image(&album.images, art_size), art_size);
+
+    context.ui.same_line();
+
+    context.ui.with_group(|| {
+        context.ui.with_push(context.widget.font_small, || {
+            context.ui.text(&format!("{:?}", album.album_type));
+        });
+
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text(&album.name);
+        });
+
+        context.ui.with_push(context.widget.font_bold, || {
+            context.ui.text(
+                &album.artists
+                    .iter()
+                    .map(|a| a.name.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        });
+
+        let mut info = vec![album.release_date.clone()];
+
+        if let Some(label) = &album.label {
+            info.push(label.clone());
+        }
+
+        info.push(format!(
+            "{} {}, {}",
+            details.tracks.len(),
+            if details.tracks.len() == 1 { "song" } else { "songs" },
+            format_duration_long_ms(details.duration_ms())
+        ));
+
+        context.ui.with_push(
+            (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+            || {
+                context.ui.text(&info.join(" - "));
+            },
+        );
+
+        dummy!(context);
+
+        context.ui.with_disabled(details.tracks.is_empty(), || {
+            if context.ui.button(&format!("Play##album_play_{}", details.id())) {
+                context.widget.send_command(
+                    context.event_loop,
+                    AppCommand::PlayContext(Some(details.playback_context()), details.tracks.clone(), 0)
+                );
+            }
+        });
+    });
+}
+
+pub fn build(context: &mut ComponentContext, pane: WidgetStateAlbum) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let mut open = true;
+
+    let title = pane.title();
+
+    create_pane!(context.ui, context.widget, title.as_str(), open, {
+        match &pane.album {
+            Some(Ok(details)) => {
+                build_header(context, details);
+
+                context.ui.dummy(vec2(0.0, 16.0 * context.widget.ui_scale));
+
+                track_table::build(
+                    context,
+                    &format!("album_tracks_{}", pane.id),
+                    &details.tracks,
+                    Some(details.playback_context()),
+                    TrackTableColumns {
+                        track_number: true,
+                        ..Default::default()
+                    },
+                );
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    if !open {
+        state_arc.lock().unwrap().albums.retain(|a| a.id != pane.id);
+    }
+}
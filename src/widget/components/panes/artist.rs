@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use easy_imgui::{vec2, ColorId};
+
+use crate::{
+    api::models::{
+        artist::ArtistDetails,
+        recommendations::BrowseRecommendationItem,
+    },
+    commands::AppCommand,
+    create_pane, dummy,
+    state::artist::WidgetStateArtist,
+    widget::components::{
+        self, async_image, card,
+        track_table::{self, TrackTableColumns},
+        ComponentContext,
+    },
+};
+
+fn build_header(context: &mut ComponentContext, pane_id: &str, details: &ArtistDetails) {
+    let artist = &details.artist;
+
+    let art_size = 200.0 * context.widget.ui_scale;
+
+    async_image::build_rounded(
+        context,
+        async_image::pick_image(&artist.images, art_size),
+        art_size,
+        art_size / 2.0
+    );
+
+    context.ui.same_line();
+
+    context.ui.with_group(|| {
+        context.ui.with_push(context.widget.font_small, || {
+            context.ui.text("Artist");
+        });
+
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text(&artist.name);
+        });
+
+        let mut info = vec![format!("{} followers", artist.followers.total)];
+
+        if !artist.genres.is_empty() {
+            info.push(artist.genres.join(", "));
+        }
+
+        context.ui.with_push(
+            (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+            || {
+                context.ui.text(&info.join(" - "));
+            },
+        );
+
+        dummy!(context);
+
+        context.ui.with_disabled(details.top_tracks.is_empty(), || {
+            if context.ui.button(&format!("Play##artist_play_{}", pane_id)) {
+                context.widget.send_command(
+                    context.event_loop,
+                    AppCommand::PlayContext(None, details.top_tracks.clone(), 0)
+                );
+            }
+        });
+
+        context.ui.same_line();
+
+        let follow_label = if details.following { "Unfollow" } else { "Follow" };
+
+        if context.ui.button(&format!("{}##artist_follow_{}", follow_label, pane_id)) {
+            context.widget.send_command(
+                context.event_loop,
+                AppCommand::FollowArtist(pane_id.to_string(), !details.following)
+            );
+        }
+    });
+}
+
+fn build_discography(context: &mut ComponentContext, pane_id: &str, details: &ArtistDetails) {
+    for section in &details.discography {
+        context.ui.with_push(context.widget.font_h3, || {
+            context.ui.text(section.group.title());
+        });
+
+        dummy!(context);
+
+        let items = section.albums.items
+            .iter()
+            .cloned()
+            .map(BrowseRecommendationItem::Album)
+            .collect::<Vec<BrowseRecommendationItem>>();
+
+        card::build_grid(context, &format!("artist_{}_{}", pane_id, section.group.include_group()), &items);
+
+        if section.has_more() {
+            context.ui.with_disabled(section.fetching_more, || {
+                let label = if section.fetching_more { "Loading..." } else { "Show more" };
+
+                if context.ui.button(&format!("{}##artist_more_{}_{}", label, pane_id, section.group.include_group())) {
+                    context.widget.send_command(
+                        context.event_loop,
+                        AppCommand::ArtistAlbumsMore(pane_id.to_string(), section.group)
+                    );
+                }
+            });
+        }
+
+        dummy!(context, 4.0);
+    }
+}
+
+pub fn build(context: &mut ComponentContext, pane: WidgetStateArtist) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let mut open = true;
+
+    let title = pane.title();
+
+    create_pane!(context.ui, context.widget, title.as_str(), open, {
+        match &pane.artist {
+            Some(Ok(details)) => {
+                build_header(context, &pane.id, details);
+
+                context.ui.dummy(vec2(0.0, 16.0 * context.widget.ui_scale));
+
+                if !details.top_tracks.is_empty() {
+                    context.ui.with_push(context.widget.font_h3, || {
+                        context.ui.text("Popular");
+                    });
+
+                    dummy!(context);
+
+                    track_table::build(
+                        context,
+                        &format!("artist_top_tracks_{}", pane.id),
+                        &details.top_tracks,
+                        None,
+                        TrackTableColumns {
+                            album: true,
+                            ..Default::default()
+                        },
+                    );
+
+                    dummy!(context, 4.0);
+                }
+
+                build_discography(context, &pane.id, details);
+
+                if !details.related.is_empty() {
+                    context.ui.with_push(context.widget.font_h3, || {
+                        context.ui.text("Fans also like");
+                    });
+
+                    dummy!(context);
+
+                    let items = details.related
+                        .iter()
+                        .cloned()
+                        .map(BrowseRecommendationItem::Artist)
+                        .collect::<Vec<BrowseRecommendationItem>>();
+
+                    card::build_grid(context, &format!("artist_related_{}", pane.id), &items);
+                }
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    if !open {
+        state_arc.lock().unwrap().artists.retain(|a| a.id != pane.id);
+    }
+}
@@ -1,14 +1,14 @@
 use std::sync::Arc;
 
 use crate::{
-    api::{error::SpotifyAPIError, models::{recommendations::{BrowseRecommendationItem, BrowseRecommendationSections}, user::UserImpl as _}}, constants::UI_ROUTE_DEFAULT, create_pane, dummy, widget::components::{
-        self, card::{self, CardDetails}, ComponentContext
+    commands::AppCommand,
+    api::{error::SpotifyAPIError, models::{recommendations::BrowseRecommendationSections, user::UserImpl as _}}, constants::UI_ROUTE_DEFAULT, create_pane, dummy, widget::components::{
+        self, card, ComponentContext
     }
 };
 use chrono::{offset::Local, Timelike};
 use easy_imgui::{easy_imgui_sys::ImGui_ClearDragDrop, ImGuiID, TableColumnFlags, TableFlags};
 use tokio::runtime::Handle;
-use tracing::error;
 
 pub fn build(context: &mut ComponentContext) {
     let mut open = context.widget.state.lock().unwrap().home_visible;
@@ -17,12 +17,13 @@ pub fn build(context: &mut ComponentContext) {
 
     let last_auth_error = context.api.get_state_error();
 
-    let recommendations_sections = &context.widget.state
+    let recommendations = context.widget.state
         .lock()
         .unwrap()
         .recommendations
-        .clone()
-        .and_then(|r| r.sections);
+        .clone();
+
+    let recommendations_sections = recommendations.as_ref().and_then(|r| r.sections.as_ref());
 
     create_pane!(context.ui, context.widget, UI_ROUTE_DEFAULT, open, {
         if let Some(profile) = context.api.state().and_then(|s| s.profile) {
@@ -38,7 +39,7 @@ pub fn build(context: &mut ComponentContext) {
 
             match recommendations_sections {
                 Some(BrowseRecommendationSections::Sections(Ok(sections))) => {
-                    for section in sections {
+                    for (section_index, section) in sections.iter().enumerate() {
                         context.ui.with_push(context.widget.font_h3, || {
                             context.ui.text(&section.title);
                         });
@@ -47,41 +48,39 @@ pub fn build(context: &mut ComponentContext) {
                             context.ui.text(desc);
                         }
 
-                        for item in &section.items {
-                            match item {
-                                BrowseRecommendationItem::Playlist(playlist) => {
-                                    context.ui.with_push(context.widget.font_h4, || {
-                                        context.ui.text(playlist.name.as_str());
-                                    });
+                        dummy!(context);
+
+                        card::build_grid(context, &format!("section_{}", section_index), &section.items);
 
-                                    if let Some(desc) = &playlist.description {
-                                        context.ui.text(desc);
-                                    }
-                                },
-                                r => {
-                                    error!("Unhandled recommendation item {:?}", r);
-                                    panic!("Panic here.");
+                        if section.has_more() {
+                            context.ui.with_disabled(section.fetching_more, || {
+                                let label = if section.fetching_more { "Loading..." } else { "Show more" };
+
+                                if context.ui.button(&format!("{}##section_more_{}", label, section_index)) {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::RecommendationsMore(section_index)
+                                    );
                                 }
-                            }
+                            });
                         }
+
+                        dummy!(context);
                     }
                 },
                 Some(BrowseRecommendationSections::Sections(Err(err))) => {
-                    components::error::build(
-                        context,
-                        Box::new(err.clone())
-                    );
+                    components::error::build_api(context, err);
                 },
                 _ => {
                     context.ui.text("Loading...");
                 }
             }
         } else {
-            components::error::build(
+            components::error::build_api(
                 context,
-                Box::new(last_auth_error.unwrap_or(
+                &last_auth_error.unwrap_or(
                     SpotifyAPIError::Unknown("Failed to establish connection to Spotify.")
-                ))
+                )
             );
         }
     });
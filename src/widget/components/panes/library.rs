@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use easy_imgui::{ColorId, ImGuiID, InputTextFlags, SelectableFlags, TableColumnFlags, TableFlags, TableRowFlags};
+use rspotify_model::{FullArtist, Id as _, SavedAlbum};
+
+use crate::{
+    api::models::{entity::SpotifyEntity, recommendations::BrowseRecommendationItem},
+    commands::AppCommand,
+    constants::{UI_ROUTE_YOUR_ALBUMS, UI_ROUTE_YOUR_ARTISTS},
+    create_pane, dummy,
+    state::{
+        library::{LibraryItem, LibraryLayout, WidgetStateLibraryView},
+        State,
+    },
+    widget::components::{
+        self,
+        card::{self, CardDetails},
+        ComponentContext,
+    },
+};
+
+// Filter, layout and sort controls shared by Your Albums and Your Artists.
+// `view_mut` finds the pane's state again to write changes back to.
+fn build_toolbar<T: LibraryItem>(
+    context: &mut ComponentContext,
+    id: &str,
+    view: &WidgetStateLibraryView<T>,
+    view_mut: fn(&mut State) -> &mut WidgetStateLibraryView<T>,
+) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let mut filter = view.filter.clone();
+    let mut layout = view.layout;
+    let mut sort = view.sort;
+
+    context.ui
+        .input_text_hint_config(&format!("##{}_filter", id), "Filter", &mut filter)
+        .flags(InputTextFlags::EscapeClearsAll)
+        .build();
+
+    for (label, option) in [("Grid", LibraryLayout::Grid), ("List", LibraryLayout::List)] {
+        context.ui.same_line();
+
+        if context.ui.radio_button_config(&format!("{}##{}_layout", label, id), layout == option).build() {
+            layout = option;
+        }
+    }
+
+    // Nothing to choose between when there's only the one way to sort.
+    if T::SORTS.len() > 1 {
+        context.ui.text("Sort by");
+
+        for &option in T::SORTS {
+            context.ui.same_line();
+
+            if context.ui.radio_button_config(&format!("{}##{}_sort", option.title(), id), sort == option).build() {
+                sort = option;
+            }
+        }
+    }
+
+    let mut state = state_arc.lock().unwrap();
+    let view_mut = view_mut(&mut state);
+
+    view_mut.layout = layout;
+    view_mut.set_filter(filter);
+    view_mut.set_sort(sort);
+}
+
+fn build_summary<T: LibraryItem>(context: &mut ComponentContext, view: &WidgetStateLibraryView<T>, items: &[T], noun: (&str, &str)) {
+    let shown = if view.view.len() == items.len() {
+        format!("{} {}", items.len(), if items.len() == 1 { noun.0 } else { noun.1 })
+    } else {
+        format!("{} of {} {}", view.view.len(), items.len(), noun.1)
+    };
+
+    context.ui.with_push(
+        (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+        || {
+            context.ui.text(&format!("{}{}", shown, if view.fetching { ", loading more..." } else { "" }));
+        },
+    );
+}
+
+fn album_entity(saved: &SavedAlbum) -> SpotifyEntity {
+    SpotifyEntity::Album {
+        id: saved.id(),
+        name: saved.album.name.clone(),
+    }
+}
+
+fn artist_entity(artist: &FullArtist) -> SpotifyEntity {
+    SpotifyEntity::Artist {
+        id: artist.id.id().to_string(),
+        name: artist.name.clone(),
+    }
+}
+
+fn build_album_menu(context: &mut ComponentContext, saved: &SavedAlbum) {
+    if context.ui.menu_item_config("Play").build() {
+        context.widget.send_command(context.event_loop, AppCommand::PlayEntity(album_entity(saved)));
+    }
+
+    if context.ui.menu_item_config("Remove from Your Albums").build() {
+        context.widget.send_command(context.event_loop, AppCommand::SaveAlbum(saved.id(), false));
+    }
+}
+
+fn build_artist_menu(context: &mut ComponentContext, artist: &FullArtist) {
+    if context.ui.menu_item_config("Play").build() {
+        context.widget.send_command(context.event_loop, AppCommand::PlayEntity(artist_entity(artist)));
+    }
+
+    if context.ui.menu_item_config("Unfollow").build() {
+        context.widget.send_command(context.event_loop, AppCommand::FollowArtist(artist.id(), false));
+    }
+}
+
+fn build_album_list(context: &mut ComponentContext, view: &WidgetStateLibraryView<SavedAlbum>, items: &[SavedAlbum]) {
+    context
+        .ui
+        .table_config("your_albums_list", 4)
+        .flags(TableFlags::RowBg | TableFlags::PadOuterX | TableFlags::ScrollY)
+        .with(|| {
+            context.ui.table_setup_column("Title", TableColumnFlags::WidthStretch, 2.0, ImGuiID::default());
+            context.ui.table_setup_column("Artist", TableColumnFlags::WidthStretch, 1.5, ImGuiID::default());
+            context.ui.table_setup_column("Released", TableColumnFlags::WidthFixed, 0.0, ImGuiID::default());
+            context.ui.table_setup_column("Date added", TableColumnFlags::WidthFixed, 0.0, ImGuiID::default());
+
+            context.ui.table_setup_scroll_freeze(0, 1);
+            context.ui.table_headers_row();
+
+            context.ui.list_clipper(view.view.len(), -1.0, &[], |view_index| {
+                let saved = &items[view.view[view_index]];
+
+                context.ui.table_next_row(TableRowFlags::None, 0.0);
+                context.ui.table_next_column();
+
+                let clicked = context.ui
+                    .selectable_config(&format!("{}##your_albums_{}", saved.album.name, saved.id()))
+                    .flags(SelectableFlags::SpanAllColumns)
+                    .build();
+
+                context.ui.popup_context_item_config().with(|| {
+                    build_album_menu(context, saved);
+                });
+
+                context.ui.table_next_column();
+                context.ui.text(&saved.artist());
+
+                context.ui.table_next_column();
+                context.ui.text(&saved.album.release_date);
+
+                context.ui.table_next_column();
+                context.ui.text(&saved.added_at.format("%Y-%m-%d").to_string());
+
+                if clicked {
+                    context.widget.send_command(context.event_loop, AppCommand::Open(album_entity(saved)));
+                }
+            });
+        });
+}
+
+fn build_artist_list(context: &mut ComponentContext, view: &WidgetStateLibraryView<FullArtist>, items: &[FullArtist]) {
+    context
+        .ui
+        .table_config("your_artists_list", 3)
+        .flags(TableFlags::RowBg | TableFlags::PadOuterX | TableFlags::ScrollY)
+        .with(|| {
+            context.ui.table_setup_column("Name", TableColumnFlags::WidthStretch, 1.5, ImGuiID::default());
+            context.ui.table_setup_column("Genres", TableColumnFlags::WidthStretch, 2.0, ImGuiID::default());
+            context.ui.table_setup_column("Followers", TableColumnFlags::WidthFixed, 0.0, ImGuiID::default());
+
+            context.ui.table_setup_scroll_freeze(0, 1);
+            context.ui.table_headers_row();
+
+            context.ui.list_clipper(view.view.len(), -1.0, &[], |view_index| {
+                let artist = &items[view.view[view_index]];
+
+                context.ui.table_next_row(TableRowFlags::None, 0.0);
+                context.ui.table_next_column();
+
+                let clicked = context.ui
+                    .selectable_config(&format!("{}##your_artists_{}", artist.name, artist.id()))
+                    .flags(SelectableFlags::SpanAllColumns)
+                    .build();
+
+                context.ui.popup_context_item_config().with(|| {
+                    build_artist_menu(context, artist);
+                });
+
+                context.ui.table_next_column();
+                context.ui.text(&artist.genres.join(", "));
+
+                context.ui.table_next_column();
+                context.ui.text(&artist.followers.total.to_string());
+
+                if clicked {
+                    context.widget.send_command(context.event_loop, AppCommand::Open(artist_entity(artist)));
+                }
+            });
+        });
+}
+
+pub fn build_albums(context: &mut ComponentContext, view: WidgetStateLibraryView<SavedAlbum>) {
+    let mut open = view.visible;
+
+    create_pane!(context.ui, context.widget, UI_ROUTE_YOUR_ALBUMS, open, {
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text("Your Albums");
+        });
+
+        match &view.items {
+            Some(Ok(items)) => {
+                build_summary(context, &view, items, ("album", "albums"));
+
+                dummy!(context);
+
+                build_toolbar(context, "your_albums", &view, |state| &mut state.your_albums);
+
+                dummy!(context);
+
+                match view.layout {
+                    LibraryLayout::Grid => {
+                        let cards = view.view
+                            .iter()
+                            .map(|&i| CardDetails::from_album(&items[i].album))
+                            .collect::<Vec<CardDetails>>();
+
+                        card::build_grid_with_menu(context, "your_albums_grid", cards, |context, index| {
+                            build_album_menu(context, &items[view.view[index]]);
+                        });
+                    },
+                    LibraryLayout::List => build_album_list(context, &view, items),
+                }
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    context.widget.state.lock().unwrap().your_albums.visible = open;
+}
+
+pub fn build_artists(context: &mut ComponentContext, view: WidgetStateLibraryView<FullArtist>) {
+    let mut open = view.visible;
+
+    create_pane!(context.ui, context.widget, UI_ROUTE_YOUR_ARTISTS, open, {
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text("Your Artists");
+        });
+
+        match &view.items {
+            Some(Ok(items)) => {
+                build_summary(context, &view, items, ("artist", "artists"));
+
+                dummy!(context);
+
+                build_toolbar(context, "your_artists", &view, |state| &mut state.your_artists);
+
+                dummy!(context);
+
+                match view.layout {
+                    LibraryLayout::Grid => {
+                        let cards = view.view
+                            .iter()
+                            .map(|&i| CardDetails::from_recommendation(&BrowseRecommendationItem::Artist(items[i].clone())))
+                            .collect::<Vec<CardDetails>>();
+
+                        card::build_grid_with_menu(context, "your_artists_grid", cards, |context, index| {
+                            build_artist_menu(context, &items[view.view[index]]);
+                        });
+                    },
+                    LibraryLayout::List => build_artist_list(context, &view, items),
+                }
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    context.widget.state.lock().unwrap().your_artists.visible = open;
+}
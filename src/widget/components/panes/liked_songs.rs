@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use easy_imgui::{vec2, ColorId};
+
+use crate::{
+    commands::AppCommand,
+    constants::UI_ROUTE_LIKED_SONGS,
+    create_pane, dummy,
+    state::queue::PlaybackContext,
+    widget::components::{
+        self,
+        track_table::{self, TrackTableColumns},
+        ComponentContext,
+    },
+};
+
+pub fn build(context: &mut ComponentContext) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let (mut open, tracks, fetching) = {
+        let state = state_arc.lock().unwrap();
+
+        (state.liked_songs.visible, state.liked_songs.tracks.clone(), state.liked_songs.fetching)
+    };
+
+    create_pane!(context.ui, context.widget, UI_ROUTE_LIKED_SONGS, open, {
+        context.ui.with_push(context.widget.font_small, || {
+            context.ui.text("Playlist");
+        });
+
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text("Liked Songs");
+        });
+
+        match &tracks {
+            Some(Ok(tracks)) => {
+                context.ui.with_push(
+                    (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+                    || {
+                        context.ui.text(&format!(
+                            "{} {}{}",
+                            tracks.len(),
+                            if tracks.len() == 1 { "song" } else { "songs" },
+                            if fetching { ", loading more..." } else { "" }
+                        ));
+                    },
+                );
+
+                dummy!(context);
+
+                context.ui.with_disabled(tracks.is_empty(), || {
+                    if context.ui.button("Play##liked_songs_play") {
+                        context.widget.send_command(
+                            context.event_loop,
+                            AppCommand::PlayContext(Some(PlaybackContext::LikedSongs), tracks.to_vec(), 0)
+                        );
+                    }
+                });
+
+                context.ui.dummy(vec2(0.0, 16.0 * context.widget.ui_scale));
+
+                if tracks.is_empty() && !fetching {
+                    context.ui.text("Songs you like will appear here. Save them with the heart in the player bar.");
+                }
+
+                track_table::build(
+                    context,
+                    "liked_songs_tracks",
+                    tracks,
+                    Some(PlaybackContext::LikedSongs),
+                    TrackTableColumns {
+                        album: true,
+                        ..Default::default()
+                    },
+                );
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    state_arc.lock().unwrap().liked_songs.visible = open;
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use easy_imgui::{vec2, ColorId};
+
+use crate::{
+    commands::AppCommand,
+    constants::{UI_ROUTE_PREFERENCES, UI_ROUTE_YOUR_FILES},
+    create_pane, dummy,
+    state::queue::PlaybackContext,
+    widget::components::{
+        track_table::{self, TrackTableColumns},
+        ComponentContext,
+    },
+};
+
+pub fn build(context: &mut ComponentContext) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let (mut open, tracks, scanning) = {
+        let state = state_arc.lock().unwrap();
+
+        (state.local_files.visible, state.local_files.tracks.clone(), state.local_files.scanning)
+    };
+
+    let has_folders = context.widget.preferences
+        .get()
+        .and_then(|p| p.local_files)
+        .map(|l| !l.folders().is_empty())
+        .unwrap_or(false);
+
+    create_pane!(context.ui, context.widget, UI_ROUTE_YOUR_FILES, open, {
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text("Your Files");
+        });
+
+        match &tracks {
+            Some(tracks) => {
+                context.ui.with_push(
+                    (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+                    || {
+                        context.ui.text(&format!(
+                            "{} {}{}",
+                            tracks.len(),
+                            if tracks.len() == 1 { "song" } else { "songs" },
+                            if scanning { ", checking for changes..." } else { "" }
+                        ));
+                    },
+                );
+
+                dummy!(context);
+
+                context.ui.with_disabled(tracks.is_empty(), || {
+                    if context.ui.button("Play##your_files_play") {
+                        context.widget.send_command(
+                            context.event_loop,
+                            AppCommand::PlayContext(Some(PlaybackContext::LocalFiles), tracks.to_vec(), 0)
+                        );
+                    }
+                });
+
+                context.ui.same_line();
+
+                context.ui.with_disabled(scanning || !has_folders, || {
+                    if context.ui.button("Rescan##your_files_rescan") {
+                        context.widget.send_command(context.event_loop, AppCommand::ScanLocalFiles);
+                    }
+                });
+
+                context.ui.dummy(vec2(0.0, 16.0 * context.widget.ui_scale));
+
+                if !has_folders {
+                    context.ui.text("Music on this computer will appear here. Choose which folders to look in from Preferences.");
+
+                    if context.ui.button("Open Preferences##your_files_preferences") {
+                        context.widget.send_command(context.event_loop, AppCommand::Navigate(UI_ROUTE_PREFERENCES));
+                    }
+                } else if tracks.is_empty() && !scanning {
+                    context.ui.text("No music found. Your Files reads MP3, FLAC, Ogg Vorbis and WAV files.");
+                }
+
+                track_table::build(
+                    context,
+                    "your_files_tracks",
+                    tracks,
+                    Some(PlaybackContext::LocalFiles),
+                    TrackTableColumns {
+                        album: true,
+                        ..Default::default()
+                    },
+                );
+            },
+            None => context.ui.text("Looking for music..."),
+        }
+    });
+
+    state_arc.lock().unwrap().local_files.visible = open;
+}
@@ -3,8 +3,16 @@ use tokio::runtime::Handle;
 
 use super::ComponentContext;
 
+pub mod album;
+pub mod artist;
 pub mod home;
+pub mod library;
+pub mod liked_songs;
+pub mod local_files;
+pub mod playlist;
 pub mod preferences;
+pub mod queue;
+pub mod recently_played;
 pub mod search;
 
 #[macro_export]
@@ -35,6 +43,10 @@ pub fn build(context: &mut ComponentContext) {
     let is_prefs_visible = context.widget.state.lock().unwrap().preferences.visible;
     let is_home_visible = context.widget.state.lock().unwrap().home_visible;
     let is_search_visible = context.widget.state.lock().unwrap().search.visible;
+    let is_queue_visible = context.widget.state.lock().unwrap().queue_visible;
+    let is_liked_songs_visible = context.widget.state.lock().unwrap().liked_songs.visible;
+    let is_recently_played_visible = context.widget.state.lock().unwrap().recently_played.visible;
+    let is_local_files_visible = context.widget.state.lock().unwrap().local_files.visible;
 
     if is_prefs_visible {
         preferences::build(context);
@@ -47,4 +59,67 @@ pub fn build(context: &mut ComponentContext) {
     if is_search_visible {
         search::build(context);
     }
+
+    if is_queue_visible {
+        queue::build(context);
+    }
+
+    if is_liked_songs_visible {
+        liked_songs::build(context);
+    }
+
+    if is_recently_played_visible {
+        recently_played::build(context);
+    }
+
+    if is_local_files_visible {
+        local_files::build(context);
+    }
+
+    let (your_albums, your_artists) = {
+        let mut state = context.widget.state.lock().unwrap();
+
+        state.your_albums.update_view();
+        state.your_artists.update_view();
+
+        (
+            state.your_albums.visible.then(|| state.your_albums.clone()),
+            state.your_artists.visible.then(|| state.your_artists.clone()),
+        )
+    };
+
+    if let Some(your_albums) = your_albums {
+        library::build_albums(context, your_albums);
+    }
+
+    if let Some(your_artists) = your_artists {
+        library::build_artists(context, your_artists);
+    }
+
+    let albums = context.widget.state.lock().unwrap().albums.clone();
+
+    for album in albums {
+        album::build(context, album);
+    }
+
+    let artists = context.widget.state.lock().unwrap().artists.clone();
+
+    for artist in artists {
+        artist::build(context, artist);
+    }
+
+    let playlists = context.widget.state
+        .lock()
+        .unwrap()
+        .playlists
+        .iter_mut()
+        .map(|p| {
+            p.update_view();
+            p.clone()
+        })
+        .collect::<Vec<_>>();
+
+    for playlist in playlists {
+        playlist::build(context, playlist);
+    }
 }
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use easy_imgui::{
+    vec2, ColorId, FocusedFlags, ImGuiID, InputTextFlags, SelectableFlags, SortDirection,
+    TableColumnFlags, TableFlags, TableRowFlags,
+};
+use rspotify_model::{FullPlaylist, FullTrack, PlaylistItem};
+
+use crate::{
+    api::models::{
+        playlists::{playlist_item_added_by, playlist_item_track},
+        user::UserImpl as _,
+    },
+    commands::AppCommand,
+    constants::UI_ACCENT_COLOR,
+    create_pane, dummy,
+    state::{
+        playlist::{PlaylistSort, PlaylistSortColumn, WidgetStatePlaylist},
+        queue::PlaybackContext,
+    },
+    utils::format_duration_ms,
+    widget::{
+        components::{self, async_image, track_menu, ComponentContext},
+        icons::set::UI_ICON_MEDIA_EXPLICIT,
+    },
+};
+
+fn build_header(context: &mut ComponentContext, pane: &WidgetStatePlaylist, playlist: &FullPlaylist) {
+    let art_size = 200.0 * context.widget.ui_scale;
+
+    async_image::build(context, async_image::pick_image(&playlist.images, art_size), art_size);
+
+    context.ui.same_line();
+
+    context.ui.with_group(|| {
+        context.ui.with_push(context.widget.font_small, || {
+            context.ui.text(if playlist.collaborative { "Collaborative playlist" } else { "Playlist" });
+        });
+
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text(&playlist.name);
+        });
+
+        if let Some(description) = playlist.description.as_ref().filter(|d| !d.trim().is_empty()) {
+            context.ui.text(description);
+        }
+
+        let loaded = if pane.fetching {
+            format!("{} of {} songs", pane.items.len(), playlist.tracks.total)
+        } else {
+            format!("{} songs", pane.items.len())
+        };
+
+        context.ui.with_push(
+            (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+            || {
+                context.ui.text(&format!(
+                    "{} - {} followers - {}",
+                    playlist.owner.name(),
+                    playlist.followers.total,
+                    loaded
+                ));
+            },
+        );
+
+        dummy!(context);
+
+        context.ui.with_disabled(pane.view.is_empty(), || {
+            if context.ui.button(&format!("Play##playlist_play_{}", pane.id)) {
+                play_from(context, pane, 0);
+            }
+        });
+    });
+}
+
+// Plays what's showing, in the order it's showing, starting from row `view_index`.
+fn play_from(context: &mut ComponentContext, pane: &WidgetStatePlaylist, view_index: usize) {
+    let mut start_index = 0;
+
+    let tracks = pane.view
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &item_index)| {
+            let track = playlist_item_track(&pane.items[item_index])?;
+
+            if i < view_index {
+                start_index += 1;
+            }
+
+            Some(track.clone())
+        })
+        .collect::<Vec<FullTrack>>();
+
+    context.widget.send_command(
+        context.event_loop,
+        AppCommand::PlayContext(
+            Some(PlaybackContext::Playlist { id: pane.id.clone(), name: pane.name.clone() }),
+            tracks,
+            start_index
+        )
+    );
+}
+
+fn build_row(
+    context: &mut ComponentContext,
+    pane: &WidgetStatePlaylist,
+    view_index: usize,
+    item_index: usize,
+    item: &PlaylistItem,
+    playing_id: &Option<rspotify_model::TrackId<'static>>,
+) {
+    let track = playlist_item_track(item);
+
+    let playing = track.is_some_and(|t| t.id.is_some() && &t.id == playing_id);
+
+    let text_color = match track {
+        Some(_) if playing => UI_ACCENT_COLOR,
+        Some(_) => context.ui.style().color(ColorId::Text),
+        // Episodes and the like, which we can't play.
+        None => context.ui.style().color_alpha(ColorId::Text, 0.5),
+    };
+
+    context.ui.table_next_row(TableRowFlags::None, 0.0);
+
+    context.ui.with_push((ColorId::Text, text_color), || {
+        context.ui.table_next_column();
+
+        let clicked = context.ui
+            .selectable_config(&format!("{}##playlist_{}_{}", item_index + 1, pane.id, item_index))
+            .flags(SelectableFlags::SpanAllColumns)
+            .build();
+
+        if let Some(track) = track {
+            track_menu::build(context, track);
+        }
+
+        context.ui.table_next_column();
+
+        if track.is_some_and(|t| t.explicit) {
+            context.widget.create_icon(
+                context.ui,
+                UI_ICON_MEDIA_EXPLICIT,
+                14.0,
+                context.ui.style().color_alpha(ColorId::Text, 0.7),
+            );
+            context.ui.same_line();
+        }
+
+        context.ui.text(track.map(|t| t.name.as_str()).unwrap_or("Unavailable"));
+
+        context.ui.table_next_column();
+        context.ui.text(
+            &track
+                .map(|t| t.artists
+                    .iter()
+                    .map(|a| a.name.clone())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                )
+                .unwrap_or_default()
+        );
+
+        context.ui.table_next_column();
+        context.ui.text(track.map(|t| t.album.name.as_str()).unwrap_or(""));
+
+        context.ui.table_next_column();
+        context.ui.text(
+            &item.added_at
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        );
+
+        context.ui.table_next_column();
+        context.ui.text(&playlist_item_added_by(item));
+
+        context.ui.table_next_column();
+        context.ui.text(
+            &track
+                .map(|t| format_duration_ms(t.duration.num_milliseconds().max(0) as u32))
+                .unwrap_or_default()
+        );
+
+        if clicked && track.is_some() {
+            play_from(context, pane, view_index);
+        }
+    });
+}
+
+fn build_table(context: &mut ComponentContext, pane: &WidgetStatePlaylist) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let playing_id = context.playback.status().track.and_then(|t| t.id);
+
+    context
+        .ui
+        .table_config(&format!("playlist_items_{}", pane.id), PlaylistSortColumn::ALL.len() as i32)
+        .flags(
+            TableFlags::RowBg
+                | TableFlags::PadOuterX
+                | TableFlags::Sortable
+                | TableFlags::SortTristate
+                | TableFlags::ScrollY
+                | TableFlags::Resizable
+        )
+        .with(|| {
+            for column in PlaylistSortColumn::ALL {
+                let (flags, weight) = match column {
+                    PlaylistSortColumn::Index | PlaylistSortColumn::Duration => (TableColumnFlags::WidthFixed, 0.0),
+                    PlaylistSortColumn::Title => (TableColumnFlags::WidthStretch, 2.0),
+                    _ => (TableColumnFlags::WidthStretch, 1.0),
+                };
+
+                context.ui.table_setup_column(column.title(), flags, weight, column as ImGuiID);
+            }
+
+            context.ui.table_setup_scroll_freeze(0, 1);
+            context.ui.table_headers_row();
+
+            context.ui.table_with_sort_specs(|specs| {
+                let sort = specs.first().and_then(|spec| {
+                    PlaylistSortColumn::ALL
+                        .into_iter()
+                        .find(|c| *c as ImGuiID == spec.id())
+                        .map(|column| PlaylistSort {
+                            column,
+                            descending: spec.sort_direction() == SortDirection::Descending,
+                        })
+                });
+
+                if let Some(pane) = state_arc.lock().unwrap().playlists.iter_mut().find(|p| p.id == pane.id) {
+                    pane.set_sort(sort);
+                }
+
+                true
+            });
+
+            // Only build the rows that are actually on screen.
+            context.ui.list_clipper(pane.view.len(), -1.0, &[], |view_index| {
+                let item_index = pane.view[view_index];
+
+                build_row(context, pane, view_index, item_index, &pane.items[item_index], &playing_id);
+            });
+        });
+}
+
+pub fn build(context: &mut ComponentContext, pane: WidgetStatePlaylist) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let mut open = true;
+
+    let title = pane.title();
+
+    create_pane!(context.ui, context.widget, title.as_str(), open, {
+        if context.ui.is_window_focused(FocusedFlags::RootAndChildWindows) {
+            state_arc.lock().unwrap().focused_playlist = Some(pane.id.clone());
+        }
+
+        match &pane.playlist {
+            Some(Ok(playlist)) => {
+                build_header(context, &pane, playlist);
+
+                context.ui.dummy(vec2(0.0, 16.0 * context.widget.ui_scale));
+
+                let mut filter = pane.filter.clone();
+
+                if pane.focus_filter {
+                    context.ui.set_keyboard_focus_here(0);
+                }
+
+                context.ui
+                    .input_text_hint_config(
+                        &format!("##playlist_filter_{}", pane.id),
+                        "Filter",
+                        &mut filter,
+                    )
+                    .flags(InputTextFlags::EscapeClearsAll)
+                    .build();
+
+                if let Some(pane_mut) = state_arc.lock().unwrap().playlists.iter_mut().find(|p| p.id == pane.id) {
+                    pane_mut.focus_filter = false;
+                    pane_mut.set_filter(filter);
+                }
+
+                dummy!(context);
+
+                build_table(context, &pane);
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    if !open {
+        let mut state = state_arc.lock().unwrap();
+
+        state.playlists.retain(|p| p.id != pane.id);
+
+        if state.focused_playlist.as_ref() == Some(&pane.id) {
+            state.focused_playlist = None;
+        }
+    }
+}
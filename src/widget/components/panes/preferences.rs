@@ -1,4 +1,4 @@
-use std::{borrow::{Borrow, BorrowMut}, sync::{mpsc::channel, Arc}, thread};
+use std::{borrow::{Borrow, BorrowMut}, path::PathBuf, sync::{mpsc::channel, Arc}, thread};
 
 use easy_imgui::{
     Color, ColorId, ImGuiID, InputTextFlags, TableColumnFlags, TableFlags, TextureId
@@ -8,7 +8,7 @@ use tokio::runtime::Handle;
 use tracing::{error, info};
 
 use crate::{
-    api::models::user::{UserImpl as _}, constants::{UI_ROUTE_PREFERENCES}, create_pane, event::AppEvent, state, utils::{color_darken, color_light_dark, color_lighten, color_lighten_darken}, widget::theme::UITheme, App
+    api::{models::user::{UserImpl as _}, providers::oauth2::SpotifyAPIOAuthConfig}, state::preferences::WidgetStatePreferencesOAuth, commands::AppCommand, constants::{UI_ROUTE_PREFERENCES}, create_pane, event::AppEvent, state, utils::{color_darken, color_light_dark, color_lighten, color_lighten_darken}, widget::theme::UITheme, App
 };
 
 use super::ComponentContext;
@@ -139,13 +139,19 @@ pub fn build(context: &mut ComponentContext) {
                                     .build();
 
                                 if context.ui.button("Log out") {
-                                    let api_arc = Arc::clone(&context.api);
-
-                                    tokio::task::spawn(async move {
-                                        api_arc.logout().await;
-                                    });
+                                    context.widget.send_command(context.event_loop, AppCommand::LogOut);
                                 }
-                            } else if context.ui.button(if context.api.is_logging_in() { "Logging in..." } else { "Login" }) {
+                            }
+                        });
+
+                        // Stays clickable while we wait on the browser, so a login
+                        // that was abandoned there can be started over.
+                        if context.api.state().and_then(|s| s.profile).is_none() {
+                            if context.api.is_authenticating() {
+                                context.ui.text("Waiting for you to log in through your browser...");
+                            }
+
+                            if context.ui.button(if context.api.is_authenticating() { "Login again" } else { "Login" }) {
                                 let api_arc = Arc::clone(&context.api);
                                 let event_loop_arc = Arc::new(context.event_loop.clone());
 
@@ -156,7 +162,82 @@ pub fn build(context: &mut ComponentContext) {
                                     api_arc.login(Some(true)).await;
                                 });
                             }
+                        }
+                    }
+                );
+
+                gen_pref_section!(
+                    context.ui,
+                    context.widget,
+                    "Spotify app",
+                    Some("Log in through your own registered Spotify app. Takes effect the next time you log in."),
+                    {
+                        let mut draft = context.widget.state.lock().unwrap()
+                            .preferences
+                            .oauth_draft
+                            .clone()
+                            .unwrap_or_else(|| WidgetStatePreferencesOAuth::from_config(&context.api.oauth_config.lock().unwrap()));
+
+                        context.ui.with_push(context.widget.font_bold, || {
+                            context.ui.text("Client ID");
+                        });
+                        context.ui.input_text_config("##oauth_client_id", &mut draft.client_id).build();
+
+                        context.ui.with_push(context.widget.font_bold, || {
+                            context.ui.text("Redirect URI");
+                        });
+                        context.ui.text("http://");
+                        context.ui.same_line();
+                        context.ui.set_next_item_width(200.0);
+                        context.ui.input_text_config("##oauth_redirect_host", &mut draft.redirect_host).build();
+                        context.ui.same_line();
+                        context.ui.text(":");
+                        context.ui.same_line();
+                        context.ui.set_next_item_width(80.0);
+                        context.ui.input_text_config("##oauth_redirect_port", &mut draft.redirect_port).build();
+                        context.ui.same_line();
+                        context.ui.text("/login");
+
+                        context.ui.checkbox("Try other ports when this one is taken##oauth_fallback", &mut draft.fallback);
+
+                        if draft.fallback {
+                            context.ui.set_next_item_width(80.0);
+                            context.ui.input_text_config("##oauth_fallback_start", &mut draft.fallback_start).build();
+                            context.ui.same_line();
+                            context.ui.text("to");
+                            context.ui.same_line();
+                            context.ui.set_next_item_width(80.0);
+                            context.ui.input_text_config("##oauth_fallback_end", &mut draft.fallback_end).build();
+                        }
+
+                        let config = draft.to_config();
+
+                        if let Err(message) = &config {
+                            let current_theme = context.widget.state.lock().unwrap().current_theme;
+
+                            context.ui.with_push((ColorId::Text, color_lighten_darken(current_theme, Color::RED, 0.3)), || {
+                                context.ui.text(message);
+                            });
+                        }
+
+                        let is_saved = config.as_ref().ok() == Some(&*context.api.oauth_config.lock().unwrap());
+
+                        context.ui.with_disabled(is_saved || config.is_err(), || {
+                            if context.ui.button("Save##oauth_save") {
+                                if let Ok(config) = config.clone() {
+                                    context
+                                        .event_loop
+                                        .send_event(AppEvent::SetOAuthConfig(config))
+                                        .ok();
+                                }
+                            }
                         });
+                        context.ui.same_line();
+                        if context.ui.button("Reset to defaults##oauth_reset") {
+                            draft = WidgetStatePreferencesOAuth::from_config(&SpotifyAPIOAuthConfig::default());
+                        }
+
+                        context.widget.state.lock().unwrap().preferences.oauth_draft = Some(draft);
                     }
                 );
 
@@ -221,6 +302,91 @@ pub fn build(context: &mut ComponentContext) {
                     }
                 );
 
+                gen_pref_section!(
+                    context.ui,
+                    context.widget,
+                    "Storage",
+                    Some("Spotify responses are kept on disk, so the app opens quicker and works offline."),
+                    {
+                        const CACHE_LIMITS_MB: [u64; 4] = [100, 250, 500, 1024];
+
+                        let mut max_size_mb = context.api.cache.max_bytes() / 1024 / 1024;
+
+                        context.ui.text(&format!(
+                            "Using {:.1} MB",
+                            context.api.cache.size_bytes() as f64 / 1024.0 / 1024.0
+                        ));
+
+                        if context.ui.combo(
+                            "Cache size limit",
+                            CACHE_LIMITS_MB,
+                            |v| match v {
+                                1024 => "1 GB".to_string(),
+                                v => format!("{} MB", v),
+                            },
+                            &mut max_size_mb,
+                        ) {
+                            context.widget.send_command(context.event_loop, AppCommand::SetCacheLimit(max_size_mb));
+                        }
+
+                        if context.ui.button("Clear cache") {
+                            context.widget.send_command(context.event_loop, AppCommand::ClearCache);
+                        }
+                    }
+                );
+
+                gen_pref_section!(
+                    context.ui,
+                    context.widget,
+                    "Local files",
+                    Some("Folders of your own music to show in Your Files, subfolders included."),
+                    {
+                        let folders = context.widget.preferences
+                            .get()
+                            .and_then(|p| p.local_files)
+                            .map(|l| l.folders())
+                            .unwrap_or_default();
+
+                        let (mut draft, scanning) = {
+                            let state = context.widget.state.lock().unwrap();
+
+                            (state.preferences.local_folder_draft.clone(), state.local_files.scanning)
+                        };
+
+                        for (index, folder) in folders.iter().enumerate() {
+                            context.ui.text(&folder.display().to_string());
+                            context.ui.same_line();
+                            if context.ui.button(&format!("Remove##local_folder_remove_{}", index)) {
+                                context.widget.send_command(context.event_loop, AppCommand::RemoveLocalFolder(folder.clone()));
+                            }
+                        }
+
+                        context.ui.set_next_item_width(400.0);
+                        context.ui
+                            .input_text_hint_config("##local_folder_draft", "Folder path", &mut draft)
+                            .build();
+                        context.ui.same_line();
+                        context.ui.with_disabled(draft.trim().is_empty(), || {
+                            if context.ui.button("Add##local_folder_add") {
+                                context.widget.send_command(
+                                    context.event_loop,
+                                    AppCommand::AddLocalFolder(PathBuf::from(draft.trim()))
+                                );
+
+                                draft.clear();
+                            }
+                        });
+
+                        context.ui.with_disabled(scanning || folders.is_empty(), || {
+                            if context.ui.button(if scanning { "Scanning...##local_files_rescan" } else { "Rescan##local_files_rescan" }) {
+                                context.widget.send_command(context.event_loop, AppCommand::ScanLocalFiles);
+                            }
+                        });
+
+                        context.widget.state.lock().unwrap().preferences.local_folder_draft = draft;
+                    }
+                );
+
                 gen_pref_section!(context.ui, context.widget, "Audio quality", None, {
                     #[derive(Clone, Copy, PartialEq)]
                     enum StreamingQuality {
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use easy_imgui::{ColorId, MouseButton};
+use rspotify_model::FullTrack;
+
+use crate::{
+    commands::AppCommand, constants::UI_ROUTE_QUEUE, create_pane, dummy, utils::format_duration_ms, widget::components::ComponentContext
+};
+
+fn track_label(track: &FullTrack) -> String {
+    format!(
+        "{} - {}",
+        track.name,
+        track.artists.iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+fn track_duration(track: &FullTrack) -> String {
+    format_duration_ms(track.duration.num_milliseconds().max(0) as u32)
+}
+
+pub fn build(context: &mut ComponentContext) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let mut open = state_arc.lock().unwrap().queue_visible;
+
+    // Cheap, the context's tracks are shared rather than copied.
+    let queue = state_arc.lock().unwrap().queue.clone();
+
+    let font_h3 = context.widget.font_h3;
+
+    create_pane!(context.ui, context.widget, UI_ROUTE_QUEUE, open, {
+        context.ui.with_push(font_h3, || {
+            context.ui.text("Now playing");
+        });
+        dummy!(context);
+
+        match queue.now_playing() {
+            Some(track) => {
+                context.ui.with_push(context.widget.font_bold, || {
+                    context.ui.text(&track_label(track));
+                });
+            },
+            None => context.ui.text("Nothing is playing."),
+        }
+
+        dummy!(context, 4.0);
+
+        context.ui.with_push(font_h3, || {
+            context.ui.text("Next in queue");
+        });
+
+        if !queue.user_queue.is_empty() {
+            context.ui.same_line();
+
+            if context.ui.button("Clear queue") {
+                state_arc.lock().unwrap().queue.clear_queue();
+            }
+        }
+
+        dummy!(context);
+
+        if queue.user_queue.is_empty() {
+            context.ui.with_push((
+                ColorId::Text,
+                context.ui.style().color_alpha(ColorId::Text, 0.7)
+            ), || {
+                context.ui.text("Right-click a song and choose \"Add to queue\" to add it here.");
+            });
+        }
+
+        for (index, track) in queue.user_queue.iter().enumerate() {
+            if context.ui.button(&format!("Remove##queue_remove_{}", index)) {
+                state_arc.lock().unwrap().queue.remove_from_queue(index);
+            }
+
+            context.ui.same_line();
+
+            context.ui.selectable_config(&format!("{}  ({})##queue_{}", track_label(track), track_duration(track), index)).build();
+
+            // Drag a row over its neighbours to reorder the queue,
+            // this follows the "simple reorder" example from the imgui demo.
+            if context.ui.is_item_active() && !context.ui.is_item_hovered() {
+                let drag_delta = context.ui.get_mouse_drag_delta(MouseButton::Left, -1.0).y;
+
+                let target = if drag_delta < 0.0 {
+                    index.checked_sub(1)
+                } else {
+                    Some(index + 1)
+                };
+
+                if let Some(target) = target.filter(|&t| t < queue.user_queue.len()) {
+                    state_arc.lock().unwrap().queue.move_in_queue(index, target);
+                    context.ui.reset_mouse_drag_delta(MouseButton::Left);
+                }
+            }
+        }
+
+        dummy!(context, 4.0);
+
+        let upcoming = queue.upcoming_indices();
+
+        context.ui.with_push(font_h3, || {
+            match &queue.context {
+                Some(playback_context) => context.ui.text(&format!("Next from: {}", playback_context.name())),
+                None => context.ui.text("Next up"),
+            }
+        });
+        dummy!(context);
+
+        if upcoming.is_empty() {
+            context.ui.with_push((
+                ColorId::Text,
+                context.ui.style().color_alpha(ColorId::Text, 0.7)
+            ), || {
+                context.ui.text("Nothing else to play.");
+            });
+        }
+
+        // A whole playlist can be up next, only build the rows on screen.
+        context.ui.list_clipper(upcoming.len(), -1.0, &[], |row| {
+            // The row's own place in the context, the same track can be in it twice.
+            let start_index = upcoming[row];
+            let track = &queue.context_tracks()[start_index];
+
+            if context.ui.selectable_config(&format!("{}  ({})##upcoming_{}", track_label(track), track_duration(track), row)).build() {
+                context.widget.send_command(
+                    context.event_loop,
+                    AppCommand::PlayContext(queue.context.clone(), queue.context_tracks().to_vec(), start_index)
+                );
+            }
+        });
+    });
+
+    state_arc.lock().unwrap().queue_visible = open;
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use chrono::Local;
+use easy_imgui::{ColorId, ImGuiID, SelectableFlags, TableColumnFlags, TableFlags, TableRowFlags};
+use rspotify_model::PlayHistory;
+
+use crate::{
+    commands::AppCommand,
+    constants::{UI_ACCENT_COLOR, UI_ROUTE_RECENTLY_PLAYED},
+    create_pane, dummy,
+    state::recently_played::RecentlyPlayedDay,
+    widget::components::{self, track_menu, ComponentContext},
+};
+
+fn build_row(context: &mut ComponentContext, day: &RecentlyPlayedDay, index: usize, play: &PlayHistory) {
+    let track = &play.track;
+
+    context.ui.table_next_column();
+
+    let clicked = context.ui
+        .selectable_config(&format!(
+            "{}##recently_played_{}_{}",
+            play.played_at.with_timezone(&Local).format("%H:%M"),
+            day.date,
+            index
+        ))
+        .flags(SelectableFlags::SpanAllColumns)
+        .build();
+
+    track_menu::build(context, track);
+
+    context.ui.table_next_column();
+    context.ui.text(&track.name);
+
+    context.ui.table_next_column();
+    context.ui.text(
+        &track.artists
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+
+    context.ui.table_next_column();
+    context.ui.text(&track.album.name);
+
+    // Replays just this track, the rest of the day isn't a context worth queueing.
+    if clicked {
+        context.widget.send_command(context.event_loop, AppCommand::PlayTrack(track.clone()));
+    }
+}
+
+fn build_day(context: &mut ComponentContext, day: &RecentlyPlayedDay) {
+    let playing_id = context.playback.status().track.and_then(|t| t.id);
+
+    context.ui.with_push(context.widget.font_h3, || {
+        context.ui.text(&day.title());
+    });
+
+    context
+        .ui
+        .table_config(&format!("recently_played_{}", day.date), 4)
+        .flags(TableFlags::RowBg | TableFlags::PadOuterX)
+        .with(|| {
+            context.ui.table_setup_column("Time", TableColumnFlags::WidthFixed, 0.0, ImGuiID::default());
+            context.ui.table_setup_column("Title", TableColumnFlags::WidthStretch, 2.0, ImGuiID::default());
+            context.ui.table_setup_column("Artists", TableColumnFlags::WidthStretch, 1.5, ImGuiID::default());
+            context.ui.table_setup_column("Album", TableColumnFlags::WidthStretch, 1.5, ImGuiID::default());
+
+            let plays = day.plays();
+
+            context.ui.list_clipper(plays.len(), -1.0, &[], |index| {
+                let play = &plays[index];
+
+                let text_color = if play.track.id.is_some() && play.track.id == playing_id {
+                    UI_ACCENT_COLOR
+                } else {
+                    context.ui.style().color(ColorId::Text)
+                };
+
+                context.ui.table_next_row(TableRowFlags::None, 0.0);
+
+                context.ui.with_push((ColorId::Text, text_color), || {
+                    build_row(context, day, index, play);
+                });
+            });
+        });
+
+    dummy!(context);
+}
+
+pub fn build(context: &mut ComponentContext) {
+    let state_arc = Arc::clone(&context.widget.state);
+
+    let (mut open, days, fetching) = {
+        let state = state_arc.lock().unwrap();
+
+        (state.recently_played.visible, state.recently_played.days.clone(), state.recently_played.fetching)
+    };
+
+    create_pane!(context.ui, context.widget, UI_ROUTE_RECENTLY_PLAYED, open, {
+        context.ui.with_push(context.widget.font_h1, || {
+            context.ui.text("Recently Played");
+        });
+
+        match &days {
+            Some(Ok(days)) => {
+                let plays = days.iter().map(|d| d.plays().len()).sum::<usize>();
+
+                context.ui.with_push(
+                    (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+                    || {
+                        context.ui.text(&format!(
+                            "{} {}{}",
+                            plays,
+                            if plays == 1 { "play" } else { "plays" },
+                            if fetching { ", checking for more..." } else { "" }
+                        ));
+                    },
+                );
+
+                dummy!(context);
+
+                if days.is_empty() {
+                    context.ui.text("Nothing yet. Tracks you play, here or on any other device, will show up here.");
+                }
+
+                for day in days.iter() {
+                    build_day(context, day);
+                }
+            },
+            Some(Err(err)) => components::error::build_api(context, err),
+            None => context.ui.text("Loading..."),
+        }
+    });
+
+    state_arc.lock().unwrap().recently_played.visible = open;
+}
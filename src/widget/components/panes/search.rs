@@ -1,9 +1,9 @@
 use std::{borrow::BorrowMut, sync::Arc};
 
 use crate::{
-    api::models::user::UserImpl, commands::AppCommand, constants::UI_ROUTE_SEARCH, create_pane, dummy, state::search::WidgetStateSearchResults, widget::{
+    api::models::{entity::SpotifyEntity, search::{SearchResults, SearchResultsKind}, user::UserImpl}, commands::AppCommand, constants::UI_ROUTE_SEARCH, create_pane, dummy, state::{queue::PlaybackContext, search::WidgetStateSearchResults}, widget::{
         components::{
-            self, card::{self, CardDetails}, ComponentContext
+            self, card::{self, CardDetails}, track_menu, ComponentContext
         },
         icons::set::UI_ICON_SEARCH,
     }
@@ -12,7 +12,27 @@ use easy_imgui::{
     ColorId, ImGuiID, InputTextFlags, TableColumnFlags,
     TableFlags,
 };
-use rspotify_model::SearchResult;
+use rspotify_model::{Id as _, SearchResult};
+
+fn build_show_more(context: &ComponentContext, results: &SearchResults, kind: SearchResultsKind, fetching_more: Option<SearchResultsKind>) {
+    if !results.has_more(kind) {
+        return;
+    }
+
+    dummy!(context);
+
+    let fetching = fetching_more == Some(kind);
+
+    context.ui.with_disabled(fetching_more.is_some(), || {
+        let label = if fetching { "Loading..." } else { "Show more" };
+
+        if context.ui.button(&format!("{}##show_more_{:?}", label, kind)) {
+            context.widget.send_command(context.event_loop, AppCommand::SearchMore(kind));
+        }
+    });
+
+    dummy!(context, 2.0);
+}
 
 pub fn build(context: &mut ComponentContext) {
     let state_arc = Arc::clone(&context.widget.state);
@@ -134,6 +154,8 @@ pub fn build(context: &mut ComponentContext) {
 
                 dummy!(context);
 
+                let fetching_more = state_arc.lock().unwrap().search.fetching_more;
+
                 match &state_arc
                     .lock()
                     .unwrap()
@@ -152,8 +174,8 @@ pub fn build(context: &mut ComponentContext) {
                                     dummy!(context);
                                 });
 
-                                for track in &tracks.items {
-                                    context.ui.text(&format!(
+                                for (index, track) in tracks.items.iter().enumerate() {
+                                    let label = format!(
                                         "{} by {} on {}",
                                         track.name,
                                         track.artists.iter()
@@ -161,8 +183,27 @@ pub fn build(context: &mut ComponentContext) {
                                             .collect::<Vec<String>>()
                                             .join(", "),
                                         track.album.name
-                                    ));
+                                    );
+
+                                    let clicked = context.ui.selectable_config(&label).build();
+
+                                    track_menu::build(context, track);
+
+                                    if clicked {
+                                        context.widget.send_command(
+                                            context.event_loop,
+                                            AppCommand::PlayContext(
+                                                Some(PlaybackContext::SearchResults {
+                                                    query: search_value.clone()
+                                                }),
+                                                tracks.items.clone(),
+                                                index
+                                            )
+                                        );
+                                    }
                                 }
+
+                                build_show_more(context, results, SearchResultsKind::Tracks, fetching_more);
                             }
     
                             if let Some(artists) = &results.artists {
@@ -171,9 +212,19 @@ pub fn build(context: &mut ComponentContext) {
                                     dummy!(context);
                                 });
 
-                                for artist in &artists.items {
-                                    context.ui.text(&artist.name);
+                                for (index, artist) in artists.items.iter().enumerate() {
+                                    if context.ui.selectable_config(&format!("{}##search_artist_{}", artist.name, index)).build() {
+                                        context.widget.send_command(
+                                            context.event_loop,
+                                            AppCommand::Open(SpotifyEntity::Artist {
+                                                id: artist.id.id().to_string(),
+                                                name: artist.name.clone(),
+                                            })
+                                        );
+                                    }
                                 }
+
+                                build_show_more(context, results, SearchResultsKind::Artists, fetching_more);
                             }
     
                             if let Some(albums) = &results.albums {
@@ -182,16 +233,31 @@ pub fn build(context: &mut ComponentContext) {
                                     dummy!(context);
                                 });
 
-                                for album in &albums.items {
-                                    context.ui.text(&format!(
-                                        "{} created by {}",
+                                for (index, album) in albums.items.iter().enumerate() {
+                                    let label = format!(
+                                        "{} created by {}##search_album_{}",
                                         album.name,
                                         album.artists.iter()
                                             .map(|a| a.name.clone())
                                             .collect::<Vec<String>>()
                                             .join(", "),
-                                    ));
+                                        index
+                                    );
+
+                                    if context.ui.selectable_config(&label).build() {
+                                        if let Some(id) = &album.id {
+                                            context.widget.send_command(
+                                                context.event_loop,
+                                                AppCommand::Open(SpotifyEntity::Album {
+                                                    id: id.id().to_string(),
+                                                    name: album.name.clone(),
+                                                })
+                                            );
+                                        }
+                                    }
                                 }
+
+                                build_show_more(context, results, SearchResultsKind::Albums, fetching_more);
                             }
     
                             if let Some(playlists) = &results.playlists {
@@ -207,11 +273,13 @@ pub fn build(context: &mut ComponentContext) {
                                         playlist.owner.name()
                                     ));
                                 }
+
+                                build_show_more(context, results, SearchResultsKind::Playlists, fetching_more);
                             }
                         }
                     },
                     WidgetStateSearchResults::Fetched(Err(err)) => {
-                        components::error::build(context, Box::new(err.clone()));
+                        components::error::build_api(context, err);
                     },
                     WidgetStateSearchResults::None => {
                         context.ui.with_push(font_h3, || {
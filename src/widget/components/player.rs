@@ -1,19 +1,27 @@
 
 use easy_imgui::{
-    vec2, Color, ColorId, ImGuiID, StyleValue, StyleVar, TableColumnFlags, TableFlags, WindowFlags,
+    vec2, Color, ColorId, ImGuiID, MouseButton, StyleValue, StyleVar, TableColumnFlags, TableFlags, WindowFlags,
 };
+use rspotify_model::{FullTrack, Id as _, Image, PlayableItem};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::UI_PLAYER_BAR_HEIGHT,
+    commands::AppCommand,
+    playback::{connect, status::SpotifyPlaybackStatus},
+    constants::{UI_ACCENT_COLOR, UI_PLAYER_BAR_HEIGHT},
     imgui_additions::{self, sidebar::ViewportSidebarDirection},
-    widget::icons::set::{
-        UI_ICON_HEART, UI_ICON_MEDIA_NEXT, UI_ICON_MEDIA_PAUSE, UI_ICON_MEDIA_PREVIOUS,
-        UI_ICON_REPEAT, UI_ICON_SHUFFLE,
+    state::queue::RepeatMode,
+    utils::format_duration_ms,
+    widget::icons::{
+        set::{
+            UI_ICON_HEART, UI_ICON_MEDIA_NEXT, UI_ICON_MEDIA_PAUSE, UI_ICON_MEDIA_PLAY, UI_ICON_MEDIA_PREVIOUS,
+            UI_ICON_REPEAT, UI_ICON_REPEAT_SONG, UI_ICON_SHUFFLE, UI_ICON_VOLUME,
+        },
+        IconOffset,
     },
 };
 
-use super::ComponentContext;
+use super::{async_image, ComponentContext};
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 pub enum PlayerArea {
@@ -27,7 +35,79 @@ pub enum PlayerPosition {
     Bottom,
 }
 
+/// Cover art for whatever's playing, here or on the remote device we're controlling.
+pub fn now_playing_images(context: &ComponentContext) -> Vec<Image> {
+    if let Some(remote_playback) = &context.widget.state.lock().unwrap().devices.remote_playback {
+        if let Some(PlayableItem::Track(track)) = &remote_playback.item {
+            return track.album.images.clone();
+        }
+    }
+
+    context.playback.status().album_images()
+}
+
+/// The track playing here or on the remote device we're controlling, if any.
+pub fn now_playing_track(context: &ComponentContext) -> Option<FullTrack> {
+    if let Some(remote_playback) = &context.widget.state.lock().unwrap().devices.remote_playback {
+        if let Some(PlayableItem::Track(track)) = &remote_playback.item {
+            return Some(track.clone());
+        }
+    }
+
+    context.playback.status().track
+}
+
+/// Playback status of the remote device we're controlling, or of this one.
+pub fn now_playing_status(context: &ComponentContext) -> SpotifyPlaybackStatus {
+    let remote_status = context.widget.state.lock().unwrap().devices.remote_status();
+
+    remote_status.unwrap_or_else(|| context.playback.status())
+}
+
+fn build_album_art(context: &mut ComponentContext) {
+    let size = (UI_PLAYER_BAR_HEIGHT - 24.0) * context.widget.ui_scale;
+
+    let images = now_playing_images(context);
+
+    context.ui.table_next_column();
+    context.ui.set_cursor_pos_y(((UI_PLAYER_BAR_HEIGHT * context.widget.ui_scale) - size) / 2.0);
+
+    async_image::build(context, async_image::pick_image(&images, size), size);
+}
+
 fn build_track_info(context: &mut ComponentContext) {
+    let status = context.playback.status();
+
+    let mut title = status.title().unwrap_or("Not playing".to_string());
+    let mut artists = status.artists().unwrap_or_default();
+
+    // Show what the remote device is playing while we're controlling it.
+    if let Some(remote_playback) = &context.widget.state.lock().unwrap().devices.remote_playback {
+        if let Some(PlayableItem::Track(track)) = &remote_playback.item {
+            title = track.name.clone();
+            artists = track.artists
+                .iter()
+                .map(|a| a.name.clone())
+                .collect::<Vec<String>>()
+                .join(", ");
+        }
+    }
+
+    let remote_device_name = context.widget.state.lock().unwrap()
+        .devices
+        .remote_device
+        .as_ref()
+        .map(|d| d.name.clone());
+
+    let connect_controller = if context.playback.connect.is_active() {
+        Some(format!(
+            "Playing from {}",
+            context.playback.connect.controller().unwrap_or("a remote device".to_string())
+        ))
+    } else {
+        remote_device_name.map(|name| format!("Playing on {}", name))
+    };
+
     let centre_y = ((UI_PLAYER_BAR_HEIGHT / 2.0) - 20.0) * context.widget.ui_scale;
 
     context.ui.table_next_column();
@@ -42,7 +122,7 @@ fn build_track_info(context: &mut ComponentContext) {
                     (ColorId::Text, context.ui.style().color(ColorId::Text)),
                 ),
                 || {
-                    context.ui.text("Making Plans For Nigel");
+                    context.ui.text(&title);
                 },
             );
 
@@ -55,9 +135,18 @@ fn build_track_info(context: &mut ComponentContext) {
                     ),
                 ),
                 || {
-                    context.ui.text("XTC");
+                    context.ui.text(&artists);
                 },
             );
+
+            if let Some(controller) = &connect_controller {
+                context.ui.with_push(
+                    (context.widget.font_small, (ColorId::Text, UI_ACCENT_COLOR)),
+                    || {
+                        context.ui.text(controller);
+                    },
+                );
+            }
         },
     );
 
@@ -67,38 +156,103 @@ fn build_track_info(context: &mut ComponentContext) {
     context.ui.set_cursor_pos_y(
         (UI_PLAYER_BAR_HEIGHT * context.widget.ui_scale) / 2.0 - context.ui.get_item_rect_size().y,
     );
-    context.widget.create_icon_button(
-        context.ui,
-        UI_ICON_HEART,
-        16.0,
-        context.ui.style().color(ColorId::Text),
-        Color::TRANSPARENT,
-        Color::TRANSPARENT,
-        Color::TRANSPARENT,
-        0.0,
-    );
+    build_save_button(context);
+}
+
+fn build_save_button(context: &mut ComponentContext) {
+    let track = now_playing_track(context);
+    let track_id = track.as_ref().and_then(|t| t.id.as_ref()).map(|id| id.id().to_string());
+
+    let saved = match &track_id {
+        Some(track_id) => {
+            let mut state = context.widget.state.lock().unwrap();
+
+            // Ask Spotify the first time we see a track, the heart stays
+            // unfilled until it answers.
+            if !state.liked_songs.saved.contains_key(track_id) {
+                state.liked_songs.saved.insert(track_id.clone(), None);
+
+                context.widget.send_command(context.event_loop, AppCommand::CheckSavedTrack(track_id.clone()));
+            }
+
+            state.liked_songs.is_saved(track_id)
+        },
+        None => None,
+    };
+
+    let color = if saved == Some(true) {
+        UI_ACCENT_COLOR
+    } else {
+        context.ui.style().color(ColorId::Text)
+    };
+
+    let mut clicked = false;
+
+    context.ui.with_disabled(saved.is_none(), || {
+        clicked = context.widget.create_icon_button(
+            context.ui,
+            UI_ICON_HEART,
+            16.0,
+            color,
+            Color::TRANSPARENT,
+            Color::TRANSPARENT,
+            Color::TRANSPARENT,
+            0.0,
+        );
+    });
+
+    if context.ui.is_item_hovered() {
+        context.ui.set_tooltip(if saved == Some(true) { "Remove from Liked Songs" } else { "Save to Liked Songs" });
+    }
+
+    if let (true, Some(track), Some(saved)) = (clicked, track, saved) {
+        context.widget.send_command(context.event_loop, AppCommand::SaveTrack(track, !saved));
+    }
+}
+
+fn repeat_icon(repeat: RepeatMode) -> IconOffset {
+    match repeat {
+        RepeatMode::Track => UI_ICON_REPEAT_SONG,
+        _ => UI_ICON_REPEAT,
+    }
 }
 
 fn build_media_controls(context: &mut ComponentContext) {
+    let status = now_playing_status(context);
+
+    let controlling_remote = context.widget.state.lock().unwrap().devices.remote_device.is_some();
+
+    let (shuffle, repeat) = {
+        let queue = &context.widget.state.lock().unwrap().queue;
+
+        (queue.is_shuffled(), queue.repeat)
+    };
+
     context.ui.table_next_column();
 
     context
         .ui
         .set_cursor_pos_y((context.ui.get_cursor_pos_y() + 16.0) * context.widget.ui_scale);
 
-    context.widget.create_icon_button(
+    if context.widget.create_icon_button(
         context.ui,
         UI_ICON_SHUFFLE,
         16.0,
-        context.ui.style().color_alpha(ColorId::Text, 0.75),
+        if shuffle {
+            UI_ACCENT_COLOR
+        } else {
+            context.ui.style().color_alpha(ColorId::Text, 0.75)
+        },
         Color::TRANSPARENT,
         Color::TRANSPARENT,
         Color::TRANSPARENT,
         0.0,
-    );
+    ) {
+        context.widget.send_command(context.event_loop, AppCommand::ToggleShuffle);
+    }
     context.ui.same_line();
 
-    context.widget.create_icon_button(
+    if context.widget.create_icon_button(
         context.ui,
         UI_ICON_MEDIA_PREVIOUS,
         20.0,
@@ -107,23 +261,29 @@ fn build_media_controls(context: &mut ComponentContext) {
         context.ui.style().color_alpha(ColorId::Text, 0.1),
         context.ui.style().color_alpha(ColorId::Text, 0.15),
         100.0,
-    );
+    ) {
+        context.widget.send_command(context.event_loop, AppCommand::PreviousTrack);
+    }
 
     context.ui.same_line();
 
-    context.widget.create_icon_button(
-        context.ui,
-        UI_ICON_MEDIA_PAUSE,
-        20.0,
-        context.ui.style().color_alpha(ColorId::Text, 0.75),
-        Color::TRANSPARENT,
-        context.ui.style().color_alpha(ColorId::Text, 0.1),
-        context.ui.style().color_alpha(ColorId::Text, 0.15),
-        100.0,
-    );
+    context.ui.with_disabled(!context.playback.is_connected() && !controlling_remote, || {
+        if context.widget.create_icon_button(
+            context.ui,
+            if status.playing { UI_ICON_MEDIA_PAUSE } else { UI_ICON_MEDIA_PLAY },
+            20.0,
+            context.ui.style().color_alpha(ColorId::Text, 0.75),
+            Color::TRANSPARENT,
+            context.ui.style().color_alpha(ColorId::Text, 0.1),
+            context.ui.style().color_alpha(ColorId::Text, 0.15),
+            100.0,
+        ) {
+            context.widget.send_command(context.event_loop, AppCommand::PlayPause);
+        }
+    });
     context.ui.same_line();
 
-    context.widget.create_icon_button(
+    if context.widget.create_icon_button(
         context.ui,
         UI_ICON_MEDIA_NEXT,
         20.0,
@@ -132,29 +292,144 @@ fn build_media_controls(context: &mut ComponentContext) {
         context.ui.style().color_alpha(ColorId::Text, 0.1),
         context.ui.style().color_alpha(ColorId::Text, 0.15),
         100.0,
-    );
+    ) {
+        context.widget.send_command(context.event_loop, AppCommand::NextTrack);
+    }
     context.ui.same_line();
 
     context
         .ui
         .set_cursor_pos_y((context.ui.get_cursor_pos_y() + 2.0) * context.widget.ui_scale);
-    context.widget.create_icon_button(
+    if context.widget.create_icon_button(
         context.ui,
-        UI_ICON_REPEAT,
+        repeat_icon(repeat),
         16.0,
-        context.ui.style().color_alpha(ColorId::Text, 0.75),
+        if repeat == RepeatMode::Off {
+            context.ui.style().color_alpha(ColorId::Text, 0.75)
+        } else {
+            UI_ACCENT_COLOR
+        },
         Color::TRANSPARENT,
         Color::TRANSPARENT,
         Color::TRANSPARENT,
         0.0,
-    );
+    ) {
+        context.widget.send_command(context.event_loop, AppCommand::CycleRepeat);
+    }
     context.ui.same_line();
 }
 
+fn build_device_picker(context: &ComponentContext) {
+    let (devices, fetching, remote_device) = {
+        let state = context.widget.state.lock().unwrap();
+
+        (
+            state.devices.devices.clone(),
+            state.devices.fetching,
+            state.devices.remote_device.clone(),
+        )
+    };
+
+    if context.widget.create_icon_button(
+        context.ui,
+        UI_ICON_VOLUME,
+        16.0,
+        if remote_device.is_some() {
+            UI_ACCENT_COLOR
+        } else {
+            context.ui.style().color_alpha(ColorId::Text, 0.75)
+        },
+        Color::TRANSPARENT,
+        Color::TRANSPARENT,
+        Color::TRANSPARENT,
+        0.0,
+    ) {
+        context.ui.open_popup("###device_picker");
+        context.widget.send_command(context.event_loop, AppCommand::FetchDevices);
+    }
+
+    context.ui.popup_config("###device_picker").with(|| {
+        context.ui.with_push(context.widget.font_bold, || {
+            context.ui.text("Connect to a device");
+        });
+        context.ui.separator();
+
+        match &devices {
+            _ if fetching && devices.is_none() => context.ui.text("Looking for devices..."),
+            None => {},
+            Some(Err(_)) => context.ui.text("Couldn't load your devices, try again later."),
+            Some(Ok(devices)) if devices.is_empty() => {
+                context.ui.text("No devices found. Open Spotify on another device to see it here.");
+            },
+            Some(Ok(devices)) => {
+                for device in devices {
+                    let selected = match &remote_device {
+                        Some(remote_device) => remote_device.id == device.id,
+                        None => connect::is_local_device(device, &context.playback.device_id),
+                    };
+
+                    let label = format!(
+                        "{} ({:?})##device_{}",
+                        device.name,
+                        device._type,
+                        device.id.clone().unwrap_or_default()
+                    );
+
+                    let clicked = context.ui
+                        .selectable_config(&label)
+                        .selected(selected)
+                        .build();
+
+                    if let (true, Some(device_id)) = (clicked && !selected, &device.id) {
+                        context.widget.send_command(
+                            context.event_loop,
+                            AppCommand::TransferPlayback(device_id.clone())
+                        );
+                    }
+                }
+            },
+        }
+    });
+}
+
 fn build_playback_options(context: &ComponentContext) {
+    let status = now_playing_status(context);
+
     context.ui.table_next_column();
 
-    context.ui.progress_bar_config(0.75).build();
+    context.ui.progress_bar_config(status.progress()).build();
+
+    // Clicking anywhere on the progress bar seeks to that point in the track.
+    if status.track.is_some() && context.ui.is_item_clicked(MouseButton::Left) {
+        let bar_min = context.ui.get_item_rect_min();
+        let bar_size = context.ui.get_item_rect_size();
+
+        let fraction = ((context.ui.get_mouse_pos().x - bar_min.x) / bar_size.x).clamp(0.0, 1.0);
+        let position_ms = (status.duration_ms() as f32 * fraction) as u32;
+
+        context.widget.send_command(context.event_loop, AppCommand::Seek(position_ms));
+    }
+
+    context.ui.with_push(
+        (
+            context.widget.font_small,
+            (
+                ColorId::Text,
+                context.ui.style().color_alpha(ColorId::Text, 0.7),
+            ),
+        ),
+        || {
+            context.ui.text(&format!(
+                "{} / {}",
+                format_duration_ms(status.current_position_ms()),
+                format_duration_ms(status.duration_ms())
+            ));
+        },
+    );
+
+    context.ui.same_line();
+
+    build_device_picker(context);
 }
 
 pub fn build(context: &mut ComponentContext) {
@@ -207,9 +482,15 @@ pub fn build(context: &mut ComponentContext) {
 
             context
                 .ui
-                .table_config("player_layout", 6)
+                .table_config("player_layout", 7)
                 .flags(TableFlags::None)
                 .with(|| {
+                    context.ui.table_setup_column(
+                        "album_art",
+                        TableColumnFlags::WidthFixed,
+                        0.0,
+                        ImGuiID::default(),
+                    );
                     context.ui.table_setup_column(
                         "track_info",
                         TableColumnFlags::WidthFixed,
@@ -247,6 +528,9 @@ pub fn build(context: &mut ComponentContext) {
                         ImGuiID::default(),
                     );
 
+                    // Album Art
+                    build_album_art(context);
+
                     // Track Info
                     build_track_info(context);
                     context.ui.table_next_column();
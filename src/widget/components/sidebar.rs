@@ -2,17 +2,20 @@ use easy_imgui::{
     vec2, ColorId, Cond, DockNodeFlags, MouseButton, StyleValue, StyleVar, TreeNodeFlags, WindowFlags,
 };
 
+use rspotify_model::Id as _;
+
 use crate::{
+    api::models::{entity::SpotifyEntity, user::UserImpl as _},
     commands::AppCommand,
     constants::{
-        UI_ALBUM_ART_SIZE, UI_ROUTE_DEFAULT, UI_ROUTE_SEARCH,
+        UI_ACCENT_COLOR, UI_ROUTE_DEFAULT, UI_ROUTE_LIKED_SONGS, UI_ROUTE_QUEUE, UI_ROUTE_RECENTLY_PLAYED, UI_ROUTE_SEARCH, UI_ROUTE_YOUR_ALBUMS, UI_ROUTE_YOUR_ARTISTS, UI_ROUTE_YOUR_FILES,
         UI_SIDEBAR_WIDTH,
     },
     imgui_additions::{self, sidebar::ViewportSidebarDirection},
     widget::icons::{self},
 };
 
-use super::ComponentContext;
+use super::{async_image, player, ComponentContext};
 
 macro_rules! build_sidebar_item {
     ($ui: expr, $widget: expr, $label: expr, $icon: expr, $on_click: block) => {
@@ -51,7 +54,74 @@ macro_rules! build_sidebar_item {
     };
 }
 
-pub fn build(context: &ComponentContext) {
+fn build_playlists(context: &ComponentContext) {
+    let Some(data) = context.api.state() else {
+        return;
+    };
+
+    let Some(playlists) = data.playlists else {
+        context.ui.with_push(
+            (ColorId::Text, context.ui.style().color_alpha(ColorId::Text, 0.7)),
+            || {
+                context.ui.text("Loading...");
+            },
+        );
+
+        return;
+    };
+
+    let user_id = data.profile.map(|p| p.id);
+
+    for (index, playlist) in playlists.iter().enumerate() {
+        let owned = Some(&playlist.owner.id) == user_id.as_ref();
+
+        build_sidebar_item!(
+            context.ui,
+            context.widget,
+            &format!("{}##sidebar_playlist_{}", playlist.name, index),
+            if owned {
+                icons::set::UI_ICON_MUSICAL_NOTE
+            } else {
+                icons::set::UI_ICON_USER
+            },
+            {
+                context.widget.send_command(
+                    context.event_loop,
+                    AppCommand::Open(SpotifyEntity::Playlist {
+                        id: playlist.id.id().to_string(),
+                        name: playlist.name.clone(),
+                    }),
+                );
+            }
+        );
+
+        if playlist.collaborative {
+            let badge = "Collab";
+            let badge_size = context.ui.calc_text_size(badge);
+            let item_max = context.ui.get_item_rect_max();
+            let item_min = context.ui.get_item_rect_min();
+
+            context.ui.window_draw_list().add_text(
+                vec2(
+                    item_max.x - badge_size.x - context.ui.style().FramePadding.x,
+                    item_min.y + (item_max.y - item_min.y - badge_size.y) / 2.0,
+                ),
+                UI_ACCENT_COLOR,
+                badge,
+            );
+        }
+
+        if context.ui.is_item_hovered() {
+            context.ui.set_tooltip(&format!(
+                "{}\nBy {}",
+                playlist.name,
+                playlist.owner.name()
+            ));
+        }
+    }
+}
+
+pub fn build(context: &mut ComponentContext) {
     let viewport = context.ui.get_main_viewport();
     let viewport_size = viewport.work_size();
 
@@ -137,6 +207,18 @@ pub fn build(context: &ComponentContext) {
                                     );
                                 }
                             );
+                            build_sidebar_item!(
+                                context.ui,
+                                context.widget,
+                                "Queue",
+                                icons::set::UI_ICON_QUEUE,
+                                {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::Navigate(UI_ROUTE_QUEUE),
+                                    );
+                                }
+                            );
                             build_sidebar_item!(
                                 context.ui,
                                 context.widget,
@@ -174,35 +256,60 @@ pub fn build(context: &ComponentContext) {
                                 context.widget,
                                 "Liked Songs",
                                 icons::set::UI_ICON_HEART,
-                                {}
+                                {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::Navigate(UI_ROUTE_LIKED_SONGS),
+                                    );
+                                }
                             );
                             build_sidebar_item!(
                                 context.ui,
                                 context.widget,
                                 "Recently Played",
                                 icons::set::UI_ICON_CLOCK,
-                                {}
+                                {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::Navigate(UI_ROUTE_RECENTLY_PLAYED),
+                                    );
+                                }
                             );
                             build_sidebar_item!(
                                 context.ui,
                                 context.widget,
                                 "Your Artists",
                                 icons::set::UI_ICON_USER,
-                                {}
+                                {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::Navigate(UI_ROUTE_YOUR_ARTISTS),
+                                    );
+                                }
                             );
                             build_sidebar_item!(
                                 context.ui,
                                 context.widget,
                                 "Your Albums",
                                 icons::set::UI_ICON_DISC,
-                                {}
+                                {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::Navigate(UI_ROUTE_YOUR_ALBUMS),
+                                    );
+                                }
                             );
                             build_sidebar_item!(
                                 context.ui,
                                 context.widget,
                                 "Your Files",
                                 icons::set::UI_ICON_DOWNLOAD,
-                                {}
+                                {
+                                    context.widget.send_command(
+                                        context.event_loop,
+                                        AppCommand::Navigate(UI_ROUTE_YOUR_FILES),
+                                    );
+                                }
                             );
                         });
 
@@ -215,15 +322,7 @@ pub fn build(context: &ComponentContext) {
                                 | TreeNodeFlags::FramePadding,
                         )
                         .with(|| {
-                            for _ in 0..50 {
-                                build_sidebar_item!(
-                                    context.ui,
-                                    context.widget,
-                                    "Rediscover - Aug 9th",
-                                    icons::set::UI_ICON_MUSICAL_NOTE,
-                                    {}
-                                );
-                            }
+                            build_playlists(context);
                         });
                 });
 
@@ -264,16 +363,15 @@ pub fn build(context: &ComponentContext) {
                         )
                         .with(|| {
                             let display_size = context.ui.get_content_region_avail();
-                            let album_art_scale =
-                                display_size.x.min(display_size.y) / UI_ALBUM_ART_SIZE;
-
-                            context
-                                .ui
-                                .image_with_custom_rect_config(
-                                    context.widget.glyph_album_art,
-                                    album_art_scale,
-                                )
-                                .build();
+                            let album_art_size = display_size.x.min(display_size.y);
+
+                            let images = player::now_playing_images(context);
+
+                            async_image::build(
+                                context,
+                                async_image::pick_image(&images, album_art_size),
+                                album_art_size,
+                            );
                         });
                 });
 
@@ -0,0 +1,38 @@
+use rspotify_model::{FullTrack, Id as _};
+
+use crate::{api::models::entity::SpotifyEntity, commands::AppCommand};
+
+use super::ComponentContext;
+
+// Right-click menu for the last item, shared by every list of tracks.
+pub fn build(context: &ComponentContext, track: &FullTrack) {
+    context.ui.popup_context_item_config().with(|| {
+        if context.ui.menu_item_config("Play next").build() {
+            context.widget.send_command(
+                context.event_loop,
+                AppCommand::PlayNext(track.clone())
+            );
+        }
+
+        if context.ui.menu_item_config("Add to queue").build() {
+            context.widget.send_command(
+                context.event_loop,
+                AppCommand::AddToQueue(track.clone())
+            );
+        }
+
+        if let Some(album_id) = &track.album.id {
+            context.ui.separator();
+
+            if context.ui.menu_item_config("Go to album").build() {
+                context.widget.send_command(
+                    context.event_loop,
+                    AppCommand::Open(SpotifyEntity::Album {
+                        id: album_id.id().to_string(),
+                        name: track.album.name.clone(),
+                    })
+                );
+            }
+        }
+    });
+}
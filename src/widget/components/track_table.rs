@@ -0,0 +1,132 @@
+use easy_imgui::{ColorId, ImGuiID, SelectableFlags, TableColumnFlags, TableFlags, TableRowFlags};
+use rspotify_model::FullTrack;
+
+use crate::{
+    commands::AppCommand,
+    constants::UI_ACCENT_COLOR,
+    local,
+    state::queue::PlaybackContext,
+    utils::format_duration_ms,
+    widget::icons::set::UI_ICON_MEDIA_EXPLICIT,
+};
+
+use super::{track_menu, ComponentContext};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TrackTableColumns {
+    // Number rows by where they are on their album, rather than in the list.
+    pub track_number: bool,
+    pub album: bool,
+}
+
+// A table of tracks, clicking one plays `tracks` from there under `playback_context`.
+pub fn build(
+    context: &mut ComponentContext,
+    id: &str,
+    tracks: &[FullTrack],
+    playback_context: Option<PlaybackContext>,
+    columns: TrackTableColumns,
+) {
+    let playing_track = context.playback.status().track;
+
+    let column_count = if columns.album { 5 } else { 4 };
+
+    context
+        .ui
+        .table_config(id, column_count)
+        .flags(TableFlags::RowBg | TableFlags::PadOuterX)
+        .with(|| {
+            context.ui.table_setup_column("#", TableColumnFlags::WidthFixed, 0.0, ImGuiID::default());
+            context.ui.table_setup_column("Title", TableColumnFlags::WidthStretch, 2.0, ImGuiID::default());
+            context.ui.table_setup_column("Artists", TableColumnFlags::WidthStretch, 1.5, ImGuiID::default());
+
+            if columns.album {
+                context.ui.table_setup_column("Album", TableColumnFlags::WidthStretch, 1.5, ImGuiID::default());
+            }
+
+            context.ui.table_setup_column("Duration", TableColumnFlags::WidthFixed, 0.0, ImGuiID::default());
+
+            context.ui.table_headers_row();
+
+            // Only build the rows that are actually on screen, Liked Songs can run to thousands.
+            context.ui.list_clipper(tracks.len(), -1.0, &[], |index| {
+                let track = &tracks[index];
+                let playing = playing_track.as_ref().map(|t| local::is_same_track(t, track)).unwrap_or(false);
+
+                let text_color = if playing {
+                    UI_ACCENT_COLOR
+                } else {
+                    context.ui.style().color(ColorId::Text)
+                };
+
+                context.ui.table_next_row(TableRowFlags::None, 0.0);
+
+                context.ui.with_push((ColorId::Text, text_color), || {
+                    build_row(context, id, index, track, tracks, &playback_context, columns);
+                });
+            });
+        });
+}
+
+fn build_row(
+    context: &mut ComponentContext,
+    id: &str,
+    index: usize,
+    track: &FullTrack,
+    tracks: &[FullTrack],
+    playback_context: &Option<PlaybackContext>,
+    columns: TrackTableColumns,
+) {
+    let number = if columns.track_number {
+        track.track_number as usize
+    } else {
+        index + 1
+    };
+
+    context.ui.table_next_column();
+
+    let clicked = context.ui
+        .selectable_config(&format!("{}##{}_{}", number, id, index))
+        .flags(SelectableFlags::SpanAllColumns)
+        .build();
+
+    track_menu::build(context, track);
+
+    context.ui.table_next_column();
+
+    if track.explicit {
+        context.widget.create_icon(
+            context.ui,
+            UI_ICON_MEDIA_EXPLICIT,
+            14.0,
+            context.ui.style().color_alpha(ColorId::Text, 0.7),
+        );
+        context.ui.same_line();
+    }
+
+    context.ui.text(&track.name);
+
+    context.ui.table_next_column();
+    context.ui.text(
+        &track.artists
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+
+    if columns.album {
+        context.ui.table_next_column();
+        context.ui.text(&track.album.name);
+    }
+
+    context.ui.table_next_column();
+    context.ui.text(&format_duration_ms(track.duration.num_milliseconds().max(0) as u32));
+
+    if clicked {
+        context.widget.send_command(
+            context.event_loop,
+            AppCommand::PlayContext(playback_context.clone(), tracks.to_vec(), index)
+        );
+    }
+}
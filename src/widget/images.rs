@@ -0,0 +1,516 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use directories::ProjectDirs;
+use easy_imgui::{mint::Vector2, TextureId};
+use easy_imgui_renderer::{glow::{self, HasContext}, glr, Renderer};
+use easy_imgui_window::winit::event_loop::EventLoopProxy;
+use image::{imageops::FilterType, RgbaImage};
+use sha1::{Digest, Sha1};
+use tracing::{error, info, warn};
+
+use crate::{
+    constants::{
+        UI_APP_NAME, UI_ALBUM_ART_SIZE, UI_IMAGE_CACHE_MAX_BYTES, UI_IMAGE_DISK_CACHE_MAX_BYTES,
+        UI_IMAGE_RETRY_BASE_SECS, UI_IMAGE_RETRY_MAX_SECS,
+    },
+    event::AppEvent,
+};
+
+// Decoded images waiting for the UI thread to upload them.
+type ImageLoaderReady = Arc<Mutex<Vec<(String, Option<RgbaImage>)>>>;
+
+// Every downloaded image with its size and when it was last used.
+fn disk_entries(dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    fs::read_dir(dir)
+        .map(|entries| entries
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+
+                Some((entry.path(), metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)))
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+// Removes the least recently used images until the rest fit in `max_bytes`,
+// returning how much is left on disk.
+fn evict_disk(dir: &Path, max_bytes: u64) -> u64 {
+    let mut entries = disk_entries(dir);
+    entries.sort_by_key(|(_, _, used)| *used);
+
+    let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    let mut evicted = 0;
+
+    for (path, entry_size, _) in entries {
+        if size <= max_bytes {
+            break;
+        }
+
+        if fs::remove_file(&path).is_ok() {
+            size -= entry_size;
+            evicted += 1;
+        }
+    }
+
+    if evicted > 0 {
+        info!("Evicted {} images from the image cache.", evicted);
+    }
+
+    size
+}
+
+// Written beside the real file and renamed over it, so a download cut
+// short never leaves half an image behind to be read back next time.
+async fn write_entry(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let partial = path.with_extension("part");
+
+    tokio::fs::write(&partial, bytes).await?;
+
+    if let Err(err) = tokio::fs::rename(&partial, path).await {
+        tokio::fs::remove_file(&partial).await.ok();
+
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn decode(bytes: &[u8]) -> Option<RgbaImage> {
+    let image = image::load_from_memory(bytes).ok()?;
+
+    // Spotify's largest covers are 640px, nothing we draw needs that many.
+    let size = UI_ALBUM_ART_SIZE as u32;
+
+    Some(if image.width() > size || image.height() > size {
+        image.resize(size, size, FilterType::Triangle).to_rgba8()
+    } else {
+        image.to_rgba8()
+    })
+}
+
+// How long to wait before loading an image again after it's failed `attempts` times.
+fn retry_delay(attempts: u32) -> Duration {
+    let secs = UI_IMAGE_RETRY_BASE_SECS
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(UI_IMAGE_RETRY_MAX_SECS);
+
+    Duration::from_secs(secs)
+}
+
+/// Downloads and decodes images off the UI thread, keeping the
+/// downloaded files on disk so we only ever fetch a cover once.
+#[derive(Clone, Default)]
+pub struct ImageLoader {
+    client: reqwest::Client,
+    dir: Option<PathBuf>,
+
+    max_bytes: u64,
+    size_bytes: Arc<AtomicU64>,
+
+    // Held while evicting, so two downloads don't both sweep the directory.
+    lock: Arc<Mutex<()>>,
+
+    ready: ImageLoaderReady,
+
+    event_loop: Option<Arc<EventLoopProxy<AppEvent>>>,
+}
+
+impl ImageLoader {
+    pub fn new(event_loop: Option<Arc<EventLoopProxy<AppEvent>>>) -> Self {
+        // Linux: ~/.cache/spottyfi/images
+        let dir = ProjectDirs::from("com", UI_APP_NAME, UI_APP_NAME)
+            .map(|d| d.cache_dir().join("images"))
+            .filter(|dir| fs::create_dir_all(dir).is_ok());
+
+        let size_bytes = dir
+            .as_ref()
+            .map(|dir| disk_entries(dir).iter().map(|(_, size, _)| size).sum())
+            .unwrap_or_default();
+
+        ImageLoader {
+            client: reqwest::Client::new(),
+            dir,
+
+            max_bytes: UI_IMAGE_DISK_CACHE_MAX_BYTES,
+            size_bytes: Arc::new(AtomicU64::new(size_bytes)),
+
+            lock: Arc::new(Mutex::new(())),
+
+            ready: Arc::new(Mutex::new(Vec::new())),
+
+            event_loop,
+        }
+    }
+
+    fn path(&self, url: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(hex::encode(Sha1::digest(url.as_bytes()))))
+    }
+
+    async fn fetch(&self, url: &str) -> Option<Vec<u8>> {
+        let path = self.path(url);
+
+        if let Some(path) = &path {
+            if let Ok(bytes) = tokio::fs::read(path).await {
+                let path = path.clone();
+
+                // Bump the modified time, it's what eviction goes by.
+                tokio::task::spawn_blocking(move || {
+                    File::options()
+                        .append(true)
+                        .open(&path)
+                        .and_then(|file| file.set_modified(SystemTime::now()))
+                        .ok();
+                });
+
+                return Some(bytes);
+            }
+        }
+
+        let bytes = match self.client.get(url).send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => response.bytes().await.ok()?.to_vec(),
+            Err(err) => {
+                warn!("Failed to download image {}: {:#?}", url, err);
+
+                return None;
+            }
+        };
+
+        if let Some(path) = path {
+            match write_entry(&path, &bytes).await {
+                Ok(_) => self.stored(bytes.len() as u64),
+                Err(err) => warn!("Failed to write image cache entry {}: {:#?}", path.display(), err),
+            }
+        }
+
+        Some(bytes)
+    }
+
+    // Counts a newly written image, evicting the oldest once we're over the limit.
+    fn stored(&self, bytes: u64) {
+        let size = self.size_bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+
+        let (Some(dir), true) = (self.dir.clone(), size > self.max_bytes) else {
+            return;
+        };
+
+        let loader = self.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let Ok(_lock) = loader.lock.try_lock() else {
+                return;
+            };
+
+            loader.size_bytes.store(evict_disk(&dir, loader.max_bytes), Ordering::Relaxed);
+        });
+    }
+
+    // Drops a cached file that won't decode, or we'd keep reading it back
+    // instead of downloading the image again.
+    async fn forget(&self, url: &str, bytes: u64) {
+        let Some(path) = self.path(url) else {
+            return;
+        };
+
+        if tokio::fs::remove_file(&path).await.is_ok() {
+            warn!("Removed undecodable image cache entry {}.", path.display());
+
+            self.size_bytes
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |size| Some(size.saturating_sub(bytes)))
+                .ok();
+        }
+    }
+
+    pub fn load(&self, url: String) {
+        let loader = self.clone();
+
+        tokio::task::spawn(async move {
+            let image = match loader.fetch(&url).await {
+                Some(bytes) => {
+                    let len = bytes.len() as u64;
+
+                    let image = tokio::task::spawn_blocking(move || decode(&bytes))
+                        .await
+                        .ok()
+                        .flatten();
+
+                    if image.is_none() {
+                        loader.forget(&url, len).await;
+                    }
+
+                    image
+                },
+                None => None,
+            };
+
+            loader.ready.lock().unwrap().push((url, image));
+
+            if let Some(event_loop) = &loader.event_loop {
+                event_loop.send_event(AppEvent::ImagesLoaded).ok();
+            }
+        });
+    }
+
+    fn take_ready(&self) -> Vec<(String, Option<RgbaImage>)> {
+        std::mem::take(&mut *self.ready.lock().unwrap())
+    }
+}
+
+struct ImageTexture {
+    texture: glr::Texture,
+    id: TextureId,
+    size: Vector2<f32>,
+    bytes: usize,
+
+    // Frame this was last drawn in, for evicting the least recently used.
+    last_used: u64,
+}
+
+/// Textures for images from the web, e.g. album art and playlist covers.
+/// Lives on the UI thread, which is the only one allowed to touch GL.
+#[derive(Default)]
+pub struct ImageManager {
+    loader: ImageLoader,
+
+    textures: HashMap<String, ImageTexture>,
+    loading: HashSet<String>,
+    // Images that failed, with how many times and when to try again,
+    // so we don't keep retrying them every frame.
+    failed: HashMap<String, (u32, Instant)>,
+
+    frame: u64,
+}
+
+impl ImageManager {
+    pub fn new(event_loop: Option<Arc<EventLoopProxy<AppEvent>>>) -> Self {
+        ImageManager {
+            loader: ImageLoader::new(event_loop),
+
+            ..Default::default()
+        }
+    }
+
+    /// Returns the texture for `url`, or starts loading it and returns None
+    /// so the caller can draw a placeholder in the meantime.
+    pub fn get(&mut self, url: &str) -> Option<(TextureId, Vector2<f32>)> {
+        if let Some(texture) = self.textures.get_mut(url) {
+            texture.last_used = self.frame;
+
+            return Some((texture.id, texture.size));
+        }
+
+        let waiting = self.failed
+            .get(url)
+            .is_some_and(|(_, retry_at)| Instant::now() < *retry_at);
+
+        if !self.loading.contains(url) && !waiting {
+            self.loading.insert(url.to_string());
+            self.loader.load(url.to_string());
+        }
+
+        None
+    }
+
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Uploads everything the loader has finished decoding.
+    pub fn upload_ready(&mut self, gl: &glr::GlContext) {
+        for (url, image) in self.loader.take_ready() {
+            self.loading.remove(&url);
+
+            let Some(image) = image else {
+                self.fail(url);
+                continue;
+            };
+
+            match upload_texture(gl, &image) {
+                Ok((texture, id)) => {
+                    self.failed.remove(&url);
+
+                    self.textures.insert(url, ImageTexture {
+                        texture,
+                        id,
+                        size: Vector2 { x: image.width() as f32, y: image.height() as f32 },
+                        bytes: image.as_raw().len(),
+                        last_used: self.frame,
+                    });
+                },
+                Err(err) => {
+                    error!("Failed to upload image texture for {}: {:#?}", url, err);
+
+                    self.fail(url);
+                }
+            }
+        }
+
+        self.evict();
+    }
+
+    fn fail(&mut self, url: String) {
+        let attempts = self.failed.get(&url).map(|(attempts, _)| attempts + 1).unwrap_or(1);
+
+        self.failed.insert(url, (attempts, Instant::now() + retry_delay(attempts)));
+    }
+
+    fn evict(&mut self) {
+        let mut total: usize = self.textures.values().map(|t| t.bytes).sum();
+
+        if total <= UI_IMAGE_CACHE_MAX_BYTES {
+            return;
+        }
+
+        let mut by_last_used = self.textures
+            .iter()
+            .map(|(url, t)| (url.clone(), t.last_used))
+            .collect::<Vec<(String, u64)>>();
+
+        by_last_used.sort_by_key(|(_, last_used)| *last_used);
+
+        for (url, last_used) in by_last_used {
+            // Never evict something drawn this frame, it'd just be loaded again.
+            if total <= UI_IMAGE_CACHE_MAX_BYTES || last_used == self.frame {
+                break;
+            }
+
+            if let Some(texture) = self.textures.remove(&url) {
+                Renderer::unmap_tex(texture.id);
+                total -= texture.bytes;
+
+                drop(texture.texture);
+            }
+        }
+    }
+}
+
+fn upload_texture(gl: &glr::GlContext, image: &RgbaImage) -> Result<(glr::Texture, TextureId), glr::GLError> {
+    let texture = glr::Texture::generate(gl)?;
+
+    unsafe {
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture.id()));
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::LINEAR as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::LINEAR as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            glow::RGBA as i32,
+            image.width() as i32,
+            image.height() as i32,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            Some(image.as_raw()),
+        );
+        gl.bind_texture(glow::TEXTURE_2D, None);
+    }
+
+    let id = Renderer::map_tex(texture.id());
+
+    Ok((texture, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_retries() {
+        assert_eq!(retry_delay(1), Duration::from_secs(UI_IMAGE_RETRY_BASE_SECS));
+        assert_eq!(retry_delay(2), Duration::from_secs(UI_IMAGE_RETRY_BASE_SECS * 2));
+        assert_eq!(retry_delay(3), Duration::from_secs(UI_IMAGE_RETRY_BASE_SECS * 4));
+        assert_eq!(retry_delay(100), Duration::from_secs(UI_IMAGE_RETRY_MAX_SECS));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_images() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for (index, name) in ["oldest", "older", "newest"].iter().enumerate() {
+            let path = dir.path().join(name);
+
+            fs::write(&path, vec![0u8; 100]).unwrap();
+
+            File::options()
+                .append(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(UNIX_EPOCH + Duration::from_secs(index as u64 * 60)))
+                .unwrap();
+        }
+
+        assert_eq!(evict_disk(dir.path(), 300), 300);
+        assert_eq!(evict_disk(dir.path(), 250), 200);
+
+        assert!(!dir.path().join("oldest").exists());
+        assert!(dir.path().join("older").exists());
+        assert!(dir.path().join("newest").exists());
+
+        assert_eq!(evict_disk(dir.path(), 0), 0);
+        assert!(disk_entries(dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn replaces_entries_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cover");
+
+        write_entry(&path, b"first").await.unwrap();
+        write_entry(&path, b"second").await.unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(disk_entries(dir.path()).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn forgets_entries_that_wont_decode() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let loader = ImageLoader {
+            dir: Some(dir.path().to_path_buf()),
+            size_bytes: Arc::new(AtomicU64::new(100)),
+            ..Default::default()
+        };
+
+        let url = "https://i.scdn.co/image/broken".to_string();
+        let path = loader.path(&url).unwrap();
+
+        fs::write(&path, b"not an image").unwrap();
+
+        loader.load(url.clone());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        let ready = loop {
+            let ready = loader.take_ready();
+
+            if !ready.is_empty() {
+                break ready;
+            }
+
+            assert!(Instant::now() < deadline, "Timed out waiting for the image");
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].0, url);
+        assert!(ready[0].1.is_none());
+
+        assert!(!path.exists());
+        assert_eq!(loader.size_bytes.load(Ordering::Relaxed), 100 - b"not an image".len() as u64);
+    }
+}
@@ -25,6 +25,8 @@ use icons::{IconOffset, IconsManager};
 use image::GenericImage;
 use image::{load_from_memory, GenericImageView};
 use num::clamp;
+use accounts::AccountsManager;
+use images::ImageManager;
 use preferences::{Preferences, PreferencesManager};
 use theme::UITheme;
 use tokio::{runtime::Handle, sync::Mutex};
@@ -32,8 +34,8 @@ use tracing::{debug, error, info, warn};
 
 use crate::{
     api::{SpotifyAPI, SpotifyAPIState}, commands::AppCommand, constants::{
-        self, UI_ALBUM_ART_SIZE, UI_DARK_CHROME_BG_COLOR, UI_DEFAULT_LOCALE, UI_DEFAULT_SCALE, UI_ICONS_BASE_SIZE, UI_LIGHT_CHROME_BG_COLOR, UI_MAX_SCALE, UI_MIN_SCALE, UI_ROUTE_DEFAULT, UI_ROUTE_PREFERENCES, UI_ROUTE_SEARCH
-    }, event::{AppEvent, AppFetchType}, state::State, App
+        self, UI_ALBUM_ART_SIZE, UI_DARK_CHROME_BG_COLOR, UI_DEFAULT_LOCALE, UI_DEFAULT_SCALE, UI_ICONS_BASE_SIZE, UI_LIGHT_CHROME_BG_COLOR, UI_MAX_SCALE, UI_MIN_SCALE, UI_ROUTE_DEFAULT, UI_ROUTE_LIKED_SONGS, UI_ROUTE_PREFERENCES, UI_ROUTE_QUEUE, UI_ROUTE_RECENTLY_PLAYED, UI_ROUTE_SEARCH, UI_ROUTE_YOUR_ALBUMS, UI_ROUTE_YOUR_ARTISTS, UI_ROUTE_YOUR_FILES
+    }, event::{AppEvent, AppFetchType}, playback::SpotifyPlayback, state::{album::WidgetStateAlbum, artist::WidgetStateArtist, playlist::WidgetStatePlaylist, State}, App
 };
 
 mod flex;
@@ -43,6 +45,8 @@ mod fonts;
 pub mod components;
 pub mod icons;
 pub mod preferences;
+pub mod accounts;
+pub mod images;
 pub mod style;
 pub mod theme;
 pub mod actions;
@@ -74,6 +78,8 @@ pub struct Widget {
     pub state: Arc<std::sync::Mutex<State>>,
 
     pub preferences: PreferencesManager,
+    pub accounts: AccountsManager,
+    pub images: ImageManager,
 
     viewport_dockspace: ImGuiID,
 
@@ -87,6 +93,9 @@ impl Widget {
         let mut preferences = PreferencesManager::new();
         preferences.read_preferences();
 
+        let accounts = AccountsManager::new(preferences.config_dir());
+        preferences.set_overrides(accounts.current().and_then(|a| a.overrides.clone()));
+
         Widget {
             ui_scale: constants::UI_DEFAULT_SCALE,
 
@@ -96,6 +105,7 @@ impl Widget {
             icons: IconsManager::new(),
             flex: FlexEngine::new(),
             preferences,
+            accounts,
 
             viewport_dockspace: ImGuiID::default(),
 
@@ -116,6 +126,8 @@ impl Widget {
 
         self.set_ui_scale(event_loop, zoom_level);
 
+        self.images = ImageManager::new(Some(Arc::new(event_loop.clone())));
+
         event_loop.send_event(AppEvent::SetInitialWindowState).ok();
 
         event_loop.send_event(AppEvent::Fetch(AppFetchType::All)).ok();
@@ -201,15 +213,19 @@ impl Widget {
         &mut self,
         event_loop: &EventLoopProxy<AppEvent>,
         ui: &imgui::Ui<App>,
-        api: Arc<SpotifyAPI>
+        api: Arc<SpotifyAPI>,
+        playback: Arc<SpotifyPlayback>
     ) {
         let mut context = ComponentContext {
             widget: self,
             event_loop,
             ui,
-            api
+            api,
+            playback
         };
 
+        context.widget.images.next_frame();
+
         let current_search_value = context.widget.state
             .lock()
             .unwrap()
@@ -332,6 +348,12 @@ impl Widget {
             if ui.is_keychord_pressed(preferences_chord) {
                 self.send_command(event_loop, AppCommand::Navigate(UI_ROUTE_PREFERENCES));
             }
+
+            let filter_chord = KeyChord::new(KeyMod::Ctrl, Key::F);
+
+            if ui.is_keychord_pressed(filter_chord) {
+                self.send_command(event_loop, AppCommand::Filter);
+            }
         }
     }
 
@@ -431,9 +453,18 @@ impl Widget {
             .unwrap_or(UITheme::System)
     }
 
+    /// Saves a change made in the Preferences pane. While someone's logged in it
+    /// goes in their account's overrides, otherwise straight into preferences.toml.
+    pub fn set_account_preferences(&mut self, prefs: Preferences) {
+        match self.accounts.set_overrides(prefs.clone()) {
+            Some(overrides) => self.preferences.set_overrides(Some(overrides)),
+            None => { self.preferences.set(prefs); },
+        }
+    }
+
     pub fn set_theme(&mut self, theme: UITheme, store: bool) {
         if store {
-            self.preferences.set(Preferences {
+            self.set_account_preferences(Preferences {
                 theme: Some(theme),
                 ..Default::default()
             });
@@ -460,16 +491,120 @@ impl Widget {
         match route {
             UI_ROUTE_DEFAULT => self.state.lock().unwrap().home_visible = true,
             UI_ROUTE_SEARCH => self.state.lock().unwrap().search.visible = true,
+            UI_ROUTE_QUEUE => self.state.lock().unwrap().queue_visible = true,
+            UI_ROUTE_LIKED_SONGS => self.state.lock().unwrap().liked_songs.visible = true,
+            UI_ROUTE_RECENTLY_PLAYED => self.state.lock().unwrap().recently_played.visible = true,
+            UI_ROUTE_YOUR_ARTISTS => self.state.lock().unwrap().your_artists.visible = true,
+            UI_ROUTE_YOUR_ALBUMS => self.state.lock().unwrap().your_albums.visible = true,
+            UI_ROUTE_YOUR_FILES => self.state.lock().unwrap().local_files.visible = true,
 
             UI_ROUTE_PREFERENCES => self.state.lock().unwrap().preferences.visible = true,
             _ => warn!("No application route matching '{}'", route),
         }
 
+        self.focus_window(route);
+    }
+
+    pub fn focus_window(&self, title: &str) {
         unsafe {
-            let c_str = CString::new(route).expect("Failed to cast route to C string");
+            let c_str = CString::new(title).expect("Failed to cast window title to C string");
             let c_str_ptr = c_str.as_ptr();
 
             ImGui_SetWindowFocus1(c_str_ptr);
         }
     }
+
+    /// Brings up the pane for album `id`, opening one if needed.
+    /// Returns whether the album still has to be fetched.
+    pub fn open_album(&mut self, id: String, name: String) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(pane) = state.albums.iter_mut().find(|a| a.id == id) {
+            let title = pane.title();
+
+            // Give it another go if it failed last time.
+            let refetch = matches!(pane.album, Some(Err(_)));
+
+            if refetch {
+                pane.album = None;
+            }
+
+            drop(state);
+            self.focus_window(&title);
+
+            return refetch;
+        }
+
+        state.albums.push(WidgetStateAlbum::new(id, name));
+
+        true
+    }
+
+    /// Same as `open_album`, for artists.
+    pub fn open_artist(&mut self, id: String, name: String) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(pane) = state.artists.iter_mut().find(|a| a.id == id) {
+            let title = pane.title();
+
+            let refetch = matches!(pane.artist, Some(Err(_)));
+
+            if refetch {
+                pane.artist = None;
+            }
+
+            drop(state);
+            self.focus_window(&title);
+
+            return refetch;
+        }
+
+        state.artists.push(WidgetStateArtist::new(id, name));
+
+        true
+    }
+
+    /// Same as `open_album`, for playlists.
+    pub fn open_playlist(&mut self, id: String, name: String) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(pane) = state.playlists.iter_mut().find(|p| p.id == id) {
+            let title = pane.title();
+
+            let refetch = matches!(pane.playlist, Some(Err(_)));
+
+            if refetch {
+                *pane = WidgetStatePlaylist::new(id, name);
+            }
+
+            drop(state);
+            self.focus_window(&title);
+
+            return refetch;
+        }
+
+        state.playlists.push(WidgetStatePlaylist::new(id, name));
+
+        true
+    }
+
+    /// Jumps to the filter box of the playlist pane used last.
+    pub fn focus_filter(&mut self) {
+        let mut state = self.state.lock().unwrap();
+
+        let focused_playlist = state.focused_playlist.clone();
+
+        let pane = state.playlists
+            .iter_mut()
+            .find(|p| Some(&p.id) == focused_playlist.as_ref());
+
+        if let Some(pane) = pane {
+            pane.focus_filter = true;
+
+            let title = pane.title();
+
+            drop(state);
+            self.focus_window(&title);
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use std::{
-    default, fs::{create_dir_all, exists, File, OpenOptions}, io::{Read, Write}, path::PathBuf, time::Duration
+    default, env, fs::{create_dir_all, exists, File, OpenOptions}, io::{Read, Write}, path::PathBuf, time::Duration
 };
 
 use directories::ProjectDirs;
@@ -7,7 +7,10 @@ use merge_struct::merge;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
-use crate::constants::{UI_APP_NAME, UI_DEFAULT_LOCALE};
+use crate::{
+    api::providers::oauth2::{SpotifyAPIOAuthConfig, SpotifyAPIOAuthPortFallback},
+    constants::{UI_APP_NAME, UI_DEFAULT_LOCALE},
+};
 
 use super::{
     components::player::{PlayerArea, PlayerPosition},
@@ -24,7 +27,11 @@ pub struct Preferences {
     pub theme: Option<UITheme>,
 
     pub credentials: Option<PreferencesCredentials>,
+    pub oauth: Option<PreferencesOAuth>,
     pub player_bar: Option<PreferencesPlayerBar>,
+    pub audio: Option<PreferencesAudio>,
+    pub cache: Option<PreferencesCache>,
+    pub local_files: Option<PreferencesLocalFiles>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
@@ -33,6 +40,46 @@ pub struct PreferencesPlayerBar {
     pub position: Option<super::components::player::PlayerPosition>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PreferencesAudio {
+    pub backend: Option<String>,
+    pub device: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PreferencesCache {
+    pub max_size_mb: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PreferencesLocalFiles {
+    // Folders scanned for Your Files, joined like PATH. One string rather
+    // than a list so saving replaces it instead of merging into it.
+    pub folders: Option<String>,
+}
+
+impl PreferencesLocalFiles {
+    /// Fails for a folder with the separator in its name, ':' or ';'.
+    pub fn from_folders(folders: &[PathBuf]) -> Result<Self, env::JoinPathsError> {
+        let folders = env::join_paths(folders)?;
+
+        Ok(PreferencesLocalFiles {
+            folders: Some(folders.to_string_lossy().to_string()),
+        })
+    }
+
+    pub fn folders(&self) -> Vec<PathBuf> {
+        self.folders
+            .as_ref()
+            .map(|folders| {
+                env::split_paths(folders)
+                    .filter(|f| !f.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Default, Debug)]
 pub struct PreferencesWindowState {
     pub x: Option<u32>,
@@ -47,12 +94,52 @@ pub struct PreferencesCredentials {
     pub secret: Option<String>,
 }
 
+// For logging in through your own registered Spotify app.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PreferencesOAuth {
+    pub client_id: Option<String>,
+    pub redirect_host: Option<String>,
+    pub redirect_port: Option<u16>,
+    pub redirect_port_fallback: Option<SpotifyAPIOAuthPortFallback>,
+}
+
+impl From<PreferencesOAuth> for SpotifyAPIOAuthConfig {
+    fn from(prefs: PreferencesOAuth) -> Self {
+        let default = SpotifyAPIOAuthConfig::default();
+
+        SpotifyAPIOAuthConfig {
+            client_id: prefs.client_id
+                .filter(|id| !id.trim().is_empty())
+                .unwrap_or(default.client_id),
+            redirect_host: prefs.redirect_host
+                .filter(|host| !host.trim().is_empty())
+                .unwrap_or(default.redirect_host),
+            redirect_port: prefs.redirect_port.unwrap_or(default.redirect_port),
+            port_fallback: prefs.redirect_port_fallback.unwrap_or(default.port_fallback),
+        }
+    }
+}
+
+impl From<SpotifyAPIOAuthConfig> for PreferencesOAuth {
+    fn from(config: SpotifyAPIOAuthConfig) -> Self {
+        PreferencesOAuth {
+            client_id: Some(config.client_id),
+            redirect_host: Some(config.redirect_host),
+            redirect_port: Some(config.redirect_port),
+            redirect_port_fallback: Some(config.port_fallback),
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct PreferencesManager {
     config_dir: Option<PathBuf>,
 
     user_prefs: Option<Preferences>,
     data: Option<Preferences>,
+
+    // The current account's overrides, layered on top when reading.
+    overrides: Option<Preferences>,
 }
 
 impl PreferencesManager {
@@ -64,6 +151,7 @@ impl PreferencesManager {
 
             window_state: None,
             credentials: None,
+            oauth: None,
 
             player_bar: Some(PreferencesPlayerBar {
                 position: Some(PlayerPosition::Bottom),
@@ -71,6 +159,10 @@ impl PreferencesManager {
 
                 ..Default::default()
             }),
+
+            audio: None,
+            cache: None,
+            local_files: None,
         }
     }
 
@@ -93,6 +185,8 @@ impl PreferencesManager {
 
             user_prefs: None,
             data: None,
+
+            overrides: None,
         }
     }
 
@@ -121,6 +215,10 @@ impl PreferencesManager {
         }
     }
 
+    pub fn config_dir(&self) -> Option<PathBuf> {
+        self.config_dir.clone()
+    }
+
     pub fn get_prefs_path(&self) -> Option<PathBuf> {
         self.config_dir
             .clone().map(|d| d.join("preferences.toml"))
@@ -312,7 +410,32 @@ impl PreferencesManager {
     }
 
     pub fn get(&self) -> Option<Preferences> {
-        self.data.clone()
+        match (&self.data, &self.overrides) {
+            (Some(data), Some(overrides)) => match merge(data, overrides) {
+                Ok(prefs) => Some(prefs),
+                Err(err) => {
+                    error!("Failed to merge account preference overrides: {:#?}", err);
+
+                    Some(data.clone())
+                }
+            },
+            (data, _) => data.clone(),
+        }
+    }
+
+    pub fn set_overrides(&mut self, overrides: Option<Preferences>) {
+        self.overrides = overrides;
+    }
+
+    /// Takes the plaintext login older versions kept out of preferences.toml.
+    /// `set` merges, which can only ever add keys, so this writes without it.
+    pub fn remove_credentials(&mut self) -> Option<Preferences> {
+        self.read_preferences();
+
+        let mut prefs = self.data.clone().unwrap_or(self.default_prefs());
+        prefs.credentials = None;
+
+        self.write_preferences(prefs)
     }
 
     pub fn set(&mut self, new_prefs: Preferences) -> Option<Preferences> {
@@ -337,3 +460,37 @@ impl PreferencesManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn removes_credentials_and_keeps_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+
+        fs::write(
+            dir.path().join("preferences.toml"),
+            "zoom_level = 1.5\n\n[credentials]\nsecret = \"refresh-token\"\n"
+        ).unwrap();
+
+        let mut preferences = PreferencesManager {
+            config_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        preferences.read_preferences();
+
+        assert!(preferences.get().and_then(|p| p.credentials).and_then(|c| c.secret).is_some());
+
+        preferences.remove_credentials();
+
+        let contents = fs::read_to_string(dir.path().join("preferences.toml")).unwrap();
+
+        assert!(!contents.contains("secret"));
+        assert!(!contents.contains("[credentials]"));
+        assert_eq!(preferences.get().and_then(|p| p.zoom_level), Some(1.5));
+    }
+}